@@ -8,6 +8,8 @@
 #   ./loop.sh plan [max_iterations]    - Run planning mode (gap analysis only)
 #   ./loop.sh verify [max_iterations]  - Run verify mode (visual testing & compliance)
 #   ./loop.sh build [max_iterations]   - Run build mode (implementation only - LEGACY)
+#   ./loop.sh --profile NAME ...       - Any of the above with project profile NAME
+#                                        (ralph/profiles/NAME.toml)
 #
 # Examples:
 #   ./loop.sh            # Run unified mode indefinitely (RECOMMENDED)
@@ -15,6 +17,44 @@
 #   ./loop.sh plan 5     # Run 5 planning iterations
 #   ./loop.sh verify 3   # Run 3 verification iterations
 #   ./loop.sh build 10   # Run 10 build-only iterations (legacy)
+#   ./loop.sh --profile artwork-audit 10   # Run 10 unified iterations on that profile
+#
+# Environment:
+#   RALPH_PROFILE   Project profile, like --profile (default: project.profile
+#                   in the config, else its [project] section)
+#   RALPH_PROMISES  Completion promises that stop the loop, as TAG=EXIT_CODE
+#                   (default: the project's unified, build and verify promises
+#                   with exit codes 10, 11 and 12)
+#   RALPH_ITERATION_TIMEOUT  Wall-clock seconds per iteration, 0 = none (default: 3600)
+#   RALPH_STALL_TIMEOUT      Seconds without output before an iteration is
+#                            considered stalled, 0 = none (default: 600)
+#   RALPH_TIMEOUT_POLICY     continue | abort after a timeout or stall (default: continue)
+#   RALPH_MAX_FAILURES       Consecutive failed iterations before the loop halts,
+#                            0 = never (default: 3)
+#   RALPH_MAX_SPINS          Consecutive iterations that move no plan task before
+#                            the loop halts, 0 = never (default: 3)
+#   RALPH_BUDGET_USD         Dollar budget; the loop stops once exceeded, 0 = none (default: 0)
+#   RALPH_BUDGET_TOKENS      Token budget (input, output and cache), 0 = none (default: 0)
+#   RALPH_BUDGET_SCOPE       run | branch: measure the budget against this run or
+#                            every run on the branch (default: run)
+#   RALPH_CONFIG             Settings file: model per mode, fallback models,
+#                            push policy, worktrees, Archon (default: ralph/ralph.toml)
+#   RALPH_PUSH_POLICY        Override [push] policy: never | after-commit | at-end |
+#                            on-promise
+#   RALPH_WORKTREE           true | false: run each iteration in a scratch worktree
+#                            (default: [worktree] enabled in the config)
+#   RALPH_WORKERS            Iterations to run in parallel, each on its own ready
+#                            plan task and worktree (default: 1)
+#   RALPH_ARCHON             true | false: sync the plan with the Archon project
+#                            before every iteration and move started and finished
+#                            tasks there (default: [archon] enabled)
+#   RALPH_GATE               true | false: after every unified or build round, run
+#                            the tests and roll the round back when the suite
+#                            regressed (default: [gate] enabled in the config)
+#   RALPH_JUNIT              true | false: collect the JUnit XML reports of each
+#                            iteration's test runs into logs/tests_N.json
+#                            (default: [junit] enabled in the config)
+#   RALPH_BIN       Path to the ralph helper (built on first run if missing)
 
 set -e
 
@@ -23,6 +63,88 @@ PROJECT_ROOT="$(dirname "$SCRIPT_DIR")"
 
 cd "$PROJECT_ROOT"
 
+# Project profile: a leading --profile NAME overrides RALPH_PROFILE
+case "$1" in
+    --profile)
+        RALPH_PROFILE=$2
+        shift 2
+        ;;
+    --profile=*)
+        RALPH_PROFILE=${1#--profile=}
+        shift
+        ;;
+esac
+
+RALPH_ITERATION_TIMEOUT=${RALPH_ITERATION_TIMEOUT:-3600}
+RALPH_STALL_TIMEOUT=${RALPH_STALL_TIMEOUT:-600}
+RALPH_TIMEOUT_POLICY=${RALPH_TIMEOUT_POLICY:-continue}
+RALPH_MAX_FAILURES=${RALPH_MAX_FAILURES:-3}
+RALPH_MAX_SPINS=${RALPH_MAX_SPINS:-3}
+RALPH_BUDGET_USD=${RALPH_BUDGET_USD:-0}
+RALPH_BUDGET_TOKENS=${RALPH_BUDGET_TOKENS:-0}
+RALPH_BUDGET_SCOPE=${RALPH_BUDGET_SCOPE:-run}
+RALPH_CONFIG=${RALPH_CONFIG:-"$SCRIPT_DIR/ralph.toml"}
+
+PLAN_FILE="$SCRIPT_DIR/IMPLEMENTATION_PLAN.md"
+PLAN_SNAPSHOT="$SCRIPT_DIR/logs/plan_snapshot.json"
+BREAKER_STATE="$SCRIPT_DIR/logs/breaker.json"
+LEDGER_FILE="$SCRIPT_DIR/logs/ledger.json"
+MODEL_STATE="$SCRIPT_DIR/logs/model.json"
+GATE_STATE="$SCRIPT_DIR/logs/gate.json"
+CLAIM_DIR="$SCRIPT_DIR/logs/claims"
+
+# Helper binary for parsing plans and logs
+RALPH_BIN=${RALPH_BIN:-"$SCRIPT_DIR/target/release/ralph"}
+if [ ! -x "$RALPH_BIN" ]; then
+    echo "Building ralph helper..."
+    cargo build --release --quiet --manifest-path "$SCRIPT_DIR/Cargo.toml"
+fi
+# Absolute, so the prompts' {{ ralph }} commands also work in a scratch worktree
+RALPH_BIN="$(cd "$(dirname "$RALPH_BIN")" && pwd)/$(basename "$RALPH_BIN")"
+
+PROFILE_ARGS=(${RALPH_PROFILE:+--profile "$RALPH_PROFILE"})
+if [ -z "$RALPH_PROMISES" ]; then
+    for entry in unified=10 build=11 verify=12; do
+        promise=$("$RALPH_BIN" config --file "$RALPH_CONFIG" "${PROFILE_ARGS[@]}" \
+            get "project.promises.${entry%=*}")
+        if [ -n "$promise" ]; then
+            RALPH_PROMISES+="${RALPH_PROMISES:+ }$promise=${entry#*=}"
+        fi
+    done
+fi
+
+RALPH_WORKTREE=${RALPH_WORKTREE:-$("$RALPH_BIN" config --file "$RALPH_CONFIG" get worktree.enabled)}
+RALPH_WORKERS=${RALPH_WORKERS:-1}
+RALPH_ARCHON=${RALPH_ARCHON:-$("$RALPH_BIN" config --file "$RALPH_CONFIG" get archon.enabled)}
+RALPH_GATE=${RALPH_GATE:-$("$RALPH_BIN" config --file "$RALPH_CONFIG" get gate.enabled)}
+RALPH_JUNIT=${RALPH_JUNIT:-$("$RALPH_BIN" config --file "$RALPH_CONFIG" get junit.enabled)}
+if [ "$RALPH_WORKERS" -gt 1 ] && [ "$RALPH_WORKTREE" != true ]; then
+    echo "RALPH_WORKERS=$RALPH_WORKERS: running every worker in its own scratch worktree"
+    RALPH_WORKTREE=true
+fi
+
+# In unified and build mode (and with parallel workers) the loop picks each
+# iteration's task and marks it from the outcome; the prompt only works on it
+OWN_TASKS=false
+if [ "$RALPH_WORKERS" -gt 1 ]; then
+    OWN_TASKS=true
+fi
+TASK_ARGS=(--plan "$PLAN_FILE" --config "$RALPH_CONFIG" --claims "$CLAIM_DIR")
+if [ "$RALPH_ARCHON" = true ]; then
+    TASK_ARGS+=(--archon)
+fi
+
+PROMISE_ARGS=()
+for promise in $RALPH_PROMISES; do
+    PROMISE_ARGS+=(--promise "$promise")
+done
+
+mkdir -p "$SCRIPT_DIR/logs"
+"$RALPH_BIN" breaker --state "$BREAKER_STATE" reset
+if [ "$RALPH_WORKTREE" = true ]; then
+    "$RALPH_BIN" worktree --config "$RALPH_CONFIG" clean
+fi
+
 # Determine mode and max iterations
 if [ "$1" = "plan" ]; then
     MODE="plan"
@@ -39,29 +161,166 @@ elif [ "$1" = "build" ]; then
     MODE="build"
     PROMPT_FILE="$SCRIPT_DIR/PROMPT_build.md"
     MAX_ITERATIONS=${2:-0}  # 0 = infinite
+    OWN_TASKS=true
     echo "Running in BUILD mode (legacy - consider using unified mode)"
 elif [[ "$1" =~ ^[0-9]+$ ]]; then
     # Number = unified mode with max iterations
     MODE="unified"
     PROMPT_FILE="$SCRIPT_DIR/PROMPT_unified.md"
     MAX_ITERATIONS=$1
+    OWN_TASKS=true
     echo "Running in UNIFIED mode (implement + test + verify) with max $MAX_ITERATIONS iterations"
 else
     # Default = unified mode indefinitely
     MODE="unified"
     PROMPT_FILE="$SCRIPT_DIR/PROMPT_unified.md"
     MAX_ITERATIONS=0  # infinite
+    OWN_TASKS=true
     echo "Running in UNIFIED mode (implement + test + verify) indefinitely"
 fi
 
+# Planning and verify mode do not work on a task, so a worktree verdict (a
+# task moved to [x], tests passing) would discard every iteration: they run
+# a single worker in the project tree
+if [ "$MODE" = "plan" ] || [ "$MODE" = "verify" ]; then
+    if [ "$RALPH_WORKERS" -gt 1 ]; then
+        echo "RALPH_WORKERS=$RALPH_WORKERS: $MODE mode runs a single worker"
+        RALPH_WORKERS=1
+        OWN_TASKS=false
+    fi
+    RALPH_WORKTREE=false
+fi
+
+# Model for this mode from the config; switches to a fallback after throttling
+MODEL=$("$RALPH_BIN" model --config "$RALPH_CONFIG" --state "$MODEL_STATE" --mode "$MODE" --reset)
+echo "Model: $MODEL"
+
 # Get current branch
 CURRENT_BRANCH=$(git branch --show-current)
 echo "Working on branch: $CURRENT_BRANCH"
 
+# Fail fast on a template error (unknown variable, missing include) rather
+# than in every iteration
+if ! "$RALPH_BIN" prompt "$PROMPT_FILE" --config "$RALPH_CONFIG" "${PROFILE_ARGS[@]}" --var ralph="$RALPH_BIN" \
+    --plan "$PLAN_FILE" --mode "$MODE" --iteration 0 --branch "$CURRENT_BRANCH" --model "$MODEL" > /dev/null; then
+    echo "Cannot render $PROMPT_FILE"
+    exit 1
+fi
+
+# Test gate baseline: what the suite looks like before the first round.
+# Planning and verify mode do not change code, so they are not gated.
+if [ "$MODE" = "plan" ] || [ "$MODE" = "verify" ]; then
+    RALPH_GATE=false
+fi
+GATE_ARGS=(--config "$RALPH_CONFIG" "${PROFILE_ARGS[@]}" --state "$GATE_STATE")
+if [ "$RALPH_GATE" = true ]; then
+    echo "Running the test gate baseline..."
+    "$RALPH_BIN" gate "${GATE_ARGS[@]}" baseline --output "$SCRIPT_DIR/logs/gate_0.txt"
+fi
+
+# Push according to [push] in the config; a failed push is reported, not fatal
+push_commits() {
+    local moment=$1
+    shift
+    "$RALPH_BIN" push --config "$RALPH_CONFIG" --moment "$moment" \
+        --branch "$CURRENT_BRANCH" ${RALPH_PUSH_POLICY:+--policy "$RALPH_PUSH_POLICY"} "$@" \
+        || echo "Push failed; commits stay local (see logs/iteration_N.log)"
+}
+
+# Prompt for one iteration: the mode's template rendered with the iteration's
+# variables, plus the task the loop assigned to it
+iteration_prompt() {
+    local iteration=$1 task=${2:-}
+    "$RALPH_BIN" prompt "$PROMPT_FILE" --config "$RALPH_CONFIG" "${PROFILE_ARGS[@]}" --var ralph="$RALPH_BIN" \
+        --plan "$PLAN_FILE" --mode "$MODE" --iteration "$iteration" --branch "$CURRENT_BRANCH" \
+        --model "$MODEL" ${task:+--task "$task"}
+    if [ -n "$task" ]; then
+        local parallel=()
+        if [ "$RALPH_WORKERS" -gt 1 ]; then
+            parallel=(--parallel)
+        fi
+        "$RALPH_BIN" task --plan "$PLAN_FILE" show "$task" "${parallel[@]}"
+    fi
+}
+
+# Point the test runners of an iteration at its JUnit report directory:
+# pytest through PYTEST_ADDOPTS, jest-junit through JEST_JUNIT_OUTPUT_DIR, and
+# anything else through RALPH_JUNIT_DIR (e.g. vitest's --outputFile)
+junit_env() {
+    local dir=$1
+    if [ "$RALPH_JUNIT" = true ]; then
+        rm -rf "$dir"
+        mkdir -p "$dir"
+        export RALPH_JUNIT_DIR="$dir"
+        export PYTEST_ADDOPTS="${PYTEST_ADDOPTS:+$PYTEST_ADDOPTS }--junitxml='$dir/pytest.xml'"
+        export JEST_JUNIT_OUTPUT_DIR="$dir"
+    fi
+}
+
+# One iteration: a fresh claude session, optionally on a single task and in a
+# scratch worktree. Returns claude's exit status; everything else is in the log.
+run_iteration() {
+    local iteration=$1 task=${2:-} title=${3:-}
+    local log="$SCRIPT_DIR/logs/iteration_${iteration}.log"
+    local workdir="$PROJECT_ROOT" status head
+    local junit_dir="$SCRIPT_DIR/logs/junit_${iteration}"
+    : > "$log"
+    "$RALPH_BIN" record --log "$log" iteration_start \
+        iteration="$iteration" run="\"$RUN_ID\"" mode="$MODE" model="$MODEL" branch="$CURRENT_BRANCH" \
+        started_at="$(date +%s)" ${task:+task="\"$task\""}
+
+    # Optionally isolate the iteration in a scratch worktree off the branch.
+    # Checked explicitly: called from an `||` list, the function runs without
+    # `set -e`, and the model must never fall back to the real branch.
+    if [ "$RALPH_WORKTREE" = true ]; then
+        if ! workdir=$("$RALPH_BIN" worktree --config "$RALPH_CONFIG" create \
+            --iteration "$iteration" --base "$CURRENT_BRANCH"); then
+            echo "Could not create a scratch worktree for iteration $iteration; skipping it"
+            "$RALPH_BIN" record --log "$log" iteration_end ended_at="$(date +%s)" exit_code=1
+            return 1
+        fi
+        echo "Working in scratch worktree: $workdir"
+    fi
+    head=$(git -C "$workdir" rev-parse HEAD 2>/dev/null || echo none)
+
+    # Run Claude with fresh context each time
+    # The prompt file contains all instructions - progress is in IMPLEMENTATION_PLAN.md
+    # The supervisor tees output into the log and kills the whole process
+    # tree on timeout (exit 124) or when output stalls (exit 125)
+    set +e
+    (cd "$workdir" && junit_env "$junit_dir" && iteration_prompt "$iteration" "$task" | "$RALPH_BIN" supervise \
+        --log "$log" \
+        --timeout "$RALPH_ITERATION_TIMEOUT" \
+        --stall "$RALPH_STALL_TIMEOUT" \
+        -- claude -p \
+        --dangerously-skip-permissions \
+        --output-format=stream-json \
+        --model "$MODEL" \
+        --verbose)
+    status=$?
+    set -e
+    "$RALPH_BIN" record --log "$log" iteration_end \
+        ended_at="$(date +%s)" exit_code="$status" \
+        head_before="\"$head\"" head_after="\"$(git -C "$workdir" rev-parse HEAD 2>/dev/null || echo none)\""
+
+    # Per-test results of the iteration's test runs, compared with the last
+    # known outcome of each test in earlier iterations
+    if [ "$RALPH_JUNIT" = true ]; then
+        "$RALPH_BIN" junit ingest --iteration "$iteration" --run "$RUN_ID" --log "$log" \
+            --logs "$SCRIPT_DIR/logs" "$junit_dir" || echo "Could not read the JUnit reports in $junit_dir"
+    fi
+    return $status
+}
+
 ITERATION=0
+SPINS=0
+ROUND_LOGS=()
+EXIT_CODE=0
 START_TIME=$(date +%s)
+RUN_ID="$(date -u -d "@$START_TIME" +%Y%m%dT%H%M%SZ 2>/dev/null || echo "$START_TIME")-$$"
 
-# Main loop - each iteration is a FRESH context
+# Main loop - each iteration is a FRESH context. With RALPH_WORKERS > 1 each
+# pass is a round of parallel iterations, one per claimed task.
 while true; do
     ITERATION=$((ITERATION + 1))
     ITER_START=$(date +%s)
@@ -70,6 +329,7 @@ while true; do
     echo "============================================"
     echo "ITERATION $ITERATION - $(date '+%Y-%m-%d %H:%M:%S')"
     echo "Mode: $MODE"
+    echo "Model: $MODEL"
     echo "============================================"
 
     # Check max iterations
@@ -78,36 +338,269 @@ while true; do
         break
     fi
 
-    # Run Claude with fresh context each time
-    # The prompt file contains all instructions - progress is in IMPLEMENTATION_PLAN.md
-    cat "$PROMPT_FILE" | claude -p \
-        --dangerously-skip-permissions \
-        --output-format=stream-json \
-        --model opus \
-        --verbose \
-        2>&1 | tee "$SCRIPT_DIR/logs/iteration_${ITERATION}.log" || {
-            echo "Claude exited with error, continuing..."
-        }
+    # Archon is the source of truth for task status: pull it into the plan
+    # (and create new plan tasks in Archon) before the iteration reads it
+    if [ "$RALPH_ARCHON" = true ]; then
+        "$RALPH_BIN" archon --config "$RALPH_CONFIG" sync --plan "$PLAN_FILE" --commit \
+            || echo "Archon sync failed or found conflicts; continuing"
+    fi
 
-    # Auto-push changes after each iteration
-    if git diff --quiet && git diff --staged --quiet; then
-        echo "No changes to push"
+    # Snapshot before tasks are started, so progress is measured against
+    # the checkboxes as they were before the round (moves to [>] and [!]
+    # are the loop's own bookkeeping and do not count as progress)
+    "$RALPH_BIN" plan --file "$PLAN_FILE" snapshot --out "$PLAN_SNAPSHOT"
+
+    # Start up to one ready task per worker, highest priority first: claim
+    # it (the lock files keep two workers, or two loops, off the same task)
+    # and mark it doing. Worktrees branch off a commit, so then the plan
+    # change is committed first.
+    CLAIMED=""
+    if [ "$OWN_TASKS" = true ]; then
+        COUNT=$RALPH_WORKERS
+        if [ $MAX_ITERATIONS -gt 0 ] && [ $((MAX_ITERATIONS - ITERATION + 1)) -lt $COUNT ]; then
+            COUNT=$((MAX_ITERATIONS - ITERATION + 1))
+        fi
+        START_ARGS=()
+        if [ "$RALPH_WORKTREE" = true ]; then
+            START_ARGS=(--commit)
+        fi
+        if ! CLAIMED=$("$RALPH_BIN" task "${TASK_ARGS[@]}" "${START_ARGS[@]}" start \
+            --count "$COUNT" --owner "$RUN_ID" --pid $$); then
+            if "$RALPH_BIN" plan --file "$PLAN_FILE" next --ignore-deps > /dev/null; then
+                echo "No task is ready: open tasks are blocked, claimed or waiting on dependencies"
+            else
+                echo "No open tasks left in the plan"
+            fi
+            ITERATION=$((ITERATION - 1))
+            break
+        fi
+    fi
+
+    HEAD_BEFORE=$(git rev-parse HEAD 2>/dev/null || echo none)
+
+    ITERATIONS=()
+    TASKS=()
+    if [ "$RALPH_WORKERS" -gt 1 ]; then
+        PIDS=()
+        while IFS=$'\t' read -r TASK TITLE; do
+            N=$((ITERATION + ${#ITERATIONS[@]}))
+            ITERATIONS+=("$N")
+            TASKS+=("$TASK")
+            echo "Worker $N: $TASK $TITLE"
+            run_iteration "$N" "$TASK" "$TITLE" > /dev/null &
+            PIDS+=($!)
+        done <<< "$CLAIMED"
+        ITERATION=$((ITERATION + ${#ITERATIONS[@]} - 1))
+        CLAUDE_STATUS=0
+        for i in "${!PIDS[@]}"; do
+            STATUS=0
+            wait "${PIDS[$i]}" || STATUS=$?
+            echo "Worker ${ITERATIONS[$i]} (task ${TASKS[$i]}) finished with exit code $STATUS"
+            if [ "$STATUS" -ne 0 ]; then
+                CLAUDE_STATUS=$STATUS
+            fi
+        done
     else
-        echo "Pushing changes..."
-        git push origin "$CURRENT_BRANCH" 2>/dev/null || {
-            echo "Creating remote branch..."
-            git push -u origin "$CURRENT_BRANCH"
-        }
+        IFS=$'\t' read -r TASK TITLE <<< "$CLAIMED" || true
+        ITERATIONS=("$ITERATION")
+        TASKS=("$TASK")
+        if [ -n "$TASK" ]; then
+            echo "Task: $TASK $TITLE"
+        fi
+        CLAUDE_STATUS=0
+        run_iteration "$ITERATION" "$TASK" "$TITLE" || CLAUDE_STATUS=$?
     fi
 
+    # Per iteration of the round: model fallback, merge-back, spend, promise
+    PROMISE=""
+    OVER_BUDGET=false
+    for i in "${!ITERATIONS[@]}"; do
+        ITER_LOG="$SCRIPT_DIR/logs/iteration_${ITERATIONS[$i]}.log"
+
+        # Rate-limited or overloaded? Move down the fallback chain
+        MODEL=$("$RALPH_BIN" model --config "$RALPH_CONFIG" --state "$MODEL_STATE" \
+            --mode "$MODE" --log "$ITER_LOG")
+
+        # Land the scratch branch only if tests passed and the iteration did
+        # work on its task (or, without one, moved a task to [x]). An assigned
+        # task need not be ticked: `task finish` below marks it from what
+        # landed. Merges of parallel workers are serialized by a lock
+        if [ "$RALPH_WORKTREE" = true ]; then
+            "$RALPH_BIN" worktree --config "$RALPH_CONFIG" finish \
+                --iteration "${ITERATIONS[$i]}" \
+                --into "$CURRENT_BRANCH" \
+                --log "$ITER_LOG" \
+                --since "$PLAN_SNAPSHOT" \
+                --plan "${PLAN_FILE#"$PROJECT_ROOT"/}" \
+                ${TASKS[$i]:+--task "${TASKS[$i]}"} || true
+        fi
+
+        # Add the iteration's usage and cost to the ledger and check the budget
+        # (exit 5); any other failure leaves the iteration unaccounted
+        LEDGER_STATUS=0
+        "$RALPH_BIN" ledger --file "$LEDGER_FILE" add \
+            --log "$ITER_LOG" \
+            --run "$RUN_ID" \
+            --branch "$CURRENT_BRANCH" \
+            --iteration "${ITERATIONS[$i]}" \
+            --max-usd "$RALPH_BUDGET_USD" \
+            --max-tokens "$RALPH_BUDGET_TOKENS" \
+            --scope "$RALPH_BUDGET_SCOPE" || LEDGER_STATUS=$?
+        if [ "$LEDGER_STATUS" -eq 5 ]; then
+            OVER_BUDGET=true
+        elif [ "$LEDGER_STATUS" -ne 0 ]; then
+            echo "Could not add iteration ${ITERATIONS[$i]} to the ledger (exit $LEDGER_STATUS)"
+        fi
+
+        # Stop as soon as the model emits one of the completion promises
+        if [ -z "$PROMISE" ]; then
+            PROMISE_CODE=0
+            PROMISE=$("$RALPH_BIN" promise "$ITER_LOG" "${PROMISE_ARGS[@]}") || PROMISE_CODE=$?
+        fi
+    done
+
+    # Run the tests ourselves, whatever the model claimed: a round that made
+    # the suite worse than the previous one is reverted or quarantined, and
+    # its completion promise does not count
+    GATE_STATUS=0
+    if [ "$RALPH_GATE" = true ]; then
+        GATE_LOGS=()
+        for n in "${ITERATIONS[@]}"; do
+            GATE_LOGS+=(--log "$SCRIPT_DIR/logs/iteration_$n.log")
+        done
+        "$RALPH_BIN" gate "${GATE_ARGS[@]}" run \
+            --iteration "${ITERATIONS[0]}" \
+            --head "$HEAD_BEFORE" \
+            --branch "$CURRENT_BRANCH" \
+            --output "$SCRIPT_DIR/logs/gate_${ITERATIONS[0]}.txt" \
+            "${GATE_LOGS[@]}" || GATE_STATUS=$?
+        if [ "$GATE_STATUS" -eq 8 ]; then
+            PROMISE=""
+        elif [ "$GATE_STATUS" -ne 0 ]; then
+            echo "Test gate could not run (exit $GATE_STATUS)"
+        fi
+    fi
+
+    # Commits of the round itself; the task bookkeeping below commits too
+    HEAD_AFTER=$(git rev-parse HEAD 2>/dev/null || echo none)
+
+    # Mark each task from what landed and the test run, then release it
+    for i in "${!ITERATIONS[@]}"; do
+        if [ -n "${TASKS[$i]}" ]; then
+            "$RALPH_BIN" task "${TASK_ARGS[@]}" --commit finish "${TASKS[$i]}" \
+                --log "$SCRIPT_DIR/logs/iteration_${ITERATIONS[$i]}.log" \
+                --head "$HEAD_BEFORE" \
+                --since "$PLAN_SNAPSHOT" || echo "Could not mark task ${TASKS[$i]}"
+        fi
+    done
+
+    case $CLAUDE_STATUS in
+        0) ;;
+        124|125)
+            if [ "$CLAUDE_STATUS" -eq 124 ]; then
+                echo "Iteration $ITERATION timed out after ${RALPH_ITERATION_TIMEOUT}s"
+            else
+                echo "Iteration $ITERATION stalled (no output for ${RALPH_STALL_TIMEOUT}s)"
+            fi
+            if [ "$RALPH_TIMEOUT_POLICY" = "abort" ]; then
+                echo "Aborting (RALPH_TIMEOUT_POLICY=abort)"
+                EXIT_CODE=$CLAUDE_STATUS
+                break
+            fi
+            echo "Continuing with next iteration..."
+            ;;
+        *)
+            echo "Claude exited with error, continuing..."
+            ;;
+    esac
+
+    # Push new commits if the push policy says so (never to protected
+    # branches), recording it in the log of every iteration of the round
+    ROUND_LOGS=()
+    for n in "${ITERATIONS[@]}"; do
+        ROUND_LOGS+=(--log "$SCRIPT_DIR/logs/iteration_$n.log")
+    done
+    push_commits iteration "${ROUND_LOGS[@]}"
+
     ITER_END=$(date +%s)
     ITER_DURATION=$((ITER_END - ITER_START))
     echo "Iteration $ITERATION completed in ${ITER_DURATION}s"
 
+    if [ -n "$PROMISE" ]; then
+        echo "Completion promise received: $PROMISE"
+        push_commits promise "${ROUND_LOGS[@]}"
+        EXIT_CODE=$PROMISE_CODE
+        break
+    fi
+
+    if [ "$OVER_BUDGET" = true ]; then
+        echo "Stopping: $RALPH_BUDGET_SCOPE budget exceeded (see logs/ledger.json)"
+        EXIT_CODE=5
+        break
+    fi
+
+    # Plan progress: which tasks moved, and did anything besides the plan
+    # and the loop's own state (logs, archive, Archon store) change?
+    set +e
+    "$RALPH_BIN" plan --file "$PLAN_FILE" progress \
+        --since "$PLAN_SNAPSHOT" \
+        --changed-since "$HEAD_BEFORE" \
+        --state-dir "$SCRIPT_DIR" \
+        --log "$ITER_LOG"
+    PROGRESS_STATUS=$?
+    set -e
+
+    # Circuit breaker: halt after too many failed iterations in a row
+    FAILURES=()
+    if [ "$CLAUDE_STATUS" -ne 0 ]; then
+        FAILURES+=(--failure exit)
+    fi
+    if [ "$GATE_STATUS" -eq 8 ]; then
+        FAILURES+=(--failure tests)
+    fi
+    # Verify mode only reports, so commits and plan edits are not expected
+    if [ "$MODE" != "verify" ]; then
+        if [ "$HEAD_AFTER" = "$HEAD_BEFORE" ]; then
+            FAILURES+=(--failure no-commit)
+        fi
+        if [ "$PROGRESS_STATUS" -ne 0 ]; then
+            FAILURES+=(--failure no-progress)
+        fi
+    fi
+    set +e
+    "$RALPH_BIN" breaker --state "$BREAKER_STATE" record \
+        --iteration "$ITERATION" \
+        --threshold "$RALPH_MAX_FAILURES" \
+        --detail "exit $CLAUDE_STATUS" \
+        "${FAILURES[@]}"
+    BREAKER_STATUS=$?
+    set -e
+    if [ "$BREAKER_STATUS" -eq 3 ]; then
+        echo "Circuit breaker tripped after $RALPH_MAX_FAILURES consecutive failed iterations."
+        echo "Fix the problem manually, then restart the loop. Details: logs/iteration_N.log"
+        EXIT_CODE=3
+        break
+    fi
+
+    # Spin detection: stop when iterations keep moving no plan task
+    if [ "$MODE" != "verify" ] && [ "$PROGRESS_STATUS" -ne 0 ]; then
+        SPINS=$((SPINS + 1))
+        echo "No plan task moved ($SPINS consecutive)"
+        if [ "$RALPH_MAX_SPINS" -gt 0 ] && [ "$SPINS" -ge "$RALPH_MAX_SPINS" ]; then
+            echo "Stopping: $SPINS iterations in a row without plan progress"
+            EXIT_CODE=4
+            break
+        fi
+    else
+        SPINS=0
+    fi
+
     # Brief pause between iterations
     sleep 2
 done
 
+push_commits end "${ROUND_LOGS[@]}"
+
 END_TIME=$(date +%s)
 TOTAL_DURATION=$((END_TIME - START_TIME))
 echo ""
@@ -115,3 +608,8 @@ echo "============================================"
 echo "Loop completed after $ITERATION iterations"
 echo "Total time: ${TOTAL_DURATION}s"
 echo "============================================"
+echo ""
+"$RALPH_BIN" report --logs "$SCRIPT_DIR/logs" || true
+"$RALPH_BIN" ledger --file "$LEDGER_FILE" show --run "$RUN_ID" --branch "$CURRENT_BRANCH" || true
+
+exit $EXIT_CODE
@@ -0,0 +1,357 @@
+//! MCP over stdio: the server for the local task store, and a client for
+//! talking to any Archon-compatible server the same way.
+//!
+//! Messages are JSON-RPC 2.0, one per line, which is the transport Claude
+//! uses for a `command` MCP server. The server offers only the tools
+//! capability; every tool call is answered with the JSON response of
+//! [`super::call`] as text, and failures set `isError` the way Archon reports
+//! them (`success: false` with an `error` message).
+
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde_json::{json, Value};
+
+use crate::error::{Error, Result};
+
+/// Protocol revision answered when the client does not name one.
+const PROTOCOL_VERSION: &str = "2024-11-05";
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// Answer requests from `input` until it is closed.
+pub fn serve(store: &Path, input: impl BufRead, mut output: impl Write) -> Result<()> {
+    let stdout = |e| Error::io("<stdout>", e);
+    for line in input.lines() {
+        let line = line.map_err(|e| Error::io("<stdin>", e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match serde_json::from_str::<Value>(&line) {
+            Ok(message) => handle(store, &message),
+            Err(e) => Some(error(Value::Null, PARSE_ERROR, &e.to_string())),
+        };
+        if let Some(response) = response {
+            writeln!(output, "{response}").map_err(stdout)?;
+            output.flush().map_err(stdout)?;
+        }
+    }
+    Ok(())
+}
+
+/// Response to one message; notifications get none.
+pub fn handle(store: &Path, message: &Value) -> Option<Value> {
+    let Some(method) = message.get("method").and_then(Value::as_str) else {
+        let id = message.get("id").cloned().unwrap_or(Value::Null);
+        return Some(error(id, INVALID_REQUEST, "expected a JSON-RPC request"));
+    };
+    let id = message.get("id")?.clone();
+    let params = message.get("params").cloned().unwrap_or(Value::Null);
+
+    let result = match method {
+        "initialize" => json!({
+            "protocolVersion": params
+                .get("protocolVersion")
+                .and_then(Value::as_str)
+                .unwrap_or(PROTOCOL_VERSION),
+            "capabilities": { "tools": {} },
+            "serverInfo": { "name": "archon", "version": env!("CARGO_PKG_VERSION") },
+        }),
+        "ping" => json!({}),
+        "tools/list" => json!({ "tools": tools() }),
+        "tools/call" => {
+            let Some(name) = params.get("name").and_then(Value::as_str) else {
+                return Some(error(id, INVALID_PARAMS, "tools/call needs a tool name"));
+            };
+            let arguments = params.get("arguments").cloned().unwrap_or(Value::Null);
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default();
+            let (response, failed) = match super::call(store, name, arguments, now) {
+                Ok(response) => (response, false),
+                Err(e) => (json!({ "success": false, "error": e.to_string() }), true),
+            };
+            json!({
+                "content": [{ "type": "text", "text": response.to_string() }],
+                "isError": failed,
+            })
+        }
+        other => {
+            return Some(error(
+                id,
+                METHOD_NOT_FOUND,
+                &format!("unknown method `{other}`"),
+            ))
+        }
+    };
+    Some(json!({ "jsonrpc": "2.0", "id": id, "result": result }))
+}
+
+fn error(id: Value, code: i64, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+/// Tool descriptions with the argument schemas Archon uses.
+pub fn tools() -> Value {
+    let status = json!({ "type": "string", "enum": ["todo", "doing", "review", "done"] });
+    let action = json!({ "type": "string", "enum": ["create", "update", "delete"] });
+    json!([
+        {
+            "name": "find_projects",
+            "description": "List projects, search them by title or description, or get one by ID.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "project_id": { "type": "string", "description": "Return this project only" },
+                    "query": { "type": "string", "description": "Case-insensitive text search" },
+                },
+            },
+        },
+        {
+            "name": "manage_project",
+            "description": "Create, update or delete a project. Deleting a project deletes its tasks.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "action": action,
+                    "project_id": { "type": "string", "description": "Required for update and delete" },
+                    "title": { "type": "string", "description": "Required for create" },
+                    "description": { "type": "string" },
+                },
+                "required": ["action"],
+            },
+        },
+        {
+            "name": "find_tasks",
+            "description": "List tasks by priority (highest task_order first), search or filter them, or get one by ID.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "task_id": { "type": "string", "description": "Return this task only" },
+                    "query": { "type": "string", "description": "Case-insensitive text search of title, description and feature" },
+                    "filter_by": { "type": "string", "enum": ["status", "project", "assignee"] },
+                    "filter_value": { "type": "string", "description": "Value for filter_by" },
+                    "project_id": { "type": "string", "description": "Only tasks of this project" },
+                    "include_closed": { "type": "boolean", "description": "Include done tasks (default true)" },
+                },
+            },
+        },
+        {
+            "name": "manage_task",
+            "description": "Create, update or delete a task. Status moves todo -> doing -> review -> done.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "action": action,
+                    "task_id": { "type": "string", "description": "Required for update and delete" },
+                    "project_id": { "type": "string", "description": "Required for create" },
+                    "title": { "type": "string", "description": "Required for create" },
+                    "description": { "type": "string" },
+                    "status": status,
+                    "assignee": { "type": "string", "description": "Default: User" },
+                    "task_order": { "type": "integer", "description": "Priority; higher goes first" },
+                    "feature": { "type": "string", "description": "Feature label for grouping" },
+                },
+                "required": ["action"],
+            },
+        },
+    ])
+}
+
+/// Client for an MCP server started as a child process, e.g. a bridge such as
+/// `npx mcp-remote http://localhost:8051/mcp` to a real Archon instance.
+pub struct Client {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    next_id: u64,
+}
+
+impl Client {
+    /// Start `command` and complete the initialize handshake.
+    pub fn spawn(command: &[String]) -> Result<Client> {
+        let (program, args) = command
+            .split_first()
+            .ok_or_else(|| Error::Archon("empty MCP server command".to_string()))?;
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|e| Error::io(program, e))?;
+        let stdin = child.stdin.take().expect("stdin is piped");
+        let stdout = BufReader::new(child.stdout.take().expect("stdout is piped"));
+        let mut client = Client {
+            child,
+            stdin,
+            stdout,
+            next_id: 0,
+        };
+        client.request(
+            "initialize",
+            json!({
+                "protocolVersion": PROTOCOL_VERSION,
+                "capabilities": {},
+                "clientInfo": { "name": "ralph", "version": env!("CARGO_PKG_VERSION") },
+            }),
+        )?;
+        client.send(&json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }))?;
+        Ok(client)
+    }
+
+    /// Call `tool` and decode the JSON text it answers with.
+    pub fn call(&mut self, tool: &str, arguments: Value) -> Result<Value> {
+        let result = self.request(
+            "tools/call",
+            json!({ "name": tool, "arguments": arguments }),
+        )?;
+        let text = result["content"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|c| c["text"].as_str())
+            .collect::<String>();
+        let response: Value = serde_json::from_str(&text).unwrap_or(Value::String(text));
+        if result["isError"].as_bool() == Some(true) || response["success"] == json!(false) {
+            let message = response["error"].as_str().map(str::to_string);
+            return Err(Error::Archon(format!(
+                "{tool}: {}",
+                message.unwrap_or_else(|| response.to_string())
+            )));
+        }
+        Ok(response)
+    }
+
+    fn request(&mut self, method: &str, params: Value) -> Result<Value> {
+        self.next_id += 1;
+        let id = self.next_id;
+        self.send(&json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }))?;
+        loop {
+            let mut line = String::new();
+            let read = self
+                .stdout
+                .read_line(&mut line)
+                .map_err(|e| Error::io("<mcp server>", e))?;
+            if read == 0 {
+                return Err(Error::Archon(format!(
+                    "MCP server exited before answering {method}"
+                )));
+            }
+            // Skip notifications and log lines the server interleaves.
+            let Ok(message) = serde_json::from_str::<Value>(&line) else {
+                continue;
+            };
+            if message["id"] != json!(id) {
+                continue;
+            }
+            if let Some(error) = message.get("error") {
+                return Err(Error::Archon(format!(
+                    "{method}: {}",
+                    error["message"].as_str().unwrap_or("MCP error")
+                )));
+            }
+            return Ok(message["result"].clone());
+        }
+    }
+
+    fn send(&mut self, message: &Value) -> Result<()> {
+        writeln!(self.stdin, "{message}")
+            .and_then(|()| self.stdin.flush())
+            .map_err(|e| Error::io("<mcp server>", e))
+    }
+}
+
+impl Drop for Client {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    fn request(id: u64, method: &str, params: Value) -> Value {
+        json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params })
+    }
+
+    #[test]
+    fn answers_the_handshake_and_lists_tools() {
+        let store = Path::new("unused.json");
+        let init = handle(store, &request(1, "initialize", json!({}))).unwrap();
+        assert_eq!(init["id"], 1);
+        assert_eq!(init["result"]["protocolVersion"], PROTOCOL_VERSION);
+        let initialized = json!({ "jsonrpc": "2.0", "method": "notifications/initialized" });
+        assert_eq!(handle(store, &initialized), None);
+
+        let list = handle(store, &request(2, "tools/list", Value::Null)).unwrap();
+        let names: Vec<&str> = list["result"]["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|tool| tool["name"].as_str())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "find_projects",
+                "manage_project",
+                "find_tasks",
+                "manage_task"
+            ]
+        );
+        let unknown = handle(store, &request(3, "resources/list", Value::Null)).unwrap();
+        assert_eq!(unknown["error"]["code"], METHOD_NOT_FOUND);
+    }
+
+    #[test]
+    fn tool_calls_reach_the_store() {
+        let dir = TempDir::new("mcp");
+        let store = dir.join("archon.json");
+        let input = [
+            "not json".to_string(),
+            request(
+                1,
+                "tools/call",
+                json!({ "name": "manage_project", "arguments": { "action": "create", "title": "Audit" } }),
+            )
+            .to_string(),
+            String::new(),
+            request(
+                2,
+                "tools/call",
+                json!({ "name": "manage_task", "arguments": { "action": "delete", "task_id": "task-9" } }),
+            )
+            .to_string(),
+        ]
+        .join("\n");
+        let mut output = Vec::new();
+        serve(&store, input.as_bytes(), &mut output).unwrap();
+
+        let responses: Vec<Value> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(responses.len(), 3);
+        assert_eq!(responses[0]["error"]["code"], PARSE_ERROR);
+        assert_eq!(responses[1]["result"]["isError"], false);
+        let created: Value = serde_json::from_str(
+            responses[1]["result"]["content"][0]["text"]
+                .as_str()
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(created["success"], true);
+        assert_eq!(responses[2]["result"]["isError"], true);
+        assert!(store.exists());
+    }
+}
@@ -0,0 +1,621 @@
+//! File-backed stand-in for the Archon task server.
+//!
+//! The unified prompt tracks tasks through Archon's `find_projects`,
+//! `find_tasks` and `manage_task` tools. This module implements the same
+//! operations, with the same arguments and response shapes, over a JSON file,
+//! so a project without Archon still gets the todo → doing → review → done
+//! lifecycle. [`mcp`] serves them to Claude as an MCP server.
+
+pub mod mcp;
+pub mod sync;
+
+use std::fs::{self, File, OpenOptions};
+use std::os::fd::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::error::{Error, Result};
+
+pub const DEFAULT_STORE_PATH: &str = "ralph/archon.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskStatus {
+    Todo,
+    Doing,
+    Review,
+    Done,
+}
+
+impl TaskStatus {
+    pub fn name(self) -> &'static str {
+        match self {
+            TaskStatus::Todo => "todo",
+            TaskStatus::Doing => "doing",
+            TaskStatus::Review => "review",
+            TaskStatus::Done => "done",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Project {
+    pub id: String,
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    pub created_at: u64,
+    pub updated_at: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Task {
+    pub id: String,
+    pub project_id: String,
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    pub status: TaskStatus,
+    pub assignee: String,
+    /// Priority; higher goes first.
+    #[serde(default)]
+    pub task_order: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub feature: Option<String>,
+    pub created_at: u64,
+    pub updated_at: u64,
+}
+
+/// Arguments of `find_projects`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct FindProjects {
+    pub project_id: Option<String>,
+    /// Case-insensitive substring of the title or description.
+    pub query: Option<String>,
+}
+
+/// Arguments of `manage_project`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ManageProject {
+    pub action: String,
+    pub project_id: Option<String>,
+    pub title: Option<String>,
+    pub description: Option<String>,
+}
+
+/// Arguments of `find_tasks`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct FindTasks {
+    pub task_id: Option<String>,
+    /// Case-insensitive substring of the title, description or feature.
+    pub query: Option<String>,
+    /// `status`, `project` or `assignee`.
+    pub filter_by: Option<String>,
+    pub filter_value: Option<String>,
+    pub project_id: Option<String>,
+    /// Whether `done` tasks are listed.
+    pub include_closed: bool,
+}
+
+impl Default for FindTasks {
+    fn default() -> Self {
+        FindTasks {
+            task_id: None,
+            query: None,
+            filter_by: None,
+            filter_value: None,
+            project_id: None,
+            include_closed: true,
+        }
+    }
+}
+
+/// Arguments of `manage_task`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ManageTask {
+    pub action: String,
+    pub task_id: Option<String>,
+    pub project_id: Option<String>,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub status: Option<TaskStatus>,
+    pub assignee: Option<String>,
+    pub task_order: Option<i64>,
+    pub feature: Option<String>,
+}
+
+/// Everything the server stores.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Store {
+    /// Last number used in a project or task ID.
+    pub next_id: u64,
+    pub projects: Vec<Project>,
+    pub tasks: Vec<Task>,
+}
+
+/// Tools the server offers, with whether they change the store.
+pub const TOOLS: [(&str, bool); 4] = [
+    ("find_projects", false),
+    ("manage_project", true),
+    ("find_tasks", false),
+    ("manage_task", true),
+];
+
+impl Store {
+    /// Load the store, starting empty when the file does not exist yet.
+    pub fn load(path: &Path) -> Result<Store> {
+        match fs::read_to_string(path) {
+            Ok(text) => serde_json::from_str(&text).map_err(|e| Error::json(path, e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Store::default()),
+            Err(e) => Err(Error::io(path, e)),
+        }
+    }
+
+    /// Write the store through a temporary file, so readers never see half of
+    /// it.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            fs::create_dir_all(dir).map_err(|e| Error::io(dir, e))?;
+        }
+        let text = serde_json::to_string_pretty(self).expect("store serializes");
+        let tmp = sibling(path, "tmp");
+        fs::write(&tmp, text + "\n").map_err(|e| Error::io(&tmp, e))?;
+        fs::rename(&tmp, path).map_err(|e| Error::io(path, e))
+    }
+
+    pub fn find_projects(&self, args: &FindProjects) -> Result<Value> {
+        if let Some(id) = &args.project_id {
+            let project = self.project(id)?;
+            return Ok(json!({ "success": true, "project": project }));
+        }
+        let projects: Vec<&Project> = self
+            .projects
+            .iter()
+            .filter(|p| {
+                args.query
+                    .as_deref()
+                    .is_none_or(|q| matches(q, [p.title.as_str(), &p.description]))
+            })
+            .collect();
+        Ok(json!({ "success": true, "projects": projects, "count": projects.len() }))
+    }
+
+    pub fn manage_project(&mut self, args: ManageProject, now: u64) -> Result<Value> {
+        match args.action.as_str() {
+            "create" => {
+                let title = required(args.title, "title")?;
+                let project = Project {
+                    id: self.next_id("project"),
+                    title,
+                    description: args.description.unwrap_or_default(),
+                    created_at: now,
+                    updated_at: now,
+                };
+                self.projects.push(project.clone());
+                Ok(json!({ "success": true, "project": project, "message": "Project created" }))
+            }
+            "update" => {
+                let id = required(args.project_id, "project_id")?;
+                let project = self
+                    .projects
+                    .iter_mut()
+                    .find(|p| p.id == id)
+                    .ok_or_else(|| unknown("project", &id))?;
+                if let Some(title) = args.title {
+                    project.title = title;
+                }
+                if let Some(description) = args.description {
+                    project.description = description;
+                }
+                project.updated_at = now;
+                Ok(json!({ "success": true, "project": project, "message": "Project updated" }))
+            }
+            "delete" => {
+                let id = required(args.project_id, "project_id")?;
+                self.project(&id)?;
+                self.projects.retain(|p| p.id != id);
+                self.tasks.retain(|t| t.project_id != id);
+                Ok(json!({ "success": true, "message": format!("Project {id} deleted") }))
+            }
+            other => Err(invalid_action(other)),
+        }
+    }
+
+    pub fn find_tasks(&self, args: &FindTasks) -> Result<Value> {
+        if let Some(id) = &args.task_id {
+            let task = self.task(id)?;
+            return Ok(json!({ "success": true, "task": task }));
+        }
+        let tasks = self.matching_tasks(args)?;
+        Ok(json!({ "success": true, "tasks": tasks, "count": tasks.len() }))
+    }
+
+    /// Tasks passing the filters of `args`, highest `task_order` first.
+    pub fn matching_tasks(&self, args: &FindTasks) -> Result<Vec<&Task>> {
+        let filter = match (args.filter_by.as_deref(), args.filter_value.as_deref()) {
+            (None, _) => None,
+            (Some(by @ ("status" | "project" | "assignee")), Some(value)) => Some((by, value)),
+            (Some(by @ ("status" | "project" | "assignee")), None) => {
+                return Err(Error::Archon(format!(
+                    "filter_by `{by}` needs a filter_value"
+                )))
+            }
+            (Some(by), _) => {
+                return Err(Error::Archon(format!(
+                    "invalid filter_by `{by}` (expected status, project or assignee)"
+                )))
+            }
+        };
+        let mut tasks: Vec<&Task> = self
+            .tasks
+            .iter()
+            .filter(|t| match filter {
+                Some(("status", value)) => t.status.name() == value,
+                Some(("project", value)) => t.project_id == value,
+                Some((_, value)) => t.assignee == value,
+                None => true,
+            })
+            .filter(|t| {
+                args.project_id
+                    .as_ref()
+                    .is_none_or(|id| &t.project_id == id)
+            })
+            .filter(|t| args.include_closed || t.status != TaskStatus::Done)
+            .filter(|t| {
+                args.query.as_deref().is_none_or(|q| {
+                    matches(
+                        q,
+                        [
+                            t.title.as_str(),
+                            &t.description,
+                            t.feature.as_deref().unwrap_or(""),
+                        ],
+                    )
+                })
+            })
+            .collect();
+        // Stable sort: equal priorities keep their creation order.
+        tasks.sort_by_key(|t| std::cmp::Reverse(t.task_order));
+        Ok(tasks)
+    }
+
+    pub fn manage_task(&mut self, args: ManageTask, now: u64) -> Result<Value> {
+        match args.action.as_str() {
+            "create" => {
+                let project_id = required(args.project_id, "project_id")?;
+                self.project(&project_id)?;
+                let task = Task {
+                    id: self.next_id("task"),
+                    project_id,
+                    title: required(args.title, "title")?,
+                    description: args.description.unwrap_or_default(),
+                    status: args.status.unwrap_or(TaskStatus::Todo),
+                    assignee: args.assignee.unwrap_or_else(|| "User".to_string()),
+                    task_order: args.task_order.unwrap_or_default(),
+                    feature: args.feature,
+                    created_at: now,
+                    updated_at: now,
+                };
+                self.tasks.push(task.clone());
+                Ok(json!({ "success": true, "task": task, "message": "Task created" }))
+            }
+            "update" => {
+                let id = required(args.task_id, "task_id")?;
+                let task = self
+                    .tasks
+                    .iter_mut()
+                    .find(|t| t.id == id)
+                    .ok_or_else(|| unknown("task", &id))?;
+                if let Some(title) = args.title {
+                    task.title = title;
+                }
+                if let Some(description) = args.description {
+                    task.description = description;
+                }
+                if let Some(status) = args.status {
+                    task.status = status;
+                }
+                if let Some(assignee) = args.assignee {
+                    task.assignee = assignee;
+                }
+                if let Some(order) = args.task_order {
+                    task.task_order = order;
+                }
+                if let Some(feature) = args.feature {
+                    task.feature = Some(feature);
+                }
+                task.updated_at = now;
+                Ok(json!({ "success": true, "task": task, "message": "Task updated" }))
+            }
+            "delete" => {
+                let id = required(args.task_id, "task_id")?;
+                self.task(&id)?;
+                self.tasks.retain(|t| t.id != id);
+                Ok(json!({ "success": true, "message": format!("Task {id} deleted") }))
+            }
+            other => Err(invalid_action(other)),
+        }
+    }
+
+    pub fn project(&self, id: &str) -> Result<&Project> {
+        self.projects
+            .iter()
+            .find(|p| p.id == id)
+            .ok_or_else(|| unknown("project", id))
+    }
+
+    pub fn task(&self, id: &str) -> Result<&Task> {
+        self.tasks
+            .iter()
+            .find(|t| t.id == id)
+            .ok_or_else(|| unknown("task", id))
+    }
+
+    fn next_id(&mut self, kind: &str) -> String {
+        self.next_id += 1;
+        format!("{kind}-{}", self.next_id)
+    }
+}
+
+/// Where tool calls go: straight to the local store, or to an MCP server.
+pub enum Server {
+    Local(PathBuf),
+    Mcp(mcp::Client),
+}
+
+impl Server {
+    /// The MCP server started by `command`, or the local store when the
+    /// command is empty.
+    pub fn open(store: &Path, command: &[String]) -> Result<Server> {
+        if command.is_empty() {
+            Ok(Server::Local(store.to_path_buf()))
+        } else {
+            mcp::Client::spawn(command).map(Server::Mcp)
+        }
+    }
+
+    pub fn call(&mut self, tool: &str, args: Value) -> Result<Value> {
+        match self {
+            Server::Local(store) => {
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_secs())
+                    .unwrap_or_default();
+                call(store, tool, args, now)
+            }
+            Server::Mcp(client) => client.call(tool, args),
+        }
+    }
+}
+
+/// Run one tool against the store at `path`, holding its lock so concurrent
+/// servers (one per parallel worker) never lose each other's updates.
+pub fn call(path: &Path, tool: &str, args: Value, now: u64) -> Result<Value> {
+    let writes = TOOLS
+        .iter()
+        .find(|(name, _)| *name == tool)
+        .map(|(_, writes)| *writes)
+        .ok_or_else(|| Error::Archon(format!("unknown tool `{tool}`")))?;
+    let args = if args.is_null() { json!({}) } else { args };
+    let invalid = |e: serde_json::Error| Error::Archon(format!("invalid {tool} arguments: {e}"));
+
+    let _lock = lock(path)?;
+    let mut store = Store::load(path)?;
+    let response = match tool {
+        "find_projects" => store.find_projects(&serde_json::from_value(args).map_err(invalid)?),
+        "manage_project" => {
+            store.manage_project(serde_json::from_value(args).map_err(invalid)?, now)
+        }
+        "find_tasks" => store.find_tasks(&serde_json::from_value(args).map_err(invalid)?),
+        _ => store.manage_task(serde_json::from_value(args).map_err(invalid)?, now),
+    }?;
+    if writes {
+        store.save(path)?;
+    }
+    Ok(response)
+}
+
+/// Exclusive lock on `<store>.lock`, held until the file is dropped.
+fn lock(path: &Path) -> Result<File> {
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        fs::create_dir_all(dir).map_err(|e| Error::io(dir, e))?;
+    }
+    let path = sibling(path, "lock");
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&path)
+        .map_err(|e| Error::io(&path, e))?;
+    // SAFETY: flock(2) on a descriptor we own; the lock is released on close.
+    if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX) } != 0 {
+        return Err(Error::io(&path, std::io::Error::last_os_error()));
+    }
+    Ok(file)
+}
+
+/// `path` with `.ext` appended to its file name.
+fn sibling(path: &Path, ext: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{ext}"));
+    path.with_file_name(name)
+}
+
+fn matches<'a>(query: &str, fields: impl IntoIterator<Item = &'a str>) -> bool {
+    let query = query.to_lowercase();
+    fields
+        .into_iter()
+        .any(|field| field.to_lowercase().contains(&query))
+}
+
+fn required(value: Option<String>, name: &str) -> Result<String> {
+    value
+        .filter(|v| !v.trim().is_empty())
+        .ok_or_else(|| Error::Archon(format!("`{name}` is required")))
+}
+
+fn unknown(kind: &str, id: &str) -> Error {
+    Error::Archon(format!("{kind} `{id}` not found"))
+}
+
+fn invalid_action(action: &str) -> Error {
+    Error::Archon(format!(
+        "invalid action `{action}` (expected create, update or delete)"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    fn args<T: serde::de::DeserializeOwned>(value: Value) -> T {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn store() -> Store {
+        let mut store = Store::default();
+        store
+            .manage_project(args(json!({ "action": "create", "title": "Audit" })), 1)
+            .unwrap();
+        for (title, order, status) in [
+            ("1.1 Model", 1, "done"),
+            ("1.2 Views", 3, "todo"),
+            ("1.3 Tests", 3, "doing"),
+        ] {
+            store
+                .manage_task(
+                    args(json!({
+                        "action": "create",
+                        "project_id": "project-1",
+                        "title": title,
+                        "task_order": order,
+                        "status": status,
+                    })),
+                    2,
+                )
+                .unwrap();
+        }
+        store
+    }
+
+    fn titles(tasks: Vec<&Task>) -> Vec<&str> {
+        tasks.into_iter().map(|t| t.title.as_str()).collect()
+    }
+
+    #[test]
+    fn ids_are_numbered_across_kinds() {
+        let store = store();
+        assert_eq!(store.next_id, 4);
+        let ids: Vec<&str> = store.tasks.iter().map(|t| t.id.as_str()).collect();
+        assert_eq!(ids, ["task-2", "task-3", "task-4"]);
+        assert_eq!(store.tasks[0].assignee, "User");
+    }
+
+    #[test]
+    fn matching_tasks_filters_and_orders_by_priority() {
+        let store = store();
+        let all = store.matching_tasks(&FindTasks::default()).unwrap();
+        assert_eq!(titles(all), ["1.2 Views", "1.3 Tests", "1.1 Model"]);
+
+        let open = FindTasks {
+            include_closed: false,
+            ..FindTasks::default()
+        };
+        assert_eq!(
+            titles(store.matching_tasks(&open).unwrap()),
+            ["1.2 Views", "1.3 Tests"]
+        );
+
+        let doing: FindTasks = args(json!({ "filter_by": "status", "filter_value": "doing" }));
+        assert_eq!(titles(store.matching_tasks(&doing).unwrap()), ["1.3 Tests"]);
+
+        let query: FindTasks = args(json!({ "query": "VIEW" }));
+        assert_eq!(titles(store.matching_tasks(&query).unwrap()), ["1.2 Views"]);
+    }
+
+    #[test]
+    fn invalid_filters_are_errors() {
+        let store = store();
+        let missing: FindTasks = args(json!({ "filter_by": "status" }));
+        let err = store.matching_tasks(&missing).unwrap_err();
+        assert_eq!(err.to_string(), "filter_by `status` needs a filter_value");
+
+        let unknown: FindTasks = args(json!({ "filter_by": "owner", "filter_value": "x" }));
+        assert!(store.matching_tasks(&unknown).is_err());
+    }
+
+    #[test]
+    fn manage_task_updates_and_deletes() {
+        let mut store = store();
+        store
+            .manage_task(
+                args(json!({ "action": "update", "task_id": "task-3", "status": "review" })),
+                5,
+            )
+            .unwrap();
+        let task = store.task("task-3").unwrap();
+        assert_eq!(task.status, TaskStatus::Review);
+        assert_eq!(task.updated_at, 5);
+
+        let err = store
+            .manage_task(args(json!({ "action": "update", "task_id": "task-9" })), 5)
+            .unwrap_err();
+        assert_eq!(err.to_string(), "task `task-9` not found");
+
+        let err = store
+            .manage_task(
+                args(json!({ "action": "create", "project_id": "project-1", "title": " " })),
+                5,
+            )
+            .unwrap_err();
+        assert_eq!(err.to_string(), "`title` is required");
+
+        store
+            .manage_task(args(json!({ "action": "delete", "task_id": "task-3" })), 5)
+            .unwrap();
+        assert!(store.task("task-3").is_err());
+    }
+
+    #[test]
+    fn deleting_a_project_deletes_its_tasks() {
+        let mut store = store();
+        store
+            .manage_project(
+                args(json!({ "action": "delete", "project_id": "project-1" })),
+                5,
+            )
+            .unwrap();
+        assert!(store.projects.is_empty());
+        assert!(store.tasks.is_empty());
+    }
+
+    #[test]
+    fn call_saves_only_writes() {
+        let dir = TempDir::new("archon");
+        let path = dir.join("archon.json");
+
+        call(&path, "find_projects", Value::Null, 1).unwrap();
+        assert!(!path.exists());
+
+        let created = call(
+            &path,
+            "manage_project",
+            json!({ "action": "create", "title": "Audit" }),
+            1,
+        )
+        .unwrap();
+        assert_eq!(created["project"]["id"], "project-1");
+        assert_eq!(Store::load(&path).unwrap().projects.len(), 1);
+
+        let err = call(&path, "drop_tables", Value::Null, 1).unwrap_err();
+        assert_eq!(err.to_string(), "unknown tool `drop_tables`");
+    }
+}
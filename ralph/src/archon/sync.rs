@@ -0,0 +1,434 @@
+//! Two-way sync between an Archon project and the plan.
+//!
+//! Tasks are matched by plan task number, never by title: an Archon task
+//! names its plan task on a `plan-task: 1.3` line of its description (or,
+//! failing that, with a leading number in its title, as in `1.3 Create the
+//! model`). Archon is the source of truth for status, so the plan's
+//! checkboxes follow it; a plan task that is further along than its Archon
+//! task is still reset, but reported as a conflict. Plan tasks that Archon
+//! does not have yet are created there.
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use super::{Server, TaskStatus};
+use crate::error::Result;
+use crate::plan::{Plan, Status, Task};
+
+/// Tasks requested per `find_tasks` page.
+const PAGE_SIZE: usize = 50;
+
+/// The fields of an Archon task the sync needs. Real Archon and the local
+/// store agree on these; timestamps and the like differ, so they are skipped.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RemoteTask {
+    pub id: String,
+    pub title: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub status: TaskStatus,
+    #[serde(default)]
+    pub task_order: i64,
+}
+
+impl RemoteTask {
+    /// Number of the plan task this Archon task tracks.
+    pub fn plan_task(&self) -> Option<String> {
+        let marked = self
+            .description
+            .iter()
+            .flat_map(|d| d.lines())
+            .find_map(|line| {
+                let (key, value) = line.split_once(':')?;
+                key.trim()
+                    .eq_ignore_ascii_case("plan-task")
+                    .then(|| value.trim().to_string())
+            });
+        marked.or_else(|| {
+            let first = self.title.split_whitespace().next()?.trim_end_matches('.');
+            is_task_number(first).then(|| first.to_string())
+        })
+    }
+}
+
+/// Plan checkbox that stands for an Archon status.
+pub fn plan_status(status: TaskStatus) -> Status {
+    match status {
+        TaskStatus::Todo => Status::Todo,
+        TaskStatus::Doing => Status::Doing,
+        TaskStatus::Review => Status::InProgress,
+        TaskStatus::Done => Status::Done,
+    }
+}
+
+/// Archon status for a plan checkbox, used for tasks created from the plan.
+pub fn archon_status(status: Status) -> TaskStatus {
+    match status {
+        Status::Done => TaskStatus::Done,
+        Status::Doing | Status::InProgress => TaskStatus::Doing,
+        _ => TaskStatus::Todo,
+    }
+}
+
+/// Whether the checkbox already says what the Archon status says. Blocked
+/// and partially done (`[~]`) tasks are still `todo` in Archon, which has no
+/// such states.
+fn agrees(plan: Status, archon: TaskStatus) -> bool {
+    match archon {
+        TaskStatus::Todo => matches!(plan, Status::Todo | Status::Blocked | Status::InProgress),
+        TaskStatus::Doing => matches!(plan, Status::Doing | Status::InProgress),
+        TaskStatus::Review => matches!(plan, Status::InProgress | Status::Doing),
+        TaskStatus::Done => plan == Status::Done,
+    }
+}
+
+/// How far along a task is: not started, started, or done.
+fn stage(plan: Status) -> u8 {
+    match plan {
+        Status::Done => 2,
+        Status::Doing | Status::InProgress => 1,
+        _ => 0,
+    }
+}
+
+fn archon_stage(status: TaskStatus) -> u8 {
+    stage(plan_status(status))
+}
+
+/// A plan checkbox changed to match Archon.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Update {
+    pub task: String,
+    pub archon_id: String,
+    pub from: Status,
+    pub to: Status,
+    pub archon: TaskStatus,
+    /// The plan was further along than Archon, so progress was undone.
+    pub conflict: bool,
+}
+
+/// A plan task created in Archon.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Created {
+    pub task: String,
+    /// Empty in a dry run.
+    pub archon_id: String,
+    pub status: TaskStatus,
+}
+
+/// An Archon task with no plan task to sync with.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Unmatched {
+    pub archon_id: String,
+    pub title: String,
+    /// The plan task it names, when the plan has no such task.
+    pub plan_task: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Report {
+    pub updated: Vec<Update>,
+    pub created: Vec<Created>,
+    pub archon_only: Vec<Unmatched>,
+    /// Plan tasks without a number, which cannot be matched.
+    pub unnumbered: Vec<String>,
+    /// Plan tasks named by more than one Archon task; left alone.
+    pub duplicates: Vec<String>,
+}
+
+impl Report {
+    pub fn conflicts(&self) -> impl Iterator<Item = &Update> {
+        self.updated.iter().filter(|u| u.conflict)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.updated.is_empty() && self.created.is_empty()
+    }
+}
+
+/// Every task of `project`, following `find_tasks` pages.
+pub fn fetch(server: &mut Server, project: &str) -> Result<Vec<RemoteTask>> {
+    // Fails on an unknown project, which would otherwise look empty.
+    server.call("find_projects", json!({ "project_id": project }))?;
+    let mut tasks: Vec<RemoteTask> = Vec::new();
+    for page in 1.. {
+        let response = server.call(
+            "find_tasks",
+            json!({
+                "filter_by": "project",
+                "filter_value": project,
+                "include_closed": true,
+                "page": page,
+                "per_page": PAGE_SIZE,
+            }),
+        )?;
+        let batch: Vec<RemoteTask> =
+            serde_json::from_value(response["tasks"].clone()).unwrap_or_default();
+        let count = batch.len();
+        let before = tasks.len();
+        for task in batch {
+            if !tasks.iter().any(|t| t.id == task.id) {
+                tasks.push(task);
+            }
+        }
+        // A server that ignores paging answers every page with everything.
+        if count < PAGE_SIZE || tasks.len() == before {
+            break;
+        }
+    }
+    Ok(tasks)
+}
+
+/// Bring the plan in line with Archon and create plan-only tasks in the
+/// project. With `dry_run`, only report what would change.
+pub fn sync(plan: &mut Plan, server: &mut Server, project: &str, dry_run: bool) -> Result<Report> {
+    let remote = fetch(server, project)?;
+    let mut report = Report::default();
+
+    let named: Vec<(Option<String>, &RemoteTask)> =
+        remote.iter().map(|t| (t.plan_task(), t)).collect();
+    for (id, task) in &named {
+        let is_known = id.as_deref().is_some_and(|id| plan.task(id).is_some());
+        if !is_known {
+            report.archon_only.push(Unmatched {
+                archon_id: task.id.clone(),
+                title: task.title.clone(),
+                plan_task: id.clone(),
+            });
+        }
+    }
+
+    let tasks: Vec<Task> = plan.tasks().cloned().collect();
+    let total = tasks.len();
+    for (index, task) in tasks.iter().enumerate() {
+        let Some(id) = task.id.as_deref() else {
+            report.unnumbered.push(task.title.clone());
+            continue;
+        };
+        let matches: Vec<&RemoteTask> = named
+            .iter()
+            .filter(|(named, _)| named.as_deref() == Some(id))
+            .map(|(_, task)| *task)
+            .collect();
+        match matches.as_slice() {
+            [] => {
+                let status = archon_status(task.status);
+                let archon_id = if dry_run {
+                    String::new()
+                } else {
+                    create(server, project, plan, task, status, total - index)?
+                };
+                report.created.push(Created {
+                    task: id.to_string(),
+                    archon_id,
+                    status,
+                });
+            }
+            [remote] if !agrees(task.status, remote.status) => {
+                let to = plan_status(remote.status);
+                if !dry_run {
+                    plan.set_status(id, to)?;
+                }
+                report.updated.push(Update {
+                    task: id.to_string(),
+                    archon_id: remote.id.clone(),
+                    from: task.status,
+                    to,
+                    archon: remote.status,
+                    conflict: stage(task.status) > archon_stage(remote.status),
+                });
+            }
+            [_] => {}
+            _ => report.duplicates.push(id.to_string()),
+        }
+    }
+    Ok(report)
+}
+
+/// Create `task` in the project; the plan order becomes the priority.
+fn create(
+    server: &mut Server,
+    project: &str,
+    plan: &Plan,
+    task: &Task,
+    status: TaskStatus,
+    order: usize,
+) -> Result<String> {
+    let id = task.label();
+    let feature = plan
+        .phases
+        .iter()
+        .find(|p| p.tasks.iter().any(|t| t.line == task.line))
+        .map(|p| format!("Phase {}: {}", p.number, p.title));
+    let mut args = json!({
+        "action": "create",
+        "project_id": project,
+        "title": format!("{id} {}", task.title),
+        "description": format!("plan-task: {id}"),
+        "status": status,
+        "task_order": order,
+    });
+    if let Some(feature) = feature {
+        args["feature"] = json!(feature);
+    }
+    let response = server.call("manage_task", args)?;
+    Ok(response["task"]["id"]
+        .as_str()
+        .or_else(|| response["task_id"].as_str())
+        .unwrap_or_default()
+        .to_string())
+}
+
+fn is_task_number(token: &str) -> bool {
+    token.starts_with(|c: char| c.is_ascii_digit())
+        && token.contains('.')
+        && token.chars().all(|c| c.is_ascii_digit() || c == '.')
+}
+
+/// Move the Archon task tracking plan task `id` to `status`, appending `note`
+/// to its description. Returns the Archon task ID, or `None` when the project
+/// has no task for `id`.
+pub fn set_status(
+    server: &mut Server,
+    project: &str,
+    id: &str,
+    status: TaskStatus,
+    note: Option<&str>,
+) -> Result<Option<String>> {
+    let remote = fetch(server, project)?;
+    let Some(task) = remote.iter().find(|t| t.plan_task().as_deref() == Some(id)) else {
+        return Ok(None);
+    };
+    let mut args = json!({ "action": "update", "task_id": task.id, "status": status });
+    if let Some(note) = note {
+        let description = match task.description.as_deref().map(str::trim_end) {
+            Some(text) if !text.is_empty() => format!("{text}\n{note}"),
+            _ => note.to_string(),
+        };
+        args["description"] = json!(description);
+    }
+    server.call("manage_task", args)?;
+    Ok(Some(task.id.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use super::*;
+    use crate::archon;
+    use crate::testing::TempDir;
+
+    const PLAN: &str = "\
+## Phase 1: Backend
+
+### Tasks
+- [x] 1.1 Model (`models.py`)
+- [ ] 1.2 Views (`views.py`)
+- [ ] 1.3 Tests (`tests.py`)
+- [ ] 1.4 Admin (`admin.py`)
+- [ ] Document the endpoints
+";
+
+    /// A local store holding `tasks` (title, description, status) in
+    /// `project-1`, in a directory removed with the returned guard.
+    fn server(tasks: &[(&str, &str, &str)]) -> (Server, TempDir) {
+        let dir = TempDir::new("sync");
+        let path = dir.join("archon.json");
+        let manage = |tool: &str, args: Value| archon::call(&path, tool, args, 1).unwrap();
+        manage(
+            "manage_project",
+            json!({ "action": "create", "title": "Audit" }),
+        );
+        for (title, description, status) in tasks {
+            manage(
+                "manage_task",
+                json!({
+                    "action": "create",
+                    "project_id": "project-1",
+                    "title": title,
+                    "description": description,
+                    "status": status,
+                }),
+            );
+        }
+        (Server::Local(path), dir)
+    }
+
+    const REMOTE: [(&str, &str, &str); 5] = [
+        ("Create the model", "plan-task: 1.1", "todo"),
+        ("1.2 Views", "", "done"),
+        ("1.4 Admin", "", "doing"),
+        ("1.4. Admin again", "", "todo"),
+        ("9.9 Stale", "", "todo"),
+    ];
+
+    #[test]
+    fn plan_task_prefers_the_description_marker() {
+        let task = |title: &str, description: Option<&str>| RemoteTask {
+            id: "task-1".to_string(),
+            title: title.to_string(),
+            description: description.map(str::to_string),
+            status: TaskStatus::Todo,
+            task_order: 0,
+        };
+        let marked = task("2.1 Old title", Some("Notes\nPlan-Task: 1.3"));
+        assert_eq!(marked.plan_task().as_deref(), Some("1.3"));
+        assert_eq!(task("1.3. Tests", None).plan_task().as_deref(), Some("1.3"));
+        assert_eq!(task("Version 1.3", None).plan_task(), None);
+    }
+
+    #[test]
+    fn sync_follows_archon_and_reports_conflicts() {
+        let (mut server, _dir) = server(&REMOTE);
+        let mut plan = Plan::parse(PLAN);
+        let report = sync(&mut plan, &mut server, "project-1", false).unwrap();
+
+        let updated: Vec<(&str, Status, bool)> = report
+            .updated
+            .iter()
+            .map(|u| (u.task.as_str(), u.to, u.conflict))
+            .collect();
+        assert_eq!(
+            updated,
+            [("1.1", Status::Todo, true), ("1.2", Status::Done, false)]
+        );
+        assert_eq!(report.conflicts().count(), 1);
+        assert_eq!(plan.task("1.1").unwrap().status, Status::Todo);
+        assert_eq!(plan.task("1.2").unwrap().status, Status::Done);
+
+        assert_eq!(report.duplicates, ["1.4"]);
+        assert_eq!(plan.task("1.4").unwrap().status, Status::Todo);
+        assert_eq!(report.unnumbered, ["Document the endpoints"]);
+        let stale: Vec<Option<&str>> = report
+            .archon_only
+            .iter()
+            .map(|u| u.plan_task.as_deref())
+            .collect();
+        assert_eq!(stale, [Some("9.9")]);
+
+        assert_eq!(report.created.len(), 1);
+        assert_eq!(report.created[0].task, "1.3");
+        let remote = fetch(&mut server, "project-1").unwrap();
+        let created = remote.iter().find(|t| t.id == report.created[0].archon_id);
+        assert_eq!(created.unwrap().plan_task().as_deref(), Some("1.3"));
+    }
+
+    #[test]
+    fn dry_run_changes_nothing() {
+        let (mut server, _dir) = server(&REMOTE);
+        let mut plan = Plan::parse(PLAN);
+        let report = sync(&mut plan, &mut server, "project-1", true).unwrap();
+        assert_eq!(report.updated.len(), 2);
+        assert_eq!(report.created[0].archon_id, "");
+        assert_eq!(plan.to_string(), PLAN);
+        assert_eq!(fetch(&mut server, "project-1").unwrap().len(), REMOTE.len());
+    }
+
+    #[test]
+    fn unknown_project_is_an_error() {
+        let (mut server, _dir) = server(&[]);
+        let mut plan = Plan::parse(PLAN);
+        assert!(sync(&mut plan, &mut server, "project-9", false).is_err());
+    }
+}
@@ -0,0 +1,167 @@
+//! Consecutive-failure circuit breaker for the loop.
+//!
+//! `loop.sh` classifies every iteration and records it here. Any successful
+//! iteration resets the streak; once `threshold` iterations in a row have
+//! failed the breaker trips and the loop halts with a summary instead of
+//! burning more model time on a problem that needs a human.
+
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+
+/// Exit code of `ralph breaker record` (and the loop) once the breaker trips.
+pub const EXIT_TRIPPED: u8 = 3;
+
+/// Why an iteration counts as failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Failure {
+    /// claude exited non-zero, timed out or stalled.
+    Exit,
+    /// HEAD did not move during the iteration.
+    NoCommit,
+    /// The test suite failed.
+    Tests,
+    /// No task in the plan changed status.
+    NoProgress,
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Failure::Exit => "non-zero exit",
+            Failure::NoCommit => "no commit",
+            Failure::Tests => "tests failing",
+            Failure::NoProgress => "no plan progress",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FailedIteration {
+    pub iteration: u32,
+    pub failures: Vec<Failure>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+/// Persistent breaker state, stored as JSON next to the iteration logs.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Breaker {
+    /// Length of the current failure streak.
+    pub consecutive: u32,
+    /// The iterations making up the current streak, oldest first.
+    pub recent: Vec<FailedIteration>,
+}
+
+impl Breaker {
+    /// Load the state, starting fresh when the file does not exist yet.
+    pub fn load(path: &Path) -> Result<Breaker> {
+        match fs::read_to_string(path) {
+            Ok(text) => serde_json::from_str(&text).map_err(|e| Error::json(path, e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Breaker::default()),
+            Err(e) => Err(Error::io(path, e)),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let text = serde_json::to_string_pretty(self).expect("breaker state serializes");
+        fs::write(path, text + "\n").map_err(|e| Error::io(path, e))
+    }
+
+    /// Record one iteration and report whether the breaker has tripped.
+    ///
+    /// An iteration with no failures resets the streak. Only the last
+    /// `threshold` failed iterations are kept for the summary.
+    pub fn record(
+        &mut self,
+        iteration: u32,
+        failures: Vec<Failure>,
+        detail: Option<String>,
+        threshold: u32,
+    ) -> bool {
+        if failures.is_empty() {
+            *self = Breaker::default();
+            return false;
+        }
+
+        self.consecutive += 1;
+        self.recent.push(FailedIteration {
+            iteration,
+            failures,
+            detail,
+        });
+        let keep = threshold.max(1) as usize;
+        if self.recent.len() > keep {
+            self.recent.drain(..self.recent.len() - keep);
+        }
+        threshold > 0 && self.consecutive >= threshold
+    }
+
+    /// Human-readable description of the current failure streak.
+    pub fn summary(&self) -> String {
+        let mut out = format!("{} consecutive failed iteration(s):", self.consecutive);
+        for failed in &self.recent {
+            let reasons: Vec<String> = failed.failures.iter().map(Failure::to_string).collect();
+            out.push_str(&format!(
+                "\n  iteration {}: {}",
+                failed.iteration,
+                reasons.join(", ")
+            ));
+            if let Some(detail) = &failed.detail {
+                out.push_str(&format!(" ({detail})"));
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trips_at_the_threshold_and_resets_on_success() {
+        let mut breaker = Breaker::default();
+        assert!(!breaker.record(1, vec![Failure::NoCommit], None, 3));
+        assert!(!breaker.record(2, vec![Failure::Tests], None, 3));
+        assert!(!breaker.record(3, Vec::new(), None, 3));
+        assert_eq!(breaker.consecutive, 0);
+        assert!(breaker.recent.is_empty());
+
+        assert!(!breaker.record(4, vec![Failure::Exit], None, 3));
+        assert!(!breaker.record(5, vec![Failure::NoProgress], None, 3));
+        let detail = Some("exit 1".to_string());
+        assert!(breaker.record(6, vec![Failure::Exit, Failure::NoCommit], detail, 3));
+        assert_eq!(
+            breaker.summary(),
+            "3 consecutive failed iteration(s):\n  iteration 4: non-zero exit\
+             \n  iteration 5: no plan progress\
+             \n  iteration 6: non-zero exit, no commit (exit 1)"
+        );
+    }
+
+    #[test]
+    fn only_the_last_threshold_failures_are_kept() {
+        let mut breaker = Breaker::default();
+        for iteration in 1..=5 {
+            breaker.record(iteration, vec![Failure::Tests], None, 2);
+        }
+        assert_eq!(breaker.consecutive, 5);
+        let kept: Vec<u32> = breaker.recent.iter().map(|f| f.iteration).collect();
+        assert_eq!(kept, [4, 5]);
+    }
+
+    #[test]
+    fn a_zero_threshold_never_trips() {
+        let mut breaker = Breaker::default();
+        for iteration in 1..=3 {
+            assert!(!breaker.record(iteration, vec![Failure::Exit], None, 0));
+        }
+        assert_eq!(breaker.recent.len(), 1);
+    }
+}
@@ -0,0 +1,224 @@
+//! Burndown of the plan from its git history.
+//!
+//! Every iteration commits `IMPLEMENTATION_PLAN.md`, so walking the file's
+//! history and diffing consecutive revisions shows how many tasks each
+//! commit (or day) added, completed and blocked, and how many were left.
+
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::error::Result;
+use crate::git;
+use crate::plan::{Plan, Snapshot, Status};
+use crate::timestamp;
+
+/// Changes since the previous point, and the state of the plan after it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct Counts {
+    pub added: usize,
+    pub completed: usize,
+    pub blocked: usize,
+    /// Tasks that went from `[x]` back to open.
+    pub reopened: usize,
+    pub removed: usize,
+    pub total: usize,
+    pub done: usize,
+    pub remaining: usize,
+}
+
+impl Counts {
+    fn between(before: &Snapshot, after: &Snapshot) -> Counts {
+        let mut counts = Counts::default();
+        for moved in before.transitions(after) {
+            match (moved.from, moved.to) {
+                (None, Some(_)) => counts.added += 1,
+                (Some(_), None) => counts.removed += 1,
+                (Some(Status::Done), Some(_)) => counts.reopened += 1,
+                _ => {}
+            }
+            // A task that shows up already done (planning mode finding
+            // existing work) was not completed by this commit.
+            if moved.to == Some(Status::Done) && moved.from.is_some_and(|s| s != Status::Done) {
+                counts.completed += 1;
+            }
+            if moved.to == Some(Status::Blocked) {
+                counts.blocked += 1;
+            }
+        }
+        counts.total = after.tasks.len();
+        counts.done = after
+            .tasks
+            .iter()
+            .filter(|t| t.status == Status::Done)
+            .count();
+        counts.remaining = counts.total - counts.done;
+        counts
+    }
+}
+
+/// One commit, or one day of commits.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Point {
+    /// Short commit id, or `YYYY-MM-DD`.
+    pub period: String,
+    /// Commit time of the (last) commit, unix seconds.
+    pub time: u64,
+    pub commits: usize,
+    /// Commit subject; empty for a day.
+    pub subject: String,
+    #[serde(flatten)]
+    pub counts: Counts,
+}
+
+/// One point per commit that touched `path`, oldest first.
+pub fn by_commit(path: &Path) -> Result<Vec<Point>> {
+    let spec = revision_path(path)?;
+    let log = git::run(&[
+        "log",
+        "--reverse",
+        "--format=%h%x1f%ct%x1f%s",
+        "--",
+        &path.to_string_lossy(),
+    ])?;
+
+    let mut points = Vec::new();
+    let mut before = Snapshot::default();
+    for line in log.lines() {
+        let mut fields = line.splitn(3, '\x1f');
+        let (Some(commit), Some(time), subject) = (fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+        // The file is missing from the commit that deleted it.
+        let text = git::run(&["show", &format!("{commit}:{spec}")]).unwrap_or_default();
+        let after = Snapshot::of(&Plan::parse(&text));
+        points.push(Point {
+            period: commit.to_string(),
+            time: time.parse().unwrap_or_default(),
+            commits: 1,
+            subject: subject.unwrap_or_default().to_string(),
+            counts: Counts::between(&before, &after),
+        });
+        before = after;
+    }
+    Ok(points)
+}
+
+/// Commit points merged per UTC day: changes summed, state as of the day's
+/// last commit.
+pub fn by_day(points: &[Point]) -> Vec<Point> {
+    let mut days: Vec<Point> = Vec::new();
+    for point in points {
+        let day = timestamp::date(point.time);
+        match days.last_mut() {
+            Some(last) if last.period == day => {
+                let (sum, next) = (&mut last.counts, &point.counts);
+                sum.added += next.added;
+                sum.completed += next.completed;
+                sum.blocked += next.blocked;
+                sum.reopened += next.reopened;
+                sum.removed += next.removed;
+                (sum.total, sum.done, sum.remaining) = (next.total, next.done, next.remaining);
+                last.time = point.time;
+                last.commits += 1;
+            }
+            _ => days.push(Point {
+                period: day,
+                subject: String::new(),
+                ..point.clone()
+            }),
+        }
+    }
+    days
+}
+
+/// `path` as `git show REV:PATH` expects it: relative to the current
+/// directory when given that way, otherwise relative to the repository root.
+fn revision_path(path: &Path) -> Result<String> {
+    if path.is_relative() {
+        return Ok(format!("./{}", path.display()));
+    }
+    let root = git::run(&["rev-parse", "--show-toplevel"])?;
+    Ok(path
+        .strip_prefix(&root)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(tasks: &str) -> Snapshot {
+        Snapshot::of(&Plan::parse(&format!(
+            "## Phase 1: A\n\n### Tasks\n{tasks}"
+        )))
+    }
+
+    fn point(time: u64, counts: Counts) -> Point {
+        Point {
+            period: format!("c{time}"),
+            time,
+            commits: 1,
+            subject: "Iteration".to_string(),
+            counts,
+        }
+    }
+
+    #[test]
+    fn counts_changes_between_revisions() {
+        let before = snapshot("- [ ] 1.1 A\n- [x] 1.2 B\n- [ ] 1.3 C\n- [ ] 1.4 D\n");
+        let after = snapshot("- [x] 1.1 A\n- [ ] 1.2 B\n- [!] 1.3 C\n- [x] 1.5 E\n");
+        assert_eq!(
+            Counts::between(&before, &after),
+            Counts {
+                added: 1,
+                completed: 1,
+                blocked: 1,
+                reopened: 1,
+                removed: 1,
+                total: 4,
+                done: 2,
+                remaining: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn days_sum_changes_and_keep_the_last_state() {
+        let day = 19_782 * 86_400;
+        let first = Counts {
+            added: 3,
+            total: 3,
+            remaining: 3,
+            ..Counts::default()
+        };
+        let second = Counts {
+            completed: 1,
+            total: 3,
+            done: 1,
+            remaining: 2,
+            ..Counts::default()
+        };
+        let days = by_day(&[
+            point(day + 10, first),
+            point(day + 20, second),
+            point(day + 86_400, second),
+        ]);
+        assert_eq!(days.len(), 2);
+        assert_eq!(days[0].period, "2024-02-29");
+        assert_eq!((days[0].commits, days[0].time), (2, day + 20));
+        assert_eq!(days[0].subject, "");
+        assert_eq!(
+            (
+                days[0].counts.added,
+                days[0].counts.completed,
+                days[0].counts.remaining
+            ),
+            (3, 1, 2)
+        );
+        assert_eq!(days[1].period, "2024-03-01");
+    }
+}
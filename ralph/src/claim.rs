@@ -0,0 +1,201 @@
+//! Lock files that let parallel workers split the plan between them.
+//!
+//! A worker claims a task by creating `<dir>/<task>.lock` exclusively, so two
+//! workers can never hold the same task. The lock records the owning process;
+//! a lock whose process is gone (a killed loop) is treated as stale and taken
+//! over. Taking claims is serialized by a lock on `<dir>.lock`, so two
+//! workers can never both find a lock stale and one delete the claim the
+//! other just wrote.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::os::fd::AsRawFd;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::plan::Task;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Claim {
+    pub task: String,
+    pub title: String,
+    /// Free-form worker name, e.g. the iteration that works on the task.
+    pub owner: String,
+    pub pid: u32,
+    pub claimed_at: u64,
+}
+
+impl Claim {
+    fn path(dir: &Path, task: &str) -> PathBuf {
+        dir.join(format!("{}.lock", task.replace(['/', ' '], "_")))
+    }
+
+    fn is_stale(&self) -> bool {
+        // SAFETY: signal 0 sends nothing; it only checks the process exists.
+        let alive = unsafe { libc::kill(self.pid as libc::pid_t, 0) } == 0
+            || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM);
+        !alive
+    }
+}
+
+/// Every claim in `dir`, stale ones included.
+pub fn list(dir: &Path) -> Result<Vec<Claim>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(Error::io(dir, e)),
+    };
+    let mut claims: Vec<Claim> = Vec::new();
+    for entry in entries {
+        let path = entry.map_err(|e| Error::io(dir, e))?.path();
+        if path.extension().is_some_and(|ext| ext == "lock") {
+            let text = fs::read_to_string(&path).map_err(|e| Error::io(&path, e))?;
+            claims.push(serde_json::from_str(&text).map_err(|e| Error::json(&path, e))?);
+        }
+    }
+    claims.sort_by_key(|c| c.claimed_at);
+    Ok(claims)
+}
+
+/// Claim up to `count` of `candidates` that no live worker holds, in the
+/// given order (e.g. [`crate::plan::Plan::ready_tasks`]).
+pub fn take(
+    candidates: &[&Task],
+    dir: &Path,
+    count: usize,
+    owner: &str,
+    pid: u32,
+    now: u64,
+) -> Result<Vec<Claim>> {
+    fs::create_dir_all(dir).map_err(|e| Error::io(dir, e))?;
+    let _lock = lock(dir)?;
+    for claim in list(dir)?.iter().filter(|c| c.is_stale()) {
+        release(dir, &claim.task)?;
+    }
+
+    let mut taken = Vec::new();
+    for task in candidates {
+        if taken.len() == count {
+            break;
+        }
+        let claim = Claim {
+            task: task.label().to_string(),
+            title: task.title.clone(),
+            owner: owner.to_string(),
+            pid,
+            claimed_at: now,
+        };
+        let path = Claim::path(dir, &claim.task);
+        let mut file = match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(Error::io(&path, e)),
+        };
+        let text = serde_json::to_string_pretty(&claim).expect("claim serializes");
+        writeln!(file, "{text}").map_err(|e| Error::io(&path, e))?;
+        taken.push(claim);
+    }
+    Ok(taken)
+}
+
+/// Hold an exclusive lock on `<dir>.lock` until the returned file is
+/// dropped. It sits beside the directory, where [`list`] does not read it.
+fn lock(dir: &Path) -> Result<File> {
+    let mut name = dir.file_name().unwrap_or_default().to_os_string();
+    name.push(".lock");
+    let path = dir.with_file_name(name);
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&path)
+        .map_err(|e| Error::io(&path, e))?;
+    // SAFETY: flock(2) on a descriptor we own; the lock is released on close.
+    if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX) } != 0 {
+        return Err(Error::io(&path, std::io::Error::last_os_error()));
+    }
+    Ok(file)
+}
+
+/// Drop the claim on `task`, if any.
+pub fn release(dir: &Path, task: &str) -> Result<()> {
+    let path = Claim::path(dir, task);
+    match fs::remove_file(&path) {
+        Err(e) if e.kind() != ErrorKind::NotFound => Err(Error::io(&path, e)),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plan::Plan;
+    use crate::testing::TempDir;
+
+    const PLAN: &str = "\
+## Phase 1: Backend
+
+### Tasks
+- [ ] 1.1 Model
+- [ ] 1.2 Views
+";
+
+    /// A process ID that no longer exists.
+    fn dead_pid() -> u32 {
+        let mut child = std::process::Command::new("true").spawn().unwrap();
+        let pid = child.id();
+        child.wait().unwrap();
+        pid
+    }
+
+    fn ids(claims: &[Claim]) -> Vec<&str> {
+        claims.iter().map(|c| c.task.as_str()).collect()
+    }
+
+    #[test]
+    fn live_claims_are_skipped() {
+        let dir = TempDir::new("claims");
+        let plan = Plan::parse(PLAN);
+        let tasks: Vec<&Task> = plan.tasks().collect();
+        let me = std::process::id();
+
+        let first = take(&tasks, &dir.join("claims"), 1, "worker-1", me, 1).unwrap();
+        assert_eq!(ids(&first), ["1.1"]);
+        let second = take(&tasks, &dir.join("claims"), 2, "worker-2", me, 2).unwrap();
+        assert_eq!(ids(&second), ["1.2"]);
+        assert!(take(&tasks, &dir.join("claims"), 1, "worker-3", me, 3)
+            .unwrap()
+            .is_empty());
+
+        release(&dir.join("claims"), "1.1").unwrap();
+        release(&dir.join("claims"), "1.1").unwrap();
+        let listed = list(&dir.join("claims")).unwrap();
+        assert_eq!(ids(&listed), ["1.2"]);
+        assert_eq!(listed[0].owner, "worker-2");
+    }
+
+    #[test]
+    fn stale_claims_are_taken_over() {
+        let dir = TempDir::new("claims");
+        let plan = Plan::parse(PLAN);
+        let tasks: Vec<&Task> = plan.tasks().take(1).collect();
+
+        take(&tasks, &dir.join("claims"), 1, "killed", dead_pid(), 1).unwrap();
+        let taken = take(
+            &tasks,
+            &dir.join("claims"),
+            1,
+            "worker",
+            std::process::id(),
+            2,
+        )
+        .unwrap();
+        assert_eq!(ids(&taken), ["1.1"]);
+        let listed = list(&dir.join("claims")).unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].owner, "worker");
+        assert!(dir.join("claims.lock").exists());
+    }
+}
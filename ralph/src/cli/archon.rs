@@ -0,0 +1,223 @@
+use std::io;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use clap::{Args, Subcommand};
+use serde_json::Value;
+
+use ralph::archon::sync::{self, Report};
+use ralph::archon::{self, mcp, FindTasks, Server, Store, TaskStatus};
+use ralph::config::{Config, DEFAULT_CONFIG_PATH};
+use ralph::plan::{Plan, DEFAULT_PLAN_PATH};
+use ralph::{git, Error, Result};
+
+#[derive(Debug, Args)]
+pub struct ArchonArgs {
+    /// Config file with the `[archon]` section
+    #[arg(long, default_value = DEFAULT_CONFIG_PATH, global = true)]
+    config: PathBuf,
+
+    /// JSON file holding the projects and tasks (default: `archon.store` in
+    /// the config)
+    #[arg(long, global = true)]
+    store: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: ArchonCommand,
+}
+
+#[derive(Debug, Subcommand)]
+enum ArchonCommand {
+    /// Serve find_projects, find_tasks, manage_project and manage_task as an
+    /// MCP server on stdio
+    Serve,
+    /// Run one tool and print its JSON response
+    ///
+    /// Exits with status 1 when the tool fails, e.g. on an unknown task ID.
+    Call {
+        /// find_projects, find_tasks, manage_project or manage_task
+        tool: String,
+
+        /// Tool arguments as a JSON object
+        #[arg(default_value = "{}")]
+        arguments: String,
+    },
+    /// Sync the plan with the project's tasks, Archon first
+    ///
+    /// Plan checkboxes take the status of the Archon task with the same plan
+    /// task number, and plan tasks missing from Archon are created there.
+    /// Exits with status 1 when a plan task was further along than Archon
+    /// (a conflict); the Archon status is applied regardless.
+    Sync {
+        #[arg(long, default_value = DEFAULT_PLAN_PATH)]
+        plan: PathBuf,
+
+        /// Project ID (default: `archon.project` in the config)
+        #[arg(long)]
+        project: Option<String>,
+
+        /// Report changes without making them
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Commit the plan when the sync changed it
+        #[arg(long)]
+        commit: bool,
+
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Print the tasks as a table, highest priority first
+    Tasks {
+        #[arg(long)]
+        project: Option<String>,
+
+        #[arg(long, value_enum)]
+        status: Option<TaskStatus>,
+    },
+}
+
+pub fn run(args: ArchonArgs) -> Result<ExitCode> {
+    let config = Config::load(&args.config)?.archon;
+    let store = args.store.unwrap_or_else(|| config.store.clone());
+    match args.command {
+        ArchonCommand::Serve => {
+            mcp::serve(&store, io::stdin().lock(), io::stdout().lock())?;
+        }
+        ArchonCommand::Call { tool, arguments } => {
+            let arguments: Value = serde_json::from_str(&arguments)
+                .map_err(|e| Error::Archon(format!("invalid arguments: {e}")))?;
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default();
+            let response = archon::call(&store, &tool, arguments, now)?;
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&response).expect("response serializes")
+            );
+        }
+        ArchonCommand::Sync {
+            plan: plan_path,
+            project,
+            dry_run,
+            commit,
+            json,
+        } => {
+            let project = project.or(config.project).ok_or_else(|| {
+                Error::Archon(
+                    "no Archon project: pass --project or set archon.project in the config"
+                        .to_string(),
+                )
+            })?;
+            let mut plan = Plan::load(&plan_path)?;
+            let mut server = Server::open(&store, &config.command)?;
+            let report = sync::sync(&mut plan, &mut server, &project, dry_run)?;
+            if !dry_run && !report.updated.is_empty() {
+                plan.save(&plan_path)?;
+                if commit {
+                    let path = plan_path.to_string_lossy();
+                    git::run(&["add", "--", &path])?;
+                    git::run(&[
+                        "commit",
+                        "--quiet",
+                        "-m",
+                        "ralph: sync plan with Archon",
+                        "--",
+                        &path,
+                    ])?;
+                }
+            }
+            if json {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&report).expect("report serializes")
+                );
+            } else {
+                print_report(&project, &report, dry_run);
+            }
+            if report.conflicts().next().is_some() {
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+        ArchonCommand::Tasks { project, status } => {
+            let loaded = Store::load(&store)?;
+            let tasks = loaded.matching_tasks(&FindTasks {
+                project_id: project,
+                filter_by: status.map(|_| "status".to_string()),
+                filter_value: status.map(|s| s.name().to_string()),
+                ..FindTasks::default()
+            })?;
+            if tasks.is_empty() {
+                println!("No tasks in {}", store.display());
+            }
+            for task in &tasks {
+                println!(
+                    "{:<10} {:<7} {:>4}  {:<12} {}",
+                    task.id,
+                    task.status.name(),
+                    task.task_order,
+                    task.project_id,
+                    task.title
+                );
+            }
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn print_report(project: &str, report: &Report, dry_run: bool) {
+    let conflicts = report.conflicts().count();
+    println!(
+        "Archon sync with {project}{}: {} updated ({conflicts} conflict(s)), {} created",
+        if dry_run { " (dry run)" } else { "" },
+        report.updated.len(),
+        report.created.len()
+    );
+    for update in &report.updated {
+        let mut line = format!(
+            "  {} {} -> {}  (Archon {}: {})",
+            update.task,
+            update.from,
+            update.to,
+            update.archon_id,
+            update.archon.name()
+        );
+        if update.conflict {
+            line.push_str("  CONFLICT: the plan was further along");
+        }
+        println!("{line}");
+    }
+    for created in &report.created {
+        println!(
+            "  {} created in Archon{} ({})",
+            created.task,
+            if created.archon_id.is_empty() {
+                String::new()
+            } else {
+                format!(" as {}", created.archon_id)
+            },
+            created.status.name()
+        );
+    }
+    for task in &report.archon_only {
+        match &task.plan_task {
+            Some(id) => println!(
+                "  Archon {} \"{}\" names plan task {id}, which does not exist",
+                task.archon_id, task.title
+            ),
+            None => println!(
+                "  Archon {} \"{}\" names no plan task (add `plan-task: N.M` to its description)",
+                task.archon_id, task.title
+            ),
+        }
+    }
+    for title in &report.unnumbered {
+        println!("  Plan task \"{title}\" has no number and was not synced");
+    }
+    for id in &report.duplicates {
+        println!("  Plan task {id} is named by several Archon tasks and was not synced");
+    }
+}
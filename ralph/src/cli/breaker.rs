@@ -0,0 +1,67 @@
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::{Args, Subcommand};
+
+use ralph::breaker::{Breaker, Failure, EXIT_TRIPPED};
+use ralph::Result;
+
+#[derive(Debug, Args)]
+pub struct BreakerArgs {
+    /// Breaker state file
+    #[arg(long, default_value = "ralph/logs/breaker.json", global = true)]
+    state: PathBuf,
+
+    #[command(subcommand)]
+    command: BreakerCommand,
+}
+
+#[derive(Debug, Subcommand)]
+enum BreakerCommand {
+    /// Record an iteration's outcome
+    ///
+    /// Exits with status 3 and prints the streak once THRESHOLD iterations
+    /// in a row have failed.
+    Record {
+        #[arg(long)]
+        iteration: u32,
+
+        /// Failed iterations in a row that trip the breaker (0 disables)
+        #[arg(long, default_value_t = 3)]
+        threshold: u32,
+
+        /// Reason the iteration failed (repeatable; none means success)
+        #[arg(long = "failure", value_enum)]
+        failures: Vec<Failure>,
+
+        /// Free-form context shown in the summary, e.g. "exit 1"
+        #[arg(long)]
+        detail: Option<String>,
+    },
+    /// Clear the failure streak
+    Reset,
+}
+
+pub fn run(args: BreakerArgs) -> Result<ExitCode> {
+    match args.command {
+        BreakerCommand::Record {
+            iteration,
+            threshold,
+            failures,
+            detail,
+        } => {
+            let mut breaker = Breaker::load(&args.state)?;
+            let tripped = breaker.record(iteration, failures, detail, threshold);
+            breaker.save(&args.state)?;
+            if tripped {
+                println!("{}", breaker.summary());
+                return Ok(ExitCode::from(EXIT_TRIPPED));
+            }
+            if breaker.consecutive > 0 {
+                println!("Failure streak: {}/{}", breaker.consecutive, threshold);
+            }
+        }
+        BreakerCommand::Reset => Breaker::default().save(&args.state)?,
+    }
+    Ok(ExitCode::SUCCESS)
+}
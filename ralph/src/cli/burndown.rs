@@ -0,0 +1,138 @@
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::{Args, ValueEnum};
+
+use ralph::burndown::{self, Point};
+use ralph::plan::DEFAULT_PLAN_PATH;
+use ralph::Result;
+
+/// Chart how the plan's tasks were added, completed and blocked over its
+/// git history.
+#[derive(Debug, Args)]
+pub struct BurndownArgs {
+    /// Plan file whose history to walk
+    #[arg(long, default_value = DEFAULT_PLAN_PATH)]
+    file: PathBuf,
+
+    /// One point per commit or per day
+    #[arg(long, value_enum, default_value_t = By::Day)]
+    by: By,
+
+    #[arg(long, value_enum, default_value_t = Format::Chart)]
+    format: Format,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum By {
+    Commit,
+    Day,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Format {
+    /// Bars of remaining tasks, for the terminal
+    Chart,
+    Csv,
+    Json,
+}
+
+/// Width of the longest bar in the chart.
+const BAR_WIDTH: usize = 40;
+
+pub fn run(args: BurndownArgs) -> Result<ExitCode> {
+    let commits = burndown::by_commit(&args.file)?;
+    let points = match args.by {
+        By::Commit => commits,
+        By::Day => burndown::by_day(&commits),
+    };
+
+    match args.format {
+        Format::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&points).expect("burndown serializes")
+        ),
+        Format::Csv => {
+            println!("period,time,commits,added,completed,blocked,reopened,removed,total,done,remaining,subject");
+            for p in &points {
+                let c = &p.counts;
+                println!(
+                    "{},{},{},{},{},{},{},{},{},{},{},{}",
+                    p.period,
+                    p.time,
+                    p.commits,
+                    c.added,
+                    c.completed,
+                    c.blocked,
+                    c.reopened,
+                    c.removed,
+                    c.total,
+                    c.done,
+                    c.remaining,
+                    csv_field(&p.subject)
+                );
+            }
+        }
+        Format::Chart => chart(&args.file, args.by, &points),
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn chart(file: &std::path::Path, by: By, points: &[Point]) {
+    if points.is_empty() {
+        println!("No commits touch {}", file.display());
+        return;
+    }
+    let per = match by {
+        By::Commit => "commit",
+        By::Day => "day",
+    };
+    println!("Remaining tasks in {} per {per}", file.display());
+    println!();
+
+    let most = points.iter().map(|p| p.counts.remaining).max().unwrap_or(0);
+    let width = points.iter().map(|p| p.period.len()).max().unwrap_or(0);
+    for p in points {
+        let c = &p.counts;
+        let bar = "█".repeat((c.remaining * BAR_WIDTH).div_ceil(most.max(1)));
+        let mut line = format!(
+            "{:<width$}  {bar:<BAR_WIDTH$}  {:>3} left  +{} added  {} done  {} blocked",
+            p.period, c.remaining, c.added, c.completed, c.blocked
+        );
+        if c.reopened > 0 {
+            line.push_str(&format!("  {} reopened", c.reopened));
+        }
+        if !p.subject.is_empty() {
+            line.push_str(&format!("  {}", truncate(&p.subject, 50)));
+        }
+        println!("{line}");
+    }
+
+    let completed: usize = points.iter().map(|p| p.counts.completed).sum();
+    let last = &points[points.len() - 1].counts;
+    println!();
+    println!(
+        "Velocity: {:.1} tasks completed per {per} over {} {per}(s); {}/{} done, {} left",
+        completed as f64 / points.len() as f64,
+        points.len(),
+        last.done,
+        last.total,
+        last.remaining
+    );
+}
+
+fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        text.to_string()
+    } else {
+        format!("{}…", text.chars().take(max - 1).collect::<String>())
+    }
+}
+
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
@@ -0,0 +1,91 @@
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use clap::{Args, Subcommand};
+
+use ralph::claim;
+use ralph::plan::{Plan, DEFAULT_PLAN_PATH};
+use ralph::{timestamp, Result};
+
+#[derive(Debug, Args)]
+pub struct ClaimArgs {
+    /// Directory holding one lock file per claimed task
+    #[arg(long, default_value = "ralph/logs/claims", global = true)]
+    dir: PathBuf,
+
+    #[command(subcommand)]
+    command: ClaimCommand,
+}
+
+#[derive(Debug, Subcommand)]
+enum ClaimCommand {
+    /// Claim ready tasks and print them as `ID<TAB>TITLE`, one per line
+    ///
+    /// A task is ready when it is neither done nor blocked, comes first in
+    /// its phase, every phase named on its phase's `**Dependency**` line is
+    /// complete, and every task on its `depends:` line is done. Exits with
+    /// status 1 when nothing could be claimed.
+    Take {
+        #[arg(long, default_value = DEFAULT_PLAN_PATH)]
+        plan: PathBuf,
+
+        /// Maximum number of tasks to claim
+        #[arg(long, default_value_t = 1)]
+        count: usize,
+
+        /// Worker name stored in the lock
+        #[arg(long)]
+        owner: String,
+
+        /// Process that holds the claims (default: the calling shell)
+        #[arg(long)]
+        pid: Option<u32>,
+    },
+    /// Release claimed tasks
+    Release { tasks: Vec<String> },
+    /// Print current claims
+    List,
+}
+
+pub fn run(args: ClaimArgs) -> Result<ExitCode> {
+    match args.command {
+        ClaimCommand::Take {
+            plan,
+            count,
+            owner,
+            pid,
+        } => {
+            let plan = Plan::load(&plan)?;
+            let pid = pid.unwrap_or_else(std::os::unix::process::parent_id);
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs());
+            let taken = claim::take(&plan.ready_tasks(), &args.dir, count, &owner, pid, now)?;
+            if taken.is_empty() {
+                return Ok(ExitCode::FAILURE);
+            }
+            for claim in taken {
+                println!("{}\t{}", claim.task, claim.title);
+            }
+        }
+        ClaimCommand::Release { tasks } => {
+            for task in tasks {
+                claim::release(&args.dir, &task)?;
+            }
+        }
+        ClaimCommand::List => {
+            for claim in claim::list(&args.dir)? {
+                println!(
+                    "{}  {}  pid {}  since {}  {}",
+                    claim.task,
+                    claim.owner,
+                    claim.pid,
+                    timestamp::datetime(claim.claimed_at),
+                    claim.title
+                );
+            }
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
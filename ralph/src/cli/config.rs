@@ -0,0 +1,56 @@
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::{Args, Subcommand};
+
+use ralph::config::{Config, DEFAULT_CONFIG_PATH};
+use ralph::Result;
+
+#[derive(Debug, Args)]
+pub struct ConfigArgs {
+    /// Config file (defaults apply when it does not exist)
+    #[arg(long, default_value = DEFAULT_CONFIG_PATH, global = true)]
+    file: PathBuf,
+
+    /// Project profile replacing `[project]`: `profiles/NAME.toml` next to
+    /// the config file, or a path
+    #[arg(long, global = true)]
+    profile: Option<String>,
+
+    #[command(subcommand)]
+    command: ConfigCommand,
+}
+
+#[derive(Debug, Subcommand)]
+enum ConfigCommand {
+    /// Print the effective value of a dotted key, e.g. `models.default`
+    ///
+    /// Prints nothing for an optional key that is not set.
+    Get { key: String },
+    /// Print the effective config, defaults included, as TOML
+    Show,
+}
+
+pub fn run(args: ConfigArgs) -> Result<ExitCode> {
+    let config = Config::load_with_profile(&args.file, args.profile.as_deref())?;
+    match args.command {
+        ConfigCommand::Get { key } => match config.get(&key)? {
+            Some(toml::Value::String(value)) => println!("{value}"),
+            Some(toml::Value::Array(values)) => {
+                for value in values {
+                    match value {
+                        toml::Value::String(value) => println!("{value}"),
+                        other => println!("{other}"),
+                    }
+                }
+            }
+            Some(other) => println!("{other}"),
+            None => {}
+        },
+        ConfigCommand::Show => print!(
+            "{}",
+            toml::to_string_pretty(&config).expect("config serializes")
+        ),
+    }
+    Ok(ExitCode::SUCCESS)
+}
@@ -0,0 +1,83 @@
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::Args;
+
+use ralph::stream::{self, Event};
+use ralph::Result;
+
+/// Print one line per decoded event of a stream-json iteration log.
+#[derive(Debug, Args)]
+pub struct EventsArgs {
+    /// Iteration log, e.g. ralph/logs/iteration_3.log
+    log: PathBuf,
+
+    /// Emit events as JSON lines instead of a summary
+    #[arg(long)]
+    json: bool,
+}
+
+pub fn run(args: EventsArgs) -> Result<ExitCode> {
+    for event in stream::read(&args.log)? {
+        if args.json {
+            println!(
+                "{}",
+                serde_json::to_string(&event).expect("events serialize")
+            );
+        } else {
+            println!("{}", summary(&event));
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn summary(event: &Event) -> String {
+    match event {
+        Event::SystemInit(init) => format!(
+            "init      model={} session={}",
+            init.model.as_deref().unwrap_or("?"),
+            init.session_id.as_deref().unwrap_or("?")
+        ),
+        Event::AssistantText { text } => format!("text      {}", first_line(text)),
+        Event::ToolUse(tool) => {
+            let detail = tool
+                .input_str("command")
+                .or_else(|| tool.input_str("file_path"))
+                .or_else(|| tool.input_str("pattern"))
+                .unwrap_or_default();
+            format!("tool_use  {} {}", tool.name, first_line(detail))
+        }
+        Event::ToolResult(result) => format!(
+            "output    {} ({} chars)",
+            if result.is_error { "error" } else { "ok" },
+            result.content.len()
+        ),
+        Event::Result(result) => format!(
+            "done      {} turns={} duration={}ms cost=${:.4}",
+            result.subtype.as_deref().unwrap_or("?"),
+            result.num_turns.unwrap_or_default(),
+            result.duration_ms.unwrap_or_default(),
+            result.total_cost_usd.unwrap_or_default()
+        ),
+        Event::Record(record) => format!(
+            "ralph     {} {}",
+            record.event,
+            serde_json::Value::Object(record.fields.clone())
+        ),
+        Event::Other { value } => format!(
+            "other     {}",
+            value["type"].as_str().unwrap_or("(untyped)")
+        ),
+        Event::Raw { text } => format!("raw       {}", first_line(text)),
+        Event::Truncated { .. } => "truncated (line cut off mid-write)".to_string(),
+    }
+}
+
+fn first_line(text: &str) -> String {
+    const MAX: usize = 100;
+    let line = text.lines().next().unwrap_or_default();
+    match line.char_indices().nth(MAX) {
+        Some((cut, _)) => format!("{}...", &line[..cut]),
+        None => line.to_string(),
+    }
+}
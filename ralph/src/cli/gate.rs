@@ -0,0 +1,192 @@
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::Duration;
+
+use clap::{Args, Subcommand};
+
+use ralph::config::{Config, DEFAULT_CONFIG_PATH};
+use ralph::gate::{self, Action, Baseline, OnRegression, Record, Results, EXIT_REGRESSED};
+use ralph::supervise::Limits;
+use ralph::{git, record, Error, Result};
+
+#[derive(Debug, Args)]
+pub struct GateArgs {
+    /// Config file with the `[gate]` section
+    #[arg(long, default_value = DEFAULT_CONFIG_PATH, global = true)]
+    config: PathBuf,
+
+    /// Project profile whose `tests.backend` is the default command
+    #[arg(long, global = true)]
+    profile: Option<String>,
+
+    /// Results of the last accepted run
+    #[arg(long, default_value = "ralph/logs/gate.json", global = true)]
+    state: PathBuf,
+
+    #[command(subcommand)]
+    command: GateCommand,
+}
+
+#[derive(Debug, Subcommand)]
+enum GateCommand {
+    /// Run the tests before the first round and keep the result as the
+    /// baseline
+    Baseline {
+        /// File for the full test output
+        #[arg(long)]
+        output: PathBuf,
+    },
+    /// Run the tests after a round and roll the round back if the suite
+    /// regressed against the baseline
+    ///
+    /// A regression is a test that fails now but did not before, more
+    /// failures and errors, or a run without a pytest summary; fewer passing
+    /// tests alone is only reported. Appends a `test_gate` record to every --log and exits with
+    /// status 8 on a regression.
+    Run {
+        /// First iteration of the round
+        #[arg(long)]
+        iteration: u32,
+
+        /// Commit HEAD was at when the round started
+        #[arg(long)]
+        head: String,
+
+        /// Branch the round worked on, for the quarantine branch name
+        #[arg(long)]
+        branch: String,
+
+        /// Iteration log to record the result in (repeatable)
+        #[arg(long = "log")]
+        logs: Vec<PathBuf>,
+
+        /// File for the full test output
+        #[arg(long)]
+        output: PathBuf,
+
+        /// Override `gate.on_regression`
+        #[arg(long, value_enum)]
+        on_regression: Option<OnRegression>,
+    },
+}
+
+pub fn run(args: GateArgs) -> Result<ExitCode> {
+    let config = Config::load_with_profile(&args.config, args.profile.as_deref())?;
+    let command = config.gate_command().map(str::to_string).ok_or_else(|| {
+        Error::Gate("no test command: set gate.command or the project's tests.backend".to_string())
+    })?;
+    let limits = Limits {
+        timeout: (config.gate.timeout > 0).then(|| Duration::from_secs(config.gate.timeout)),
+        stall: None,
+    };
+
+    match args.command {
+        GateCommand::Baseline { output } => {
+            let (_, results) = gate::run(&command, limits, &output)?;
+            print_results("Test baseline", results.as_ref());
+            Baseline {
+                iteration: 0,
+                results,
+            }
+            .save(&args.state)?;
+        }
+        GateCommand::Run {
+            iteration,
+            head,
+            branch,
+            logs,
+            output,
+            on_regression,
+        } => {
+            let (exit_code, results) = gate::run(&command, limits, &output)?;
+            print_results("Test gate", results.as_ref());
+            let baseline = Baseline::load(&args.state)?.and_then(|b| b.results);
+            let (regressions, warnings) = match &baseline {
+                Some(before) => (
+                    gate::regressions(before, results.as_ref()),
+                    gate::warnings(before, results.as_ref()),
+                ),
+                None => (Vec::new(), Vec::new()),
+            };
+            if !warnings.is_empty() {
+                println!("Note: {}", warnings.join("; "));
+            }
+
+            let mut action = Action::None;
+            let mut quarantine = None;
+            if !regressions.is_empty() {
+                println!("Tests regressed: {}", regressions.join("; "));
+                let moved = git::rev_parse("HEAD")?.is_some_and(|h| h != head);
+                match on_regression.unwrap_or(config.gate.on_regression) {
+                    OnRegression::Revert if moved => {
+                        let message = format!(
+                            "ralph: revert iteration {iteration}, tests regressed\n\n{}",
+                            regressions.join("\n")
+                        );
+                        match gate::revert(&head, &message) {
+                            Ok(true) => {
+                                action = Action::Reverted;
+                                println!("Reverted the round's changes since {}", short(&head));
+                            }
+                            Ok(false) => {}
+                            Err(Error::Gate(reason)) => println!("Not reverting: {reason}"),
+                            Err(e) => return Err(e),
+                        }
+                    }
+                    OnRegression::Quarantine if moved => {
+                        let name = format!("ralph/quarantine/{branch}-{iteration}");
+                        match gate::quarantine(&head, &name) {
+                            Ok(()) => {
+                                println!("Moved the round's commits to {name}");
+                                action = Action::Quarantined;
+                                quarantine = Some(name);
+                            }
+                            Err(Error::Gate(reason)) => println!("Not quarantining: {reason}"),
+                            Err(e) => return Err(e),
+                        }
+                    }
+                    OnRegression::Keep => action = Action::Kept,
+                    _ => println!("No commits to roll back"),
+                }
+            }
+            if regressions.is_empty() || action == Action::Kept {
+                Baseline {
+                    iteration,
+                    results: results.clone(),
+                }
+                .save(&args.state)?;
+            }
+
+            let record = Record {
+                command,
+                exit_code,
+                results,
+                baseline,
+                regressions,
+                warnings,
+                action,
+                quarantine,
+                output,
+            };
+            let fields = serde_json::to_value(&record).expect("record serializes");
+            for log in &logs {
+                record::append(log, "test_gate", fields.clone())?;
+            }
+            if !record.regressions.is_empty() {
+                return Ok(ExitCode::from(EXIT_REGRESSED));
+            }
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn print_results(what: &str, results: Option<&Results>) {
+    match results {
+        Some(results) => println!("{what}: {results}"),
+        None => println!("{what}: no pytest summary in the output"),
+    }
+}
+
+fn short(commit: &str) -> &str {
+    &commit[..commit.len().min(8)]
+}
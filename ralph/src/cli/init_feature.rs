@@ -0,0 +1,147 @@
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use clap::Args;
+
+use ralph::config::{self, Config, DEFAULT_CONFIG_PATH};
+use ralph::feature::{self, Feature, DEFAULT_ARCHIVE_DIR};
+use ralph::plan::DEFAULT_PLAN_PATH;
+use ralph::spec::DEFAULT_SPECS_DIR;
+use ralph::{git, timestamp, Error, Result};
+
+/// Start a new feature run.
+///
+/// Moves the current plan and iteration logs to `ARCHIVE/DATE-PREVIOUS/`
+/// (the ledger, gate baseline, claims and other loop state stay in LOGS),
+/// then writes a spec skeleton to `SPECS/SLUG.md`, an empty plan with the
+/// phase and task structure, and `profiles/SLUG.toml` with the feature's
+/// completion promise (`SLUG_COMPLETE`), and selects that profile in the
+/// config. The new profile copies the paths, tests, limits and URLs of the
+/// current one. With `--branch`, the new branch is created after all that,
+/// so the files are carried over to it uncommitted.
+#[derive(Debug, Args)]
+pub struct InitFeatureArgs {
+    /// Feature name, e.g. "Order Export"
+    #[arg(value_parser = parse_name)]
+    name: String,
+
+    /// File and tag name (default: the name in lowercase, dash-separated)
+    #[arg(long, value_parser = parse_name)]
+    slug: Option<String>,
+
+    /// Ticket the feature is tracked under, e.g. AGENT-105
+    #[arg(long)]
+    ticket: Option<String>,
+
+    /// Also create and switch to branch `feature/SLUG`
+    #[arg(long)]
+    branch: bool,
+
+    /// Replace an existing spec or profile of the same name
+    #[arg(long)]
+    force: bool,
+
+    #[arg(long, default_value = DEFAULT_CONFIG_PATH)]
+    config: PathBuf,
+
+    #[arg(long, default_value = DEFAULT_PLAN_PATH)]
+    plan: PathBuf,
+
+    #[arg(long, default_value = DEFAULT_SPECS_DIR)]
+    specs: PathBuf,
+
+    /// Iteration logs of the current run
+    #[arg(long, default_value = "ralph/logs")]
+    logs: PathBuf,
+
+    /// Directory of archived runs
+    #[arg(long, default_value = DEFAULT_ARCHIVE_DIR)]
+    archive: PathBuf,
+}
+
+pub fn run(args: InitFeatureArgs) -> Result<ExitCode> {
+    let slug = feature::slug(args.slug.as_deref().unwrap_or(&args.name));
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let feature = Feature {
+        name: args.name,
+        slug,
+        ticket: args.ticket,
+        date: timestamp::date(now),
+    };
+    let spec_path = args.specs.join(format!("{}.md", feature.slug));
+    let profile_path = config::profile_path(&args.config, &feature.slug);
+    if !args.force {
+        if let Some(path) = [&spec_path, &profile_path].into_iter().find(|p| p.exists()) {
+            return Err(Error::Exists(path.clone()));
+        }
+    }
+    let previous = Config::load_with_profile(&args.config, None)?.project;
+    // The branch is created last, once everything is in place; refuse up
+    // front what `git checkout -b` would refuse then.
+    let branch = args.branch.then(|| format!("feature/{}", feature.slug));
+    if let Some(branch) = &branch {
+        git::run(&["check-ref-format", "--branch", branch])?;
+        if git::rev_parse(&format!("refs/heads/{branch}"))?.is_some() {
+            return Err(Error::Git {
+                command: format!("checkout -b {branch}"),
+                stderr: format!("a branch named '{branch}' already exists"),
+            });
+        }
+    }
+
+    let previous_name = previous
+        .profile
+        .clone()
+        .filter(|name| !name.ends_with(".toml"))
+        .or_else(|| Some(previous.feature.clone()).filter(|f| !feature::slug(f).is_empty()))
+        .unwrap_or_else(|| "previous".to_string());
+    let archive = feature::archive_dir(&args.archive, &feature.date, &previous_name);
+    for moved in feature::archive(&[&args.plan], &archive)? {
+        println!("Archived {}", moved.display());
+    }
+    let logs = feature::archive_logs(&args.logs, &archive)?;
+    if let Some(first) = logs.first() {
+        let dir = first.parent().unwrap_or(&archive);
+        println!(
+            "Archived {} iteration files to {}",
+            logs.len(),
+            dir.display()
+        );
+    }
+
+    feature::write(&spec_path, &feature.spec())?;
+    println!("Created {}", spec_path.display());
+    feature.plan(&spec_path).save(&args.plan)?;
+    println!("Created {}", args.plan.display());
+    feature::write(&profile_path, &feature.profile(&previous))?;
+    println!("Created {}", profile_path.display());
+    config::set_profile(&args.config, &feature.slug)?;
+    println!(
+        "Selected profile {} in {}",
+        feature.slug,
+        args.config.display()
+    );
+    if let Some(branch) = &branch {
+        git::run(&["checkout", "--quiet", "-b", branch])?;
+        println!("Switched to new branch {branch}");
+    }
+    println!(
+        "Completion promise: <promise>{}</promise>",
+        feature.promise()
+    );
+    println!(
+        "Next: describe the feature in {}, then run ./ralph/loop.sh plan",
+        spec_path.display()
+    );
+    Ok(ExitCode::SUCCESS)
+}
+
+fn parse_name(text: &str) -> Result<String, String> {
+    if feature::slug(text).is_empty() {
+        return Err("needs at least one letter or digit".to_string());
+    }
+    Ok(text.to_string())
+}
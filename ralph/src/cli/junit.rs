@@ -0,0 +1,126 @@
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::{Args, Subcommand};
+
+use ralph::junit::{self, Diff, Results};
+use ralph::{record, Result};
+
+#[derive(Debug, Args)]
+pub struct JunitArgs {
+    #[command(subcommand)]
+    command: JunitCommand,
+}
+
+#[derive(Debug, Subcommand)]
+enum JunitCommand {
+    /// Store an iteration's reports as LOGS/tests_N.json
+    ///
+    /// The results are compared with the last known outcome of every test in
+    /// the earlier iterations: newly failing, newly passing, added, and not
+    /// run this time. With
+    /// --log, a `test_results` record with the counts per suite and the diff
+    /// is appended to the iteration log. Does nothing when there are no
+    /// reports.
+    Ingest {
+        #[arg(long)]
+        iteration: u32,
+
+        /// Iteration log to record the results in
+        #[arg(long)]
+        log: Option<PathBuf>,
+
+        /// Loop run the iteration belongs to; only its earlier iterations
+        /// are compared with
+        #[arg(long)]
+        run: Option<String>,
+
+        /// Directory for tests_N.json, next to the iteration logs
+        #[arg(long, default_value = "ralph/logs")]
+        logs: PathBuf,
+
+        /// JUnit XML reports, or directories of them
+        #[arg(required = true)]
+        reports: Vec<PathBuf>,
+    },
+    /// Print the "Unit Tests" table of the verification report
+    Table {
+        /// JUnit XML reports, or directories of them
+        #[arg(required = true)]
+        reports: Vec<PathBuf>,
+    },
+}
+
+pub fn run(args: JunitArgs) -> Result<ExitCode> {
+    match args.command {
+        JunitCommand::Ingest {
+            iteration,
+            log,
+            run,
+            logs,
+            reports,
+        } => {
+            let suites = junit::load(&reports)?;
+            if suites.is_empty() {
+                println!("No JUnit reports with tests");
+                return Ok(ExitCode::SUCCESS);
+            }
+            let earlier = junit::earlier(&logs, iteration, run.as_deref())?;
+            let diff = Diff::between(&earlier, &suites);
+            let results = Results {
+                iteration,
+                run,
+                suites,
+                diff,
+            };
+            let path = junit::results_path(&logs, iteration);
+            results.save(&path)?;
+            println!(
+                "Test results: {} in {} suites ({})",
+                results.counts(),
+                results.suites.len(),
+                path.display()
+            );
+            if let Some(diff) = &results.diff {
+                print_diff(&results, diff);
+            }
+            if let Some(log) = log {
+                let fields =
+                    serde_json::to_value(results.record(&path)).expect("record serializes");
+                record::append(&log, "test_results", fields)?;
+            }
+        }
+        JunitCommand::Table { reports } => {
+            print!("{}", junit::table(&junit::load(&reports)?));
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn print_diff(results: &Results, diff: &Diff) {
+    if diff.is_empty() {
+        println!("No changes since iteration {}", diff.previous);
+        return;
+    }
+    println!(
+        "Since iteration {}: {} newly failing, {} newly passing, {} added ({} earlier tests not run)",
+        diff.previous,
+        diff.newly_failing.len(),
+        diff.newly_passing.len(),
+        diff.added.len(),
+        diff.not_run.len()
+    );
+    for id in &diff.newly_failing {
+        let message = results
+            .cases()
+            .find(|c| &c.id == id)
+            .and_then(|c| c.message.as_deref());
+        match message {
+            Some(message) => println!("  newly failing: {id} - {message}"),
+            None => println!("  newly failing: {id}"),
+        }
+    }
+    for id in &diff.newly_passing {
+        println!("  newly passing: {id}");
+    }
+}
@@ -0,0 +1,151 @@
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use clap::{Args, Subcommand};
+use serde_json::json;
+
+use ralph::ledger::{Budget, Entry, Ledger, Scope, EXIT_OVER_BUDGET};
+use ralph::{record, stream, Result};
+
+#[derive(Debug, Args)]
+pub struct LedgerArgs {
+    /// Ledger file
+    #[arg(long, default_value = "ralph/logs/ledger.json", global = true)]
+    file: PathBuf,
+
+    #[command(subcommand)]
+    command: LedgerCommand,
+}
+
+#[derive(Debug, Subcommand)]
+enum LedgerCommand {
+    /// Add an iteration's usage and cost, then check the budget
+    ///
+    /// Exits with status 5 once the spend of the budget scope exceeds
+    /// --max-usd or --max-tokens, after appending a `budget_exceeded`
+    /// record to the iteration log.
+    Add {
+        /// Iteration log to read the final result message from
+        #[arg(long)]
+        log: PathBuf,
+
+        /// Identifier of the loop run
+        #[arg(long)]
+        run: String,
+
+        #[arg(long)]
+        branch: String,
+
+        #[arg(long)]
+        iteration: u32,
+
+        /// Dollar budget (0 disables)
+        #[arg(long, default_value_t = 0.0)]
+        max_usd: f64,
+
+        /// Token budget, counting cache reads and writes (0 disables)
+        #[arg(long, default_value_t = 0)]
+        max_tokens: u64,
+
+        /// Spend the budget is measured against
+        #[arg(long, value_enum, default_value_t = Scope::Run)]
+        scope: Scope,
+    },
+    /// Print the spend of a run and/or branch (everything when neither is given)
+    Show {
+        #[arg(long)]
+        run: Option<String>,
+
+        #[arg(long)]
+        branch: Option<String>,
+
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+pub fn run(args: LedgerArgs) -> Result<ExitCode> {
+    let mut ledger = Ledger::load(&args.file)?;
+
+    match args.command {
+        LedgerCommand::Add {
+            log,
+            run,
+            branch,
+            iteration,
+            max_usd,
+            max_tokens,
+            scope,
+        } => {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs());
+            let entry = Entry::from_events(&run, &branch, iteration, now, &stream::read(&log)?);
+            if !entry.complete {
+                println!("No result message in {}; spend unknown", log.display());
+            }
+            ledger.add(entry);
+            ledger.save(&args.file)?;
+
+            let run_spend = ledger.run_spend(&run);
+            let branch_spend = ledger.branch_spend(&branch);
+            println!("Run spend:    {run_spend}");
+            println!("Branch spend: {branch_spend}");
+
+            let budget = Budget {
+                max_usd: (max_usd > 0.0).then_some(max_usd),
+                max_tokens: (max_tokens > 0).then_some(max_tokens),
+            };
+            let spend = match scope {
+                Scope::Run => run_spend,
+                Scope::Branch => branch_spend,
+            };
+            if let Some(reason) = budget.exceeded_by(&spend) {
+                println!("Budget exceeded: {reason}");
+                record::append(
+                    &log,
+                    "budget_exceeded",
+                    json!({ "scope": scope, "reason": reason, "spend": spend }),
+                )?;
+                return Ok(ExitCode::from(EXIT_OVER_BUDGET));
+            }
+        }
+        LedgerCommand::Show { run, branch, json } => {
+            let mut spends = Vec::new();
+            if let Some(run) = &run {
+                spends.push(("run", run.as_str(), ledger.run_spend(run)));
+            }
+            if let Some(branch) = &branch {
+                spends.push(("branch", branch.as_str(), ledger.branch_spend(branch)));
+            }
+            if spends.is_empty() {
+                let mut branches: Vec<&str> =
+                    ledger.entries.iter().map(|e| e.branch.as_str()).collect();
+                branches.sort_unstable();
+                branches.dedup();
+                for branch in branches {
+                    spends.push(("branch", branch, ledger.branch_spend(branch)));
+                }
+            }
+
+            if json {
+                let out: Vec<_> = spends
+                    .iter()
+                    .map(|(scope, name, spend)| {
+                        json!({ "scope": scope, "name": name, "spend": spend, "tokens": spend.tokens() })
+                    })
+                    .collect();
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&out).expect("ledger serializes")
+                );
+            } else {
+                for (scope, name, spend) in spends {
+                    println!("{scope} {name}: {spend}");
+                }
+            }
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
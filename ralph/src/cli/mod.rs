@@ -0,0 +1,108 @@
+//! Command-line interface used by `loop.sh`.
+
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand};
+
+use ralph::Result;
+
+mod archon;
+mod breaker;
+mod burndown;
+mod claim;
+mod config;
+mod events;
+mod gate;
+mod init_feature;
+mod junit;
+mod ledger;
+mod model;
+mod plan;
+mod promise;
+mod prompt;
+mod push;
+mod record;
+mod report;
+mod supervise;
+mod task;
+mod trace;
+mod worktree;
+
+#[derive(Debug, Parser)]
+#[command(name = "ralph", about = "Helpers for the Ralph Wiggum loop", version)]
+pub struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Local stand-in for the Archon task server
+    Archon(archon::ArchonArgs),
+    /// Track consecutive failed iterations
+    Breaker(breaker::BreakerArgs),
+    /// Chart plan progress over the plan's git history
+    Burndown(burndown::BurndownArgs),
+    /// Claim plan tasks for parallel workers
+    Claim(claim::ClaimArgs),
+    /// Read settings from ralph.toml
+    Config(config::ConfigArgs),
+    /// Decode an iteration log into typed events
+    Events(events::EventsArgs),
+    /// Run the tests after a round and roll back regressions
+    Gate(gate::GateArgs),
+    /// Start a new feature run: spec, empty plan, profile and archive
+    InitFeature(init_feature::InitFeatureArgs),
+    /// Read JUnit XML test reports and diff them against the last iteration
+    Junit(junit::JunitArgs),
+    /// Track token and dollar spend against a budget
+    Ledger(ledger::LedgerArgs),
+    /// Pick the model for the next iteration, falling back when throttled
+    Model(model::ModelArgs),
+    /// Query and update IMPLEMENTATION_PLAN.md
+    Plan(plan::PlanArgs),
+    /// Detect a completion promise in an iteration log
+    Promise(promise::PromiseArgs),
+    /// Render a prompt template with the loop's variables
+    Prompt(prompt::PromptArgs),
+    /// Push new commits according to the push policy
+    Push(push::PushArgs),
+    /// Append a loop record to an iteration log
+    Record(record::RecordArgs),
+    /// Summarize every iteration log of a run
+    Report(report::ReportArgs),
+    /// Run one iteration under a timeout and stall detector
+    Supervise(supervise::SuperviseArgs),
+    /// Start plan tasks and mark them from each iteration's outcome
+    Task(task::TaskArgs),
+    /// Trace spec requirements to the plan tasks that cover them
+    Trace(trace::TraceArgs),
+    /// Run iterations in scratch worktrees and merge back on success
+    Worktree(worktree::WorktreeArgs),
+}
+
+pub fn run(cli: Cli) -> Result<ExitCode> {
+    match cli.command {
+        Command::Archon(args) => archon::run(args),
+        Command::Breaker(args) => breaker::run(args),
+        Command::Burndown(args) => burndown::run(args),
+        Command::Claim(args) => claim::run(args),
+        Command::Config(args) => config::run(args),
+        Command::Events(args) => events::run(args),
+        Command::Gate(args) => gate::run(args),
+        Command::InitFeature(args) => init_feature::run(args),
+        Command::Junit(args) => junit::run(args),
+        Command::Ledger(args) => ledger::run(args),
+        Command::Model(args) => model::run(args),
+        Command::Plan(args) => plan::run(args),
+        Command::Promise(args) => promise::run(args),
+        Command::Prompt(args) => prompt::run(args),
+        Command::Push(args) => push::run(args),
+        Command::Record(args) => record::run(args),
+        Command::Report(args) => report::run(args),
+        Command::Supervise(args) => supervise::run(args),
+        Command::Task(args) => task::run(args),
+        Command::Trace(args) => trace::run(args),
+        Command::Worktree(args) => worktree::run(args),
+    }
+}
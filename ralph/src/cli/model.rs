@@ -0,0 +1,59 @@
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::Args;
+use serde_json::json;
+
+use ralph::config::{Config, Mode, DEFAULT_CONFIG_PATH};
+use ralph::model::Selection;
+use ralph::{record, stream, Result};
+
+/// Print the model the next iteration should use.
+///
+/// With --log, first account for the iteration that just finished: a
+/// rate-limit or overload error in the log moves to the next fallback model
+/// and appends a `model_fallback` record to the log. Notices go to stderr so
+/// stdout holds only the model name.
+#[derive(Debug, Args)]
+pub struct ModelArgs {
+    /// Config file (defaults apply when it does not exist)
+    #[arg(long, default_value = DEFAULT_CONFIG_PATH)]
+    config: PathBuf,
+
+    /// File keeping the current fallback between invocations
+    #[arg(long, default_value = "ralph/logs/model.json")]
+    state: PathBuf,
+
+    #[arg(long, value_enum)]
+    mode: Mode,
+
+    /// Iteration log of the iteration that just finished
+    #[arg(long)]
+    log: Option<PathBuf>,
+
+    /// Forget any fallback and start over on the mode's own model
+    #[arg(long, conflicts_with = "log")]
+    reset: bool,
+}
+
+pub fn run(args: ModelArgs) -> Result<ExitCode> {
+    let config = Config::load(&args.config)?;
+    let mut selection = if args.reset {
+        Selection::default()
+    } else {
+        Selection::load(&args.state)?
+    };
+
+    if let Some(log) = &args.log {
+        if let Some(switch) = selection.observe(&config, args.mode, &stream::read(log)?) {
+            eprintln!(
+                "Switching model {} -> {} ({})",
+                switch.from, switch.to, switch.reason
+            );
+            record::append(log, "model_fallback", json!(switch))?;
+        }
+    }
+    selection.save(&args.state)?;
+    println!("{}", selection.model(&config, args.mode));
+    Ok(ExitCode::SUCCESS)
+}
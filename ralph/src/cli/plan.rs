@@ -0,0 +1,346 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use clap::{Args, Subcommand, ValueEnum};
+
+use serde_json::json;
+
+use ralph::plan::{
+    code_changed, Graph, Node, Plan, Progress, Severity, Snapshot, Status, Task, DEFAULT_PLAN_PATH,
+    EXIT_NO_PROGRESS,
+};
+use ralph::{git, record, Result};
+
+#[derive(Debug, Args)]
+pub struct PlanArgs {
+    /// Path to the implementation plan
+    #[arg(long, default_value = DEFAULT_PLAN_PATH, global = true)]
+    file: PathBuf,
+
+    #[command(subcommand)]
+    command: PlanCommand,
+}
+
+#[derive(Debug, Subcommand)]
+enum PlanCommand {
+    /// Print phases and tasks with their status
+    Show {
+        /// Emit the parsed plan as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Print the next task that is ready to start
+    ///
+    /// That is the first task that is neither done nor blocked and whose
+    /// phase and `depends:` dependencies are done. Exits with status 1 when
+    /// no such task remains.
+    Next {
+        #[arg(long)]
+        json: bool,
+        /// Ignore dependencies: any task that is neither done nor blocked
+        #[arg(long)]
+        ignore_deps: bool,
+    },
+    /// Write the status of every task to a snapshot file
+    ///
+    /// A missing plan yields an empty snapshot, so planning mode can start
+    /// from scratch.
+    Snapshot {
+        /// Snapshot file to write
+        #[arg(long)]
+        out: PathBuf,
+    },
+    /// Compare the plan against an earlier snapshot
+    ///
+    /// Prints every task that moved and exits with status 4 when none did.
+    Progress {
+        /// Snapshot taken before the iteration
+        #[arg(long)]
+        since: PathBuf,
+        /// Commit the iteration started from (`none` for an unborn HEAD).
+        /// Files changed since then, other than the plan and the loop's
+        /// state, count as code changes
+        #[arg(long, value_name = "REV")]
+        changed_since: Option<String>,
+        /// Directory holding the loop's logs, archive and Archon store
+        /// [default: the plan's directory]
+        #[arg(long)]
+        state_dir: Option<PathBuf>,
+        /// Iteration log to append a `plan_progress` record to
+        #[arg(long)]
+        log: Option<PathBuf>,
+    },
+    /// Build the task dependency graph from `depends:` notes
+    ///
+    /// Reports cycles and references to missing tasks, the tasks that are
+    /// ready to start and the critical path. Exits with status 1 when the
+    /// graph has a cycle or a missing reference.
+    Graph {
+        #[arg(long, value_enum, default_value_t = GraphFormat::Text)]
+        format: GraphFormat,
+    },
+    /// Check the plan against the planning conventions
+    ///
+    /// Exits with status 1 when there is an error, or with `--strict` any
+    /// finding at all.
+    Lint {
+        /// Emit the findings as JSON
+        #[arg(long)]
+        json: bool,
+        /// Fail on warnings too
+        #[arg(long)]
+        strict: bool,
+    },
+    /// Recompute the Progress Summary table from the checkboxes
+    ///
+    /// Rewrites only the table and the Overall Progress line. With `--check`
+    /// the plan is left alone and the command exits with status 1 when the
+    /// summary disagrees with the tasks.
+    Summary {
+        #[arg(long)]
+        check: bool,
+    },
+    /// Set the checkbox status of a task and write the plan back
+    Set {
+        /// Task number, e.g. `1.3`
+        id: String,
+        /// todo, done, in-progress, doing, blocked (or the marker itself)
+        status: Status,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum GraphFormat {
+    /// Problems, ready tasks and critical path
+    Text,
+    /// Graphviz DOT
+    Dot,
+    /// Mermaid flowchart
+    Mermaid,
+    Json,
+}
+
+pub fn run(args: PlanArgs) -> Result<ExitCode> {
+    // Planning mode may create the plan from scratch, so snapshots treat a
+    // missing file as an empty plan; everything else needs it to exist.
+    let tolerate_missing = matches!(
+        args.command,
+        PlanCommand::Snapshot { .. } | PlanCommand::Progress { .. }
+    );
+    let mut plan = if tolerate_missing && !args.file.exists() {
+        Plan::parse("")
+    } else {
+        Plan::load(&args.file)?
+    };
+
+    match args.command {
+        PlanCommand::Show { json: true } => print_json(&plan),
+        PlanCommand::Show { json: false } => {
+            for phase in &plan.phases {
+                let status = phase.status.map(|s| s.to_string()).unwrap_or_default();
+                println!(
+                    "Phase {}: {} {} ({}/{})",
+                    phase.number,
+                    phase.title,
+                    status,
+                    phase.done_count(),
+                    phase.tasks.len()
+                );
+                for task in &phase.tasks {
+                    println!("  {}", task_line(task));
+                }
+            }
+        }
+        PlanCommand::Next { json, ignore_deps } => {
+            let task = if ignore_deps {
+                plan.first_open_task()
+            } else {
+                plan.next_task()
+            };
+            match task {
+                Some(task) if json => print_json(task),
+                Some(task) => println!("{}", task_line(task)),
+                None => return Ok(ExitCode::FAILURE),
+            }
+        }
+        PlanCommand::Snapshot { out } => Snapshot::of(&plan).save(&out)?,
+        PlanCommand::Progress {
+            since,
+            changed_since,
+            state_dir,
+            log,
+        } => {
+            let moved = Snapshot::load(&since)?.transitions(&Snapshot::of(&plan));
+            let changed = match changed_since {
+                Some(rev) => {
+                    let state_dir = state_dir.unwrap_or_else(|| {
+                        let dir = args.file.parent().filter(|d| !d.as_os_str().is_empty());
+                        dir.unwrap_or(Path::new(".")).to_path_buf()
+                    });
+                    code_changed(
+                        &git::changed_files(&rev)?,
+                        &absolute(&args.file),
+                        &absolute(&state_dir),
+                    )
+                }
+                None => false,
+            };
+            let progress = Progress::classify(&moved, changed);
+            for transition in &moved {
+                println!("{transition}");
+            }
+            println!("{progress}");
+            if let Some(log) = log {
+                record::append(
+                    &log,
+                    "plan_progress",
+                    json!({ "progress": progress, "moved": moved }),
+                )?;
+            }
+            if progress.is_spin() {
+                return Ok(ExitCode::from(EXIT_NO_PROGRESS));
+            }
+        }
+        PlanCommand::Graph { format } => {
+            let graph = Graph::of(&plan);
+            match format {
+                GraphFormat::Text => print_graph(&graph),
+                GraphFormat::Dot => print!("{}", graph.to_dot()),
+                GraphFormat::Mermaid => print!("{}", graph.to_mermaid()),
+                GraphFormat::Json => {
+                    let ids = |nodes: Vec<&Node>| -> Vec<String> {
+                        nodes.into_iter().map(|n| n.id.clone()).collect()
+                    };
+                    print_json(&json!({
+                        "nodes": graph.nodes,
+                        "edges": graph.edges,
+                        "unknown": graph.unknown,
+                        "cycles": graph.cycles,
+                        "ready": ids(graph.ready()),
+                        "critical_path": ids(graph.critical_path()),
+                    }))
+                }
+            }
+            if !graph.is_valid() {
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+        PlanCommand::Lint { json, strict } => {
+            let findings = plan.lint();
+            let errors = findings
+                .iter()
+                .filter(|f| f.severity == Severity::Error)
+                .count();
+            let warnings = findings.len() - errors;
+            if json {
+                print_json(&json!({
+                    "file": args.file,
+                    "errors": errors,
+                    "warnings": warnings,
+                    "findings": findings,
+                }));
+            } else {
+                for finding in &findings {
+                    println!(
+                        "{}:{}: {}[{}] {}",
+                        args.file.display(),
+                        finding.line,
+                        finding.severity,
+                        finding.rule,
+                        finding.message
+                    );
+                }
+                println!("{errors} error(s), {warnings} warning(s)");
+            }
+            if errors > 0 || (strict && warnings > 0) {
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+        PlanCommand::Summary { check } => {
+            let (removed, added) = plan.update_summary();
+            if removed.is_empty() && added.is_empty() {
+                println!("Progress Summary is up to date");
+                return Ok(ExitCode::SUCCESS);
+            }
+            if check {
+                println!("Progress Summary disagrees with the tasks:");
+            } else {
+                plan.save(&args.file)?;
+                println!("Progress Summary updated:");
+            }
+            for line in &removed {
+                println!("- {line}");
+            }
+            for line in &added {
+                println!("+ {line}");
+            }
+            if check {
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+        PlanCommand::Set { id, status } => {
+            plan.set_status(&id, status)?;
+            plan.save(&args.file)?;
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn task_line(task: &Task) -> String {
+    match &task.id {
+        Some(id) => format!("{} {} {}", task.status, id, task.title),
+        None => format!("{} {}", task.status, task.title),
+    }
+}
+
+fn print_graph(graph: &Graph) {
+    println!(
+        "{} task(s), {} dependenc{}",
+        graph.nodes.len(),
+        graph.edges.len(),
+        if graph.edges.len() == 1 { "y" } else { "ies" }
+    );
+    for unknown in &graph.unknown {
+        println!(
+            "Unknown: {} depends on {}, which matches no task",
+            unknown.task, unknown.reference
+        );
+    }
+    for cycle in &graph.cycles {
+        println!("Cycle: {} -> {}", cycle.join(" -> "), cycle[0]);
+    }
+    let list = |title: &str, nodes: Vec<&Node>| {
+        println!("{title} ({}):", nodes.len());
+        for node in nodes {
+            println!("  {} {} {}", node.status, node.id, node.title);
+        }
+    };
+    list("Ready", graph.ready());
+    if graph.cycles.is_empty() {
+        list("Critical path", graph.critical_path());
+    }
+}
+
+fn print_json<T: serde::Serialize>(value: &T) {
+    println!(
+        "{}",
+        serde_json::to_string_pretty(value).expect("plan model serializes")
+    );
+}
+
+/// `path` made absolute with symlinks resolved, as git reports paths. A
+/// file that does not exist yet is resolved through its directory.
+fn absolute(path: &Path) -> PathBuf {
+    if let Ok(path) = fs::canonicalize(path) {
+        return path;
+    }
+    let parent = path.parent().filter(|p| !p.as_os_str().is_empty());
+    match (
+        fs::canonicalize(parent.unwrap_or(Path::new("."))),
+        path.file_name(),
+    ) {
+        (Ok(dir), Some(name)) => dir.join(name),
+        _ => path.to_path_buf(),
+    }
+}
@@ -0,0 +1,30 @@
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::Args;
+
+use ralph::promise::{self, Promise};
+use ralph::Result;
+
+/// Print the first watched promise found in the log and exit with its code.
+///
+/// Exits 0 with no output when none of the promises was emitted.
+#[derive(Debug, Args)]
+pub struct PromiseArgs {
+    /// stream-json log of one iteration
+    log: PathBuf,
+
+    /// Promise to watch for, as TAG=EXIT_CODE (repeatable)
+    #[arg(long = "promise", value_name = "TAG=CODE", required = true)]
+    promises: Vec<Promise>,
+}
+
+pub fn run(args: PromiseArgs) -> Result<ExitCode> {
+    match promise::scan(&args.log, &args.promises)? {
+        Some(found) => {
+            println!("{}", found.tag);
+            Ok(ExitCode::from(found.exit_code))
+        }
+        None => Ok(ExitCode::SUCCESS),
+    }
+}
@@ -0,0 +1,129 @@
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use clap::Args;
+
+use ralph::config::{self, Config, Mode, DEFAULT_CONFIG_PATH};
+use ralph::plan::{Plan, DEFAULT_PLAN_PATH};
+use ralph::prompt::{self, Vars};
+use ralph::{Error, Result};
+
+/// Render a prompt template with the loop's variables and print it.
+///
+/// Variables: `mode`, `plan`, `ralph` (this binary, unless given with --var),
+/// and from the project profile (`[project]` in
+/// the config, or --profile) `feature`, `promise` (when the mode has one),
+/// `paths.NAME`, `tests.NAME`, `limits.NAME`, `urls.NAME` and `includes.NAME`;
+/// `iteration`, `branch` and `model` when given; `task.id` and `task.title`
+/// (empty without --task).
+/// `{{> FILE }}` includes FILE, relative to the including template, and
+/// `{{> includes.NAME }}` the profile's file of that name.
+#[derive(Debug, Args)]
+pub struct PromptArgs {
+    /// Template, e.g. ralph/PROMPT_unified.md
+    template: PathBuf,
+
+    /// Config file with the `[project]` section
+    #[arg(long, default_value = DEFAULT_CONFIG_PATH)]
+    config: PathBuf,
+
+    /// Project profile: `profiles/NAME.toml` next to the config, or a path
+    #[arg(long)]
+    profile: Option<String>,
+
+    #[arg(long, default_value = DEFAULT_PLAN_PATH)]
+    plan: PathBuf,
+
+    #[arg(long, value_enum)]
+    mode: Mode,
+
+    #[arg(long)]
+    iteration: Option<u32>,
+
+    #[arg(long)]
+    branch: Option<String>,
+
+    #[arg(long)]
+    model: Option<String>,
+
+    /// Task assigned to the iteration
+    #[arg(long)]
+    task: Option<String>,
+
+    /// Extra or overriding variable (repeatable)
+    #[arg(long = "var", value_name = "NAME=VALUE", value_parser = parse_var)]
+    vars: Vec<(String, String)>,
+}
+
+pub fn run(args: PromptArgs) -> Result<ExitCode> {
+    let project = Config::load_with_profile(&args.config, args.profile.as_deref())?.project;
+    let base = match &project.profile {
+        Some(name) => config::profile_path(&args.config, name),
+        None => args.config.clone(),
+    };
+    let base = base.parent().unwrap_or(Path::new("."));
+    let mut vars = Vars::new();
+    vars.insert("mode".into(), args.mode.to_string());
+    vars.insert("feature".into(), project.feature);
+    if let Some(promise) = project.promises.for_mode(args.mode) {
+        vars.insert("promise".into(), promise.to_string());
+    }
+    vars.insert("plan".into(), args.plan.display().to_string());
+    let ralph =
+        std::env::current_exe().map_or_else(|_| "ralph".into(), |p| p.display().to_string());
+    vars.insert("ralph".into(), ralph);
+    for (name, path) in project.paths {
+        vars.insert(format!("paths.{name}"), path);
+    }
+    for (name, command) in project.tests {
+        vars.insert(format!("tests.{name}"), command);
+    }
+    for (name, limit) in project.limits {
+        vars.insert(format!("limits.{name}"), limit.to_string());
+    }
+    for (name, url) in project.urls {
+        vars.insert(format!("urls.{name}"), url);
+    }
+    for (name, file) in project.includes {
+        let file = if file.is_empty() {
+            file
+        } else {
+            base.join(file).display().to_string()
+        };
+        vars.insert(format!("includes.{name}"), file);
+    }
+    if let Some(iteration) = args.iteration {
+        vars.insert("iteration".into(), iteration.to_string());
+    }
+    if let Some(branch) = args.branch {
+        vars.insert("branch".into(), branch);
+    }
+    if let Some(model) = args.model {
+        vars.insert("model".into(), model);
+    }
+
+    let (id, title) = match &args.task {
+        Some(label) => {
+            let plan = Plan::load(&args.plan)?;
+            let task = plan
+                .tasks()
+                .find(|t| t.label() == label)
+                .ok_or_else(|| Error::UnknownTask(label.clone()))?;
+            (label.clone(), task.title.clone())
+        }
+        None => (String::new(), String::new()),
+    };
+    vars.insert("task.id".into(), id);
+    vars.insert("task.title".into(), title);
+    vars.extend(args.vars);
+
+    print!("{}", prompt::render(&args.template, &vars)?);
+    Ok(ExitCode::SUCCESS)
+}
+
+fn parse_var(text: &str) -> Result<(String, String), String> {
+    let (name, value) = text
+        .split_once('=')
+        .ok_or_else(|| format!("`{text}` is not NAME=VALUE"))?;
+    Ok((name.trim().to_string(), value.to_string()))
+}
@@ -0,0 +1,92 @@
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::Args;
+use serde_json::json;
+
+use ralph::config::{Config, DEFAULT_CONFIG_PATH};
+use ralph::push::{self, Moment, Policy, EXIT_PUSH_FAILED};
+use ralph::{record, Error, Result};
+
+/// Push new local commits if the push policy allows it at this point.
+///
+/// Refuses protected branches, skips when HEAD is already on the remote,
+/// and exits with status 6 (after printing git's error and appending a
+/// `push_failed` record) when the push itself fails.
+#[derive(Debug, Args)]
+pub struct PushArgs {
+    /// Config file with the `[push]` section
+    #[arg(long, default_value = DEFAULT_CONFIG_PATH)]
+    config: PathBuf,
+
+    /// Point in the loop the push is considered at
+    #[arg(long, value_enum)]
+    moment: Moment,
+
+    /// Branch to push
+    #[arg(long)]
+    branch: String,
+
+    /// Override the configured policy
+    #[arg(long, value_enum)]
+    policy: Option<Policy>,
+
+    /// Iteration log to append a push record to (repeatable)
+    #[arg(long = "log")]
+    logs: Vec<PathBuf>,
+}
+
+pub fn run(args: PushArgs) -> Result<ExitCode> {
+    let config = Config::load(&args.config)?.push;
+    let policy = args.policy.unwrap_or(config.policy);
+    let remote = config.remote.as_str();
+    let branch = args.branch.as_str();
+    let log = |event: &str, fields: serde_json::Value| {
+        args.logs
+            .iter()
+            .try_for_each(|log| record::append(log, event, fields.clone()))
+    };
+
+    if !policy.applies(args.moment) {
+        return Ok(ExitCode::SUCCESS);
+    }
+    if branch.is_empty() {
+        println!("Not pushing: HEAD is detached");
+        return Ok(ExitCode::SUCCESS);
+    }
+    if push::is_protected(branch, &config.protected) {
+        println!("Not pushing: {branch} is a protected branch");
+        log(
+            "push_refused",
+            json!({ "remote": remote, "branch": branch }),
+        )?;
+        return Ok(ExitCode::SUCCESS);
+    }
+    let Some(pending) = push::pending(remote, branch)? else {
+        println!("Nothing to push: {branch} is up to date with {remote}");
+        return Ok(ExitCode::SUCCESS);
+    };
+
+    println!(
+        "Pushing {} commit(s) to {remote}/{branch} (policy: {policy})",
+        pending.commits
+    );
+    match push::push(remote, branch, &pending) {
+        Ok(()) => {
+            log(
+                "push",
+                json!({ "remote": remote, "branch": branch, "pending": pending }),
+            )?;
+            Ok(ExitCode::SUCCESS)
+        }
+        Err(Error::Git { stderr, .. }) => {
+            eprintln!("Push to {remote}/{branch} failed:\n{stderr}");
+            log(
+                "push_failed",
+                json!({ "remote": remote, "branch": branch, "error": stderr }),
+            )?;
+            Ok(ExitCode::from(EXIT_PUSH_FAILED))
+        }
+        Err(e) => Err(e),
+    }
+}
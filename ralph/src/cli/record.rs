@@ -0,0 +1,39 @@
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::Args;
+use serde_json::{Map, Value};
+
+use ralph::{record, Result};
+
+/// Append a `{"type": "ralph", "event": EVENT, ...}` line to an iteration log.
+///
+/// Field values that parse as JSON (numbers, booleans, ...) are stored as
+/// such; anything else is stored as a string.
+#[derive(Debug, Args)]
+pub struct RecordArgs {
+    /// Iteration log to append to
+    #[arg(long)]
+    log: PathBuf,
+
+    /// Event name, e.g. iteration_start
+    event: String,
+
+    /// Fields as key=value
+    #[arg(value_parser = parse_field)]
+    fields: Vec<(String, Value)>,
+}
+
+pub fn run(args: RecordArgs) -> Result<ExitCode> {
+    let fields: Map<String, Value> = args.fields.into_iter().collect();
+    record::append(&args.log, &args.event, Value::Object(fields))?;
+    Ok(ExitCode::SUCCESS)
+}
+
+fn parse_field(arg: &str) -> Result<(String, Value), String> {
+    let (key, value) = arg
+        .split_once('=')
+        .ok_or_else(|| format!("expected key=value, got `{arg}`"))?;
+    let value = serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.to_string()));
+    Ok((key.to_string(), value))
+}
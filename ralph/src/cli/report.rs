@@ -0,0 +1,148 @@
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::Args;
+use serde_json::json;
+
+use ralph::gate::{self, Action};
+use ralph::junit;
+use ralph::report::{self, IterationSummary, Totals};
+use ralph::{timestamp, Result};
+
+/// Print a per-iteration table for a run, with totals.
+#[derive(Debug, Args)]
+pub struct ReportArgs {
+    /// Directory holding iteration_N.log files
+    #[arg(long, default_value = "ralph/logs")]
+    logs: PathBuf,
+
+    /// Loop run to report on (default: the one that started last)
+    #[arg(long)]
+    run: Option<String>,
+
+    /// Emit iterations and totals as JSON
+    #[arg(long)]
+    json: bool,
+}
+
+const HEADERS: [&str; 14] = [
+    "#", "Mode", "Model", "Start", "End", "Duration", "Turns", "Tools", "Files", "Tests", "Gate",
+    "Commits", "Promise", "Status",
+];
+
+pub fn run(args: ReportArgs) -> Result<ExitCode> {
+    let summaries = report::load_run(&args.logs, args.run.as_deref())?;
+    let totals = Totals::of(&summaries);
+
+    if args.json {
+        let out = json!({ "iterations": summaries, "totals": totals });
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&out).expect("report serializes")
+        );
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    let rows: Vec<[String; 14]> = summaries.iter().map(row).collect();
+    let widths: Vec<usize> = (0..HEADERS.len())
+        .map(|col| {
+            rows.iter()
+                .map(|r| r[col].chars().count())
+                .chain([HEADERS[col].len()])
+                .max()
+                .unwrap_or_default()
+        })
+        .collect();
+    let print_row = |cells: &[String]| {
+        let line: Vec<String> = cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{cell:<width$}"))
+            .collect();
+        println!("{}", line.join("  ").trim_end());
+    };
+
+    print_row(&HEADERS.map(str::to_string));
+    for r in &rows {
+        print_row(r);
+    }
+
+    println!();
+    println!(
+        "Totals: {} iterations, {}, {} turns, ${:.2}, {} tool calls, {} files edited, \
+         {} test runs ({} failed), {} commits, {} with errors",
+        totals.iterations,
+        timestamp::duration(totals.duration_secs),
+        totals.turns,
+        totals.cost_usd,
+        totals.tools.values().sum::<usize>(),
+        totals.files_edited,
+        totals.tests_run,
+        totals.tests_failed,
+        totals.commits,
+        totals.errors
+    );
+    if !totals.tools.is_empty() {
+        println!("Tools:  {}", tool_counts(&totals.tools));
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn row(s: &IterationSummary) -> [String; 14] {
+    let or_dash = |value: Option<String>| value.unwrap_or_else(|| "-".to_string());
+    let failed = s.tests.iter().filter(|t| !t.passed).count();
+    [
+        s.iteration.to_string(),
+        or_dash(s.mode.clone()),
+        or_dash(s.model.clone()),
+        or_dash(s.started_at.map(timestamp::datetime)),
+        or_dash(s.ended_at.map(timestamp::datetime)),
+        or_dash(s.duration_secs.map(timestamp::duration)),
+        or_dash(s.turns.map(|t| t.to_string())),
+        or_dash((!s.tools.is_empty()).then(|| tool_counts(&s.tools))),
+        s.files_edited.len().to_string(),
+        match (&s.junit, s.tests.len(), failed) {
+            (Some(junit), _, _) => junit_cell(junit),
+            (None, 0, _) => "-".to_string(),
+            (None, runs, 0) => format!("{runs} ok"),
+            (None, runs, failed) => format!("{runs} ({failed} failed)"),
+        },
+        or_dash(s.gate.as_ref().map(gate_cell)),
+        s.commits.to_string(),
+        or_dash(s.promise.clone()),
+        s.error.clone().unwrap_or_else(|| "ok".to_string()),
+    ]
+}
+
+fn junit_cell(junit: &junit::Record) -> String {
+    let total = &junit.total;
+    let mut cell = format!("{}/{} passed", total.passed, total.tests);
+    match junit.diff.as_ref().map(|d| d.newly_failing.len()) {
+        Some(0) | None => {}
+        Some(n) => cell.push_str(&format!(", {n} newly failing")),
+    }
+    cell
+}
+
+fn gate_cell(gate: &gate::Record) -> String {
+    let mut cell = match &gate.results {
+        Some(r) if r.is_green() => format!("{} passed", r.passed),
+        Some(r) => format!("{} failed", r.failed + r.errors),
+        None => "no summary".to_string(),
+    };
+    match gate.action {
+        Action::Reverted => cell.push_str(", reverted"),
+        Action::Quarantined => cell.push_str(", quarantined"),
+        _ if !gate.regressions.is_empty() => cell.push_str(", regressed"),
+        _ => {}
+    }
+    cell
+}
+
+fn tool_counts(tools: &std::collections::BTreeMap<String, usize>) -> String {
+    tools
+        .iter()
+        .map(|(name, count)| format!("{name}:{count}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
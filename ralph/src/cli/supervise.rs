@@ -0,0 +1,44 @@
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::Duration;
+
+use clap::Args;
+
+use ralph::supervise::{self, Limits};
+use ralph::Result;
+
+/// Run COMMAND, append its merged output to LOG and kill its process group
+/// when a limit is hit.
+///
+/// Exits with the command's own status, 124 on timeout or 125 on stall.
+#[derive(Debug, Args)]
+pub struct SuperviseArgs {
+    /// Iteration log to append to
+    #[arg(long)]
+    log: PathBuf,
+
+    /// Wall-clock limit in seconds (0 disables)
+    #[arg(long, default_value_t = 0)]
+    timeout: u64,
+
+    /// Seconds without any output before the iteration counts as stalled (0 disables)
+    #[arg(long, default_value_t = 0)]
+    stall: u64,
+
+    /// Command to run, after `--`
+    #[arg(last = true, required = true)]
+    command: Vec<String>,
+}
+
+pub fn run(args: SuperviseArgs) -> Result<ExitCode> {
+    let limits = Limits {
+        timeout: seconds(args.timeout),
+        stall: seconds(args.stall),
+    };
+    let outcome = supervise::run(&args.command, limits, &args.log)?;
+    Ok(ExitCode::from(outcome.exit_code()))
+}
+
+fn seconds(secs: u64) -> Option<Duration> {
+    (secs > 0).then(|| Duration::from_secs(secs))
+}
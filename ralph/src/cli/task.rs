@@ -0,0 +1,325 @@
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use clap::{Args, Subcommand};
+use serde_json::json;
+
+use ralph::archon::sync;
+use ralph::archon::{Server, TaskStatus};
+use ralph::config::{Config, DEFAULT_CONFIG_PATH};
+use ralph::lifecycle::{self, Outcome};
+use ralph::plan::{Plan, Snapshot, Status, Task, DEFAULT_PLAN_PATH};
+use ralph::{claim, gate, git, record, report, stream, Error, Result};
+
+/// Tries at moving a task in Archon before giving up.
+const ARCHON_ATTEMPTS: u32 = 3;
+
+#[derive(Debug, Args)]
+pub struct TaskArgs {
+    #[arg(long, default_value = DEFAULT_PLAN_PATH, global = true)]
+    plan: PathBuf,
+
+    /// Config file with the `[archon]` section
+    #[arg(long, default_value = DEFAULT_CONFIG_PATH, global = true)]
+    config: PathBuf,
+
+    /// Also move the task in the Archon project of the config
+    #[arg(long, global = true)]
+    archon: bool,
+
+    /// Commit the plan when its checkboxes changed
+    #[arg(long, global = true)]
+    commit: bool,
+
+    /// Directory holding one lock file per claimed task
+    #[arg(long, default_value = "ralph/logs/claims", global = true)]
+    claims: PathBuf,
+
+    #[command(subcommand)]
+    command: TaskCommand,
+}
+
+#[derive(Debug, Subcommand)]
+enum TaskCommand {
+    /// Claim the highest-priority ready tasks, mark them doing and print
+    /// them as `ID<TAB>TITLE`, one per line
+    ///
+    /// Ready tasks are taken in plan order, or by Archon priority (highest
+    /// task_order first) with --archon. Exits with status 1 when nothing
+    /// could be claimed.
+    Start {
+        /// Maximum number of tasks to start
+        #[arg(long, default_value_t = 1)]
+        count: usize,
+
+        /// Worker name stored in the lock
+        #[arg(long)]
+        owner: String,
+
+        /// Process that holds the claims (default: the calling shell)
+        #[arg(long)]
+        pid: Option<u32>,
+    },
+    /// Print the prompt section that assigns a task to an iteration
+    Show {
+        task: String,
+
+        /// Tell the model other tasks belong to parallel workers
+        #[arg(long)]
+        parallel: bool,
+    },
+    /// Mark a started task from the iteration's outcome and release it
+    ///
+    /// Done when a commit landed and the last test run passed, review when a
+    /// commit landed without passing tests, blocked when the iteration marked
+    /// the task [!], and back to todo when nothing landed. The outcome is
+    /// appended to the log as a `task` record.
+    ///
+    /// With --archon, a failed Archon update is retried a few times. If it
+    /// still fails, the plan is updated and the task released all the same,
+    /// but the command exits with status 1: until Archon is fixed by hand,
+    /// the next sync would reset the plan to Archon's status.
+    Finish {
+        task: String,
+
+        /// Iteration log: test runs and the `worktree` record
+        #[arg(long)]
+        log: PathBuf,
+
+        /// Commit HEAD was at when the iteration started
+        #[arg(long)]
+        head: String,
+
+        /// Plan snapshot taken before the task was started
+        #[arg(long)]
+        since: Option<PathBuf>,
+    },
+}
+
+pub fn run(args: TaskArgs) -> Result<ExitCode> {
+    let mut archon = if args.archon {
+        Some(open_archon(&args.config)?)
+    } else {
+        None
+    };
+    let mut plan = Plan::load(&args.plan)?;
+    match args.command {
+        TaskCommand::Start { count, owner, pid } => {
+            let remote = match &mut archon {
+                Some((server, project)) => sync::fetch(server, project)?,
+                None => Vec::new(),
+            };
+            let pid = pid.unwrap_or_else(std::os::unix::process::parent_id);
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs());
+            let candidates = lifecycle::prioritize(plan.ready_tasks(), &remote);
+            let taken = claim::take(&candidates, &args.claims, count, &owner, pid, now)?;
+            if taken.is_empty() {
+                return Ok(ExitCode::FAILURE);
+            }
+
+            let mut changed = false;
+            for claim in &taken {
+                if plan
+                    .task(&claim.task)
+                    .is_some_and(|t| t.status != Status::Doing)
+                {
+                    plan.set_status(&claim.task, Status::Doing)?;
+                    changed = true;
+                }
+                if let Some((server, project)) = &mut archon {
+                    // The plan still says doing, which is what counts here.
+                    if let Err(e) =
+                        move_in_archon(server, project, &claim.task, TaskStatus::Doing, None)
+                    {
+                        eprintln!("ralph: {e}");
+                    }
+                }
+            }
+            if changed {
+                let ids: Vec<&str> = taken.iter().map(|c| c.task.as_str()).collect();
+                save(
+                    &plan,
+                    &args.plan,
+                    args.commit,
+                    &format!("ralph: start {}", ids.join(", ")),
+                )?;
+            }
+            for claim in taken {
+                println!("{}\t{}", claim.task, claim.title);
+            }
+        }
+        TaskCommand::Show { task, parallel } => {
+            let task = find(&plan, &task)?;
+            print!("{}", assignment(task, parallel));
+        }
+        TaskCommand::Finish {
+            task: id,
+            log,
+            head,
+            since,
+        } => {
+            let task = find(&plan, &id)?.clone();
+            let before = match &since {
+                Some(path) => Snapshot::load(path)?
+                    .tasks
+                    .into_iter()
+                    .find(|t| t.key == task.label())
+                    .map(|t| t.status),
+                None => None,
+            };
+            let events = stream::read(&log)?;
+            let tests_passed = report::summarize(0, &log, &events).tests_passed();
+            let landed = lifecycle::landed(&events, &head)?;
+            let (outcome, reason) = match gate::last_record(&events) {
+                Some(gate) if gate.action.rolled_back() => {
+                    (Outcome::Todo, "the test gate rolled it back")
+                }
+                _ => Outcome::judge(task.status, landed, tests_passed),
+            };
+
+            let to = outcome.plan_status(before);
+            // A blocked task's note may be all the iteration left uncommitted
+            if task.id.is_some() && (task.status != to || outcome == Outcome::Blocked) {
+                plan.set_status(&id, to)?;
+                save(
+                    &plan,
+                    &args.plan,
+                    args.commit,
+                    &format!("ralph: {id} {outcome} ({reason})"),
+                )?;
+            }
+            let moved = archon.as_mut().map(|(server, project)| {
+                let note = (outcome == Outcome::Blocked).then(|| {
+                    format!(
+                        "Blocked: {}",
+                        task.blocker.as_deref().unwrap_or("no reason given")
+                    )
+                });
+                move_in_archon(
+                    server,
+                    project,
+                    &id,
+                    outcome.archon_status(),
+                    note.as_deref(),
+                )
+            });
+            let (archon_id, archon_error) = match moved {
+                Some(Ok(archon_id)) => (archon_id, None),
+                Some(Err(e)) => (None, Some(e.to_string())),
+                None => (None, None),
+            };
+            claim::release(&args.claims, &id)?;
+            record::append(
+                &log,
+                "task",
+                json!({
+                    "task": id,
+                    "outcome": outcome,
+                    "reason": reason,
+                    "from": task.status,
+                    "to": to,
+                    "landed": landed,
+                    "tests_passed": tests_passed,
+                    "archon_id": archon_id,
+                    "archon_error": archon_error,
+                }),
+            )?;
+            println!("Task {id}: {outcome} ({reason})");
+            if let Some(e) = archon_error {
+                eprintln!("ralph: {e}; fix the task in Archon before the next sync");
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn open_archon(config: &Path) -> Result<(Server, String)> {
+    let config = Config::load(config)?.archon;
+    let project = config.project.ok_or_else(|| {
+        Error::Archon("no Archon project: set archon.project in the config".to_string())
+    })?;
+    Ok((Server::open(&config.store, &config.command)?, project))
+}
+
+/// Move the Archon task of plan task `id`, trying [`ARCHON_ATTEMPTS`] times.
+/// A task the project does not have is reported but not an error.
+fn move_in_archon(
+    server: &mut Server,
+    project: &str,
+    id: &str,
+    status: TaskStatus,
+    note: Option<&str>,
+) -> Result<Option<String>> {
+    let mut attempt = 1;
+    loop {
+        match sync::set_status(server, project, id, status, note) {
+            Ok(Some(archon_id)) => return Ok(Some(archon_id)),
+            Ok(None) => {
+                eprintln!("ralph: task {id} is not in Archon project {project}");
+                return Ok(None);
+            }
+            Err(e) if attempt == ARCHON_ATTEMPTS => {
+                return Err(Error::Archon(format!(
+                    "could not mark task {id} {} in Archon after {attempt} attempts: {e}",
+                    status.name()
+                )));
+            }
+            Err(_) => {
+                thread::sleep(Duration::from_secs(attempt.into()));
+                attempt += 1;
+            }
+        }
+    }
+}
+
+fn find<'a>(plan: &'a Plan, label: &str) -> Result<&'a Task> {
+    plan.tasks()
+        .find(|t| t.label() == label)
+        .ok_or_else(|| Error::UnknownTask(label.to_string()))
+}
+
+/// Write the plan and, with `commit`, commit it unless it is back to what
+/// HEAD has (a task returned to the queue before its start was committed).
+fn save(plan: &Plan, path: &Path, commit: bool, message: &str) -> Result<()> {
+    plan.save(path)?;
+    if commit {
+        let path = path.to_string_lossy();
+        git::run(&["add", "--", &path])?;
+        if git::run(&["diff", "--cached", "--quiet", "--", &path]).is_err() {
+            git::run(&["commit", "--quiet", "-m", message, "--", &path])?;
+        }
+    }
+    Ok(())
+}
+
+/// The `## Assigned Task` prompt section for `task`.
+fn assignment(task: &Task, parallel: bool) -> String {
+    let mut text = format!(
+        "\n\n## Assigned Task\n\nWork ONLY on task {}: {}\n",
+        task.label(),
+        task.title
+    );
+    if !task.notes.is_empty() {
+        text.push_str("\nTask notes from the plan:\n\n");
+        for note in &task.notes {
+            text.push_str(note);
+            text.push('\n');
+        }
+    }
+    text.push_str(
+        "\nThe loop has marked this task doing and will mark it done, review or blocked \
+         from what it can verify: a commit with a passing last test run is done, a commit \
+         without one is review. Do not change its status yourself, in the plan or with \
+         manage_task. If you cannot finish it, mark it `[!]` in the plan with a \
+         `Blocked: <reason>` note and commit that.\n",
+    );
+    if parallel {
+        text.push_str("Other tasks are being handled by parallel workers; do not start them.\n");
+    }
+    text
+}
@@ -0,0 +1,176 @@
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::{Args, ValueEnum};
+
+use ralph::plan::{Plan, DEFAULT_PLAN_PATH};
+use ralph::spec::{self, Coverage, Matrix, Row, TaskRef, DEFAULT_SPECS_DIR};
+use ralph::Result;
+
+/// Trace spec requirements to the plan tasks that cover them.
+///
+/// With `--check`, exits with status 1 when a requirement has no task, a
+/// task covers an unknown requirement, or an ID is defined twice.
+#[derive(Debug, Args)]
+pub struct TraceArgs {
+    /// Directory of spec files with `[ID]` requirements
+    #[arg(long, default_value = DEFAULT_SPECS_DIR)]
+    specs: PathBuf,
+
+    /// Implementation plan whose tasks carry `covers:` lines
+    #[arg(long, default_value = DEFAULT_PLAN_PATH)]
+    plan: PathBuf,
+
+    #[arg(long, value_enum, default_value_t = Format::Table)]
+    format: Format,
+
+    /// Fail when the matrix has gaps
+    #[arg(long)]
+    check: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Format {
+    /// Matrix and gaps, for the terminal
+    Table,
+    /// Matrix as a markdown table, e.g. for the plan
+    Markdown,
+    Json,
+}
+
+pub fn run(args: TraceArgs) -> Result<ExitCode> {
+    if !args.specs.is_dir() {
+        eprintln!(
+            "No specs in {}: write requirements as `- [ID] text` items in *.md files there, or pass --specs",
+            args.specs.display()
+        );
+    }
+    let requirements = spec::load(&args.specs)?;
+    let plan = Plan::load(&args.plan)?;
+    let matrix = Matrix::build(&requirements, &plan);
+
+    match args.format {
+        Format::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&matrix).expect("matrix serializes")
+        ),
+        Format::Markdown => {
+            println!("| Requirement | Description | Tasks | Coverage |");
+            println!("|-------------|-------------|-------|----------|");
+            for row in &matrix.rows {
+                println!(
+                    "| {} | {} | {} | {} |",
+                    row.requirement.id,
+                    row.requirement.text.replace('|', "\\|"),
+                    task_list(&row.tasks),
+                    row.coverage.name()
+                );
+            }
+        }
+        Format::Table => print_table(&matrix),
+    }
+
+    if args.check && !matrix.is_complete() {
+        return Ok(ExitCode::FAILURE);
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn print_table(matrix: &Matrix) {
+    let id_width = matrix
+        .rows
+        .iter()
+        .map(|r| r.requirement.id.len())
+        .chain(["Requirement".len()])
+        .max()
+        .unwrap_or_default();
+    let tasks: Vec<String> = matrix.rows.iter().map(|r| task_list(&r.tasks)).collect();
+    let task_width = tasks
+        .iter()
+        .map(|t| t.chars().count())
+        .chain(["Tasks".len()])
+        .max()
+        .unwrap_or_default();
+    println!(
+        "{:<id_width$}  {:<8}  {:<task_width$}  Description",
+        "Requirement", "Coverage", "Tasks"
+    );
+    for (row, tasks) in matrix.rows.iter().zip(&tasks) {
+        println!(
+            "{:<id_width$}  {:<8}  {:<task_width$}  {}",
+            row.requirement.id,
+            row.coverage.name(),
+            tasks,
+            row.requirement.text
+        );
+    }
+
+    let gaps = |title: &str, rows: Vec<&Row>| {
+        if rows.is_empty() {
+            return;
+        }
+        println!();
+        println!("{title} ({}):", rows.len());
+        for row in rows {
+            println!(
+                "  {} {} ({}:{})",
+                row.requirement.id,
+                row.requirement.text,
+                row.requirement.file.display(),
+                row.requirement.line
+            );
+        }
+    };
+    gaps(
+        "Requirements without tasks",
+        matrix.with_coverage(Coverage::Missing).collect(),
+    );
+    gaps(
+        "Requirements covered only by incomplete tasks",
+        matrix.with_coverage(Coverage::Open).collect(),
+    );
+    if !matrix.untraced.is_empty() {
+        println!();
+        println!(
+            "Tasks that cover no requirement ({}):",
+            matrix.untraced.len()
+        );
+        for task in &matrix.untraced {
+            println!("  {} {} {}", task.status, task.task, task.title);
+        }
+    }
+    if !matrix.unknown.is_empty() {
+        println!();
+        for unknown in &matrix.unknown {
+            println!(
+                "Unknown requirement: {} covers {}, which no spec defines",
+                unknown.task, unknown.requirement
+            );
+        }
+    }
+    for id in &matrix.duplicates {
+        println!("Duplicate requirement ID: {id}");
+    }
+
+    let count = |coverage| matrix.with_coverage(coverage).count();
+    println!();
+    println!(
+        "{} requirement(s): {} done, {} partial, {} open, {} missing",
+        matrix.rows.len(),
+        count(Coverage::Done),
+        count(Coverage::Partial),
+        count(Coverage::Open),
+        count(Coverage::Missing)
+    );
+}
+
+fn task_list(tasks: &[TaskRef]) -> String {
+    if tasks.is_empty() {
+        return "-".to_string();
+    }
+    tasks
+        .iter()
+        .map(|t| format!("{} {}", t.task, t.status))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -0,0 +1,168 @@
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::{Args, Subcommand};
+use serde_json::json;
+
+use ralph::config::{Config, DEFAULT_CONFIG_PATH};
+use ralph::plan::{Plan, Snapshot};
+use ralph::worktree::{self, Merge, Verdict, Worktree, EXIT_DISCARDED};
+use ralph::{record, report, stream, Result};
+
+#[derive(Debug, Args)]
+pub struct WorktreeArgs {
+    /// Config file with the `[worktree]` section
+    #[arg(long, default_value = DEFAULT_CONFIG_PATH, global = true)]
+    config: PathBuf,
+
+    #[command(subcommand)]
+    command: WorktreeCommand,
+}
+
+#[derive(Debug, Subcommand)]
+enum WorktreeCommand {
+    /// Create the scratch worktree of an iteration and print its path
+    Create {
+        #[arg(long)]
+        iteration: u32,
+
+        /// Branch the scratch branch starts from
+        #[arg(long)]
+        base: String,
+    },
+    /// Merge an iteration's scratch branch or discard it, then remove the
+    /// worktree
+    ///
+    /// The branch is merged into the branch checked out in the current
+    /// directory only when the iteration's last test run passed and a plan
+    /// task moved to [x] (with --task: the iteration left any work). Exits
+    /// with status 7 when the work was discarded.
+    Finish {
+        #[arg(long)]
+        iteration: u32,
+
+        /// Branch checked out in the current directory
+        #[arg(long)]
+        into: String,
+
+        /// Iteration log; also receives a `worktree` record
+        #[arg(long)]
+        log: PathBuf,
+
+        /// Plan snapshot taken before the iteration
+        #[arg(long)]
+        since: PathBuf,
+
+        /// Plan file, relative to the worktree root
+        #[arg(long)]
+        plan: PathBuf,
+
+        /// Override the configured merge strategy
+        #[arg(long, value_enum)]
+        merge: Option<Merge>,
+
+        /// Task the loop assigned to the iteration and marks itself
+        #[arg(long)]
+        task: Option<String>,
+    },
+    /// Remove scratch worktrees and branches left by an interrupted run
+    Clean,
+}
+
+pub fn run(args: WorktreeArgs) -> Result<ExitCode> {
+    let config = Config::load(&args.config)?.worktree;
+    let dir = match config.dir {
+        Some(dir) => dir,
+        None => worktree::default_dir()?,
+    };
+
+    match args.command {
+        WorktreeCommand::Create { iteration, base } => {
+            let tree = Worktree::named(&dir, &format!("iteration-{iteration}"));
+            tree.remove()?;
+            tree.create(&base)?;
+            println!("{}", tree.path.display());
+        }
+        WorktreeCommand::Finish {
+            iteration,
+            into,
+            log,
+            since,
+            plan,
+            merge,
+            task,
+        } => {
+            let tree = Worktree::named(&dir, &format!("iteration-{iteration}"));
+            let merge = merge.unwrap_or(config.merge);
+            let summary = report::summarize(iteration, &log, &stream::read(&log)?);
+            let mut verdict = match &task {
+                Some(task) => {
+                    let worked = tree.path.exists() && tree.has_work(&into)?;
+                    Verdict::judge_assigned(&summary, task, worked, config.require_tests)
+                }
+                None => {
+                    let plan_path = tree.path.join(&plan);
+                    let after = if plan_path.exists() {
+                        Snapshot::of(&Plan::load(&plan_path)?)
+                    } else {
+                        Snapshot::default()
+                    };
+                    let moved = Snapshot::load(&since)?.transitions(&after);
+                    Verdict::judge(&summary, &moved, config.require_tests)
+                }
+            };
+
+            let mut commits = 0;
+            let _lock = worktree::lock(&dir)?;
+            if verdict.accepted() {
+                let message = format!("Iteration {iteration}: {}", verdict.completed.join("; "));
+                let merged = tree
+                    .commit_leftovers(&format!("Iteration {iteration}: uncommitted changes"))
+                    .and_then(|_| tree.commits_ahead(&into))
+                    .and_then(|ahead| {
+                        commits = ahead;
+                        tree.merge(merge, &into, &message)
+                    });
+                if let Err(e) = merged {
+                    verdict.reasons.push(format!("merge failed: {e}"));
+                }
+            }
+            tree.remove()?;
+
+            let result = if verdict.accepted() {
+                println!(
+                    "Merged {} ({commits} commit(s), {merge}) into {into}: {}",
+                    tree.branch,
+                    verdict.completed.join("; ")
+                );
+                "merged"
+            } else {
+                println!("Discarded {}: {}", tree.branch, verdict.reasons.join("; "));
+                "discarded"
+            };
+            record::append(
+                &log,
+                "worktree",
+                json!({
+                    "result": result,
+                    "branch": tree.branch,
+                    "into": into,
+                    "merge": merge,
+                    "commits": commits,
+                    "completed": verdict.completed,
+                    "reasons": verdict.reasons,
+                }),
+            )?;
+            if !verdict.accepted() {
+                return Ok(ExitCode::from(EXIT_DISCARDED));
+            }
+        }
+        WorktreeCommand::Clean => {
+            let removed = worktree::clean(&dir)?;
+            if removed > 0 {
+                println!("Removed {removed} scratch worktree(s)");
+            }
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
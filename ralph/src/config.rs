@@ -0,0 +1,553 @@
+//! Loop settings read from `ralph/ralph.toml`.
+//!
+//! Every key is optional; a missing file means the built-in defaults, which
+//! match what `loop.sh` did before the file existed.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::gate::OnRegression;
+use crate::push::Policy;
+use crate::worktree::Merge;
+
+pub const DEFAULT_CONFIG_PATH: &str = "ralph/ralph.toml";
+
+/// The prompt a loop iteration runs with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+    Plan,
+    Unified,
+    Build,
+    Verify,
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Mode::Plan => "plan",
+            Mode::Unified => "unified",
+            Mode::Build => "build",
+            Mode::Verify => "verify",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub models: Models,
+    pub fallback: Fallback,
+    pub push: Push,
+    pub worktree: Worktrees,
+    pub archon: Archon,
+    pub gate: Gate,
+    pub junit: Junit,
+    pub project: Project,
+}
+
+/// Model passed to `claude --model`, per mode.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Models {
+    /// Used by every mode without its own entry.
+    pub default: String,
+    pub plan: Option<String>,
+    pub unified: Option<String>,
+    pub build: Option<String>,
+    pub verify: Option<String>,
+}
+
+impl Default for Models {
+    fn default() -> Self {
+        Models {
+            default: "opus".to_string(),
+            plan: None,
+            unified: None,
+            build: None,
+            verify: None,
+        }
+    }
+}
+
+impl Models {
+    pub fn for_mode(&self, mode: Mode) -> &str {
+        let model = match mode {
+            Mode::Plan => &self.plan,
+            Mode::Unified => &self.unified,
+            Mode::Build => &self.build,
+            Mode::Verify => &self.verify,
+        };
+        model.as_deref().unwrap_or(&self.default)
+    }
+}
+
+/// Models to switch to after a rate-limit or overload error.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Fallback {
+    /// Tried in order, each after the previous one was throttled.
+    pub models: Vec<String>,
+    /// Iterations to stay on a fallback model before trying the mode's own
+    /// model again (0 = for the rest of the run).
+    pub retry_primary_after: u32,
+}
+
+impl Default for Fallback {
+    fn default() -> Self {
+        Fallback {
+            models: Vec::new(),
+            retry_primary_after: 3,
+        }
+    }
+}
+
+/// Where and when the loop pushes its commits.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Push {
+    pub policy: Policy,
+    pub remote: String,
+    /// Branches that are never pushed: exact names or `prefix*` patterns.
+    pub protected: Vec<String>,
+}
+
+impl Default for Push {
+    fn default() -> Self {
+        Push {
+            policy: Policy::AfterCommit,
+            remote: "origin".to_string(),
+            protected: vec!["main".to_string(), "master".to_string()],
+        }
+    }
+}
+
+/// Running each iteration in its own scratch worktree.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Worktrees {
+    pub enabled: bool,
+    /// Parent directory of the worktrees (default: inside the git directory).
+    pub dir: Option<PathBuf>,
+    pub merge: Merge,
+    /// Merge only iterations whose last test run passed.
+    pub require_tests: bool,
+}
+
+impl Default for Worktrees {
+    fn default() -> Self {
+        Worktrees {
+            enabled: false,
+            dir: None,
+            merge: Merge::Ff,
+            require_tests: true,
+        }
+    }
+}
+
+/// Task tracking in Archon, or in the local stand-in server.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Archon {
+    /// Sync the plan with the project's tasks before every iteration.
+    pub enabled: bool,
+    /// ID of the project whose tasks mirror the plan.
+    pub project: Option<String>,
+    /// Task store of the local server.
+    pub store: PathBuf,
+    /// MCP server to use instead of the local store, e.g.
+    /// `["npx", "mcp-remote", "http://localhost:8051/mcp"]`.
+    pub command: Vec<String>,
+}
+
+impl Default for Archon {
+    fn default() -> Self {
+        Archon {
+            enabled: false,
+            project: None,
+            store: PathBuf::from(crate::archon::DEFAULT_STORE_PATH),
+            command: Vec::new(),
+        }
+    }
+}
+
+/// The loop's own test run after every round (see [`crate::gate`]).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Gate {
+    pub enabled: bool,
+    /// Test command, run from the project root (default: the project's
+    /// `tests.backend`).
+    pub command: Option<String>,
+    pub on_regression: OnRegression,
+    /// Seconds the test run may take, 0 = no limit.
+    pub timeout: u64,
+}
+
+impl Default for Gate {
+    fn default() -> Self {
+        Gate {
+            enabled: false,
+            command: None,
+            on_regression: OnRegression::Revert,
+            timeout: 1800,
+        }
+    }
+}
+
+/// JUnit XML reports of the iterations' test runs (see [`crate::junit`]).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Junit {
+    pub enabled: bool,
+}
+
+/// What the prompts are about: values for the variables of the prompt
+/// templates (see [`crate::prompt`]). Either inline in `[project]`, or a
+/// named profile in `profiles/NAME.toml` next to the config file, holding the
+/// same keys at the top level.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Project {
+    /// Profile that replaces the rest of the section.
+    pub profile: Option<String>,
+    /// `{{ feature }}`: the feature the loop is building.
+    pub feature: String,
+    /// `{{ promise }}`: the completion promise each mode's prompt asks for.
+    pub promises: Promises,
+    /// `{{ paths.NAME }}`: code locations the prompts point the model at.
+    pub paths: BTreeMap<String, String>,
+    /// `{{ tests.NAME }}`: test and build commands.
+    pub tests: BTreeMap<String, String>,
+    /// `{{ limits.NAME }}`: numeric limits such as the maximum file size.
+    pub limits: BTreeMap<String, u64>,
+    /// `{{ urls.NAME }}`: where the running app can be reached.
+    pub urls: BTreeMap<String, String>,
+    /// `{{> includes.NAME }}`: files with the feature's own prompt sections
+    /// (examples, pages to check, known issues), relative to the profile, or
+    /// to the config file for `[project]`. An empty one includes nothing.
+    pub includes: BTreeMap<String, String>,
+}
+
+impl Default for Project {
+    /// No feature: every variable the shipped prompts use exists but is
+    /// empty (the line limit aside), so a project without a profile renders
+    /// generic prompts. Feature values belong in a profile.
+    fn default() -> Self {
+        Project {
+            profile: None,
+            feature: String::new(),
+            promises: Promises::default(),
+            paths: strings(&[
+                ("backend", ""),
+                ("frontend", ""),
+                ("components", ""),
+                ("shared", ""),
+                ("lib", ""),
+                ("tests", ""),
+            ]),
+            tests: strings(&[("backend", ""), ("frontend", "")]),
+            limits: BTreeMap::from([("backend_lines".to_string(), 500)]),
+            urls: strings(&[("frontend", ""), ("backend", "")]),
+            includes: strings(&[("plan", ""), ("build", ""), ("verify", "")]),
+        }
+    }
+}
+
+impl Project {
+    /// Load the profile file at `path`. Keys the profile leaves out are
+    /// missing rather than the built-in defaults, so a template variable it
+    /// does not define fails to render.
+    pub fn load(path: &Path) -> Result<Project> {
+        let text = fs::read_to_string(path).map_err(|e| Error::io(path, e))?;
+        let keys: toml::Table = toml::from_str(&text).map_err(|e| Error::toml(path, e))?;
+        let empty = Project {
+            profile: None,
+            feature: String::new(),
+            promises: Promises::none(),
+            paths: BTreeMap::new(),
+            tests: BTreeMap::new(),
+            limits: BTreeMap::new(),
+            urls: BTreeMap::new(),
+            includes: BTreeMap::new(),
+        };
+        let mut project = toml::Table::try_from(empty).expect("project serializes");
+        project.extend(keys);
+        toml::Value::Table(project)
+            .try_into()
+            .map_err(|e| Error::toml(path, e))
+    }
+}
+
+fn strings(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+    pairs
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect()
+}
+
+/// File of profile `name`: `profiles/NAME.toml` next to the config file, or
+/// `name` itself when it is a path to a `.toml` file.
+pub fn profile_path(config: &Path, name: &str) -> PathBuf {
+    if name.ends_with(".toml") {
+        return PathBuf::from(name);
+    }
+    config
+        .parent()
+        .unwrap_or(Path::new("."))
+        .join("profiles")
+        .join(format!("{name}.toml"))
+}
+
+/// Point `project.profile` in the config file at `path` to `name`, editing
+/// the line in place so the file's comments and layout survive.
+pub fn set_profile(path: &Path, name: &str) -> Result<()> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(Error::io(path, e)),
+    };
+    let entry = format!("profile = {}", toml::Value::String(name.to_string()));
+    let mut lines: Vec<String> = text.lines().map(str::to_string).collect();
+    match lines.iter().position(|l| l.trim() == "[project]") {
+        Some(header) => {
+            let end = lines[header + 1..]
+                .iter()
+                .position(|l| l.trim_start().starts_with('['))
+                .map_or(lines.len(), |i| header + 1 + i);
+            let existing = (header + 1..end).find(|&i| {
+                lines[i]
+                    .split_once('=')
+                    .is_some_and(|(key, _)| key.trim() == "profile")
+            });
+            match existing {
+                Some(i) => lines[i] = entry,
+                None => lines.insert(header + 1, entry),
+            }
+        }
+        None => {
+            if lines.last().is_some_and(|l| !l.trim().is_empty()) {
+                lines.push(String::new());
+            }
+            lines.push("[project]".to_string());
+            lines.push(entry);
+        }
+    }
+    let mut text = lines.join("\n");
+    text.push('\n');
+    fs::write(path, text).map_err(|e| Error::io(path, e))
+}
+
+/// Completion promise tag, per mode. A mode left out of a `promises` table
+/// has none.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Promises {
+    #[serde(default)]
+    pub plan: Option<String>,
+    #[serde(default)]
+    pub unified: Option<String>,
+    #[serde(default)]
+    pub build: Option<String>,
+    #[serde(default)]
+    pub verify: Option<String>,
+}
+
+impl Default for Promises {
+    fn default() -> Self {
+        Promises {
+            plan: None,
+            unified: Some("ALL_TASKS_COMPLETE".to_string()),
+            build: Some("BUILD_COMPLETE".to_string()),
+            verify: Some("VERIFICATION_COMPLETE".to_string()),
+        }
+    }
+}
+
+impl Promises {
+    fn none() -> Promises {
+        Promises {
+            plan: None,
+            unified: None,
+            build: None,
+            verify: None,
+        }
+    }
+
+    pub fn for_mode(&self, mode: Mode) -> Option<&str> {
+        let promise = match mode {
+            Mode::Plan => &self.plan,
+            Mode::Unified => &self.unified,
+            Mode::Build => &self.build,
+            Mode::Verify => &self.verify,
+        };
+        promise.as_deref()
+    }
+}
+
+impl Config {
+    /// Load the config, using the defaults when the file does not exist.
+    pub fn load(path: &Path) -> Result<Config> {
+        match fs::read_to_string(path) {
+            Ok(text) => toml::from_str(&text).map_err(|e| Error::toml(path, e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Config::default()),
+            Err(e) => Err(Error::io(path, e)),
+        }
+    }
+
+    /// Load the config with the project settings of `profile`, or of the
+    /// profile named by `project.profile` when `profile` is `None`.
+    pub fn load_with_profile(path: &Path, profile: Option<&str>) -> Result<Config> {
+        let mut config = Config::load(path)?;
+        if let Some(name) = profile
+            .map(str::to_string)
+            .or(config.project.profile.take())
+        {
+            config.project = Project::load(&profile_path(path, &name))?;
+            config.project.profile = Some(name);
+        }
+        Ok(config)
+    }
+
+    /// Test command of the gate: `gate.command`, else the project's
+    /// `tests.backend`.
+    pub fn gate_command(&self) -> Option<&str> {
+        self.gate
+            .command
+            .as_deref()
+            .or(self.project.tests.get("backend").map(String::as_str))
+            .filter(|command| !command.trim().is_empty())
+    }
+
+    /// Effective value of a dotted key such as `models.verify`, with defaults
+    /// filled in. Unset optional keys resolve to `None`.
+    pub fn get(&self, key: &str) -> Result<Option<toml::Value>> {
+        let mut value = toml::Value::try_from(self).expect("config serializes");
+        let mut parts = key.split('.').peekable();
+        while let Some(part) = parts.next() {
+            let toml::Value::Table(mut table) = value else {
+                return Err(Error::UnknownConfigKey(key.to_string()));
+            };
+            match table.remove(part) {
+                Some(next) => value = next,
+                None if parts.peek().is_none() && is_optional(key) => return Ok(None),
+                None => return Err(Error::UnknownConfigKey(key.to_string())),
+            }
+        }
+        Ok(Some(value))
+    }
+}
+
+/// Keys that are valid but serialize to nothing while unset.
+fn is_optional(key: &str) -> bool {
+    matches!(
+        key,
+        "models.plan"
+            | "models.unified"
+            | "models.build"
+            | "models.verify"
+            | "worktree.dir"
+            | "archon.project"
+            | "gate.command"
+            | "project.profile"
+            | "project.promises.plan"
+            | "project.promises.unified"
+            | "project.promises.build"
+            | "project.promises.verify"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn defaults_name_no_feature() {
+        let config = Config::default();
+        assert_eq!(config.project.feature, "");
+        assert_eq!(config.project.paths["backend"], "");
+        assert_eq!(config.project.includes["build"], "");
+        assert_eq!(
+            config.project.promises.for_mode(Mode::Build),
+            Some("BUILD_COMPLETE")
+        );
+        assert_eq!(config.project.promises.for_mode(Mode::Plan), None);
+        assert_eq!(config.gate_command(), None);
+    }
+
+    #[test]
+    fn get_resolves_dotted_keys() {
+        let config: Config = toml::from_str(
+            "[models]\ndefault = \"opus\"\nverify = \"sonnet\"\n[gate]\nenabled = true\n",
+        )
+        .unwrap();
+        assert_eq!(config.models.for_mode(Mode::Verify), "sonnet");
+        assert_eq!(config.models.for_mode(Mode::Build), "opus");
+        assert_eq!(
+            config.get("gate.enabled").unwrap(),
+            Some(toml::Value::Boolean(true))
+        );
+        assert_eq!(config.get("models.plan").unwrap(), None);
+        assert!(matches!(
+            config.get("gate.colour"),
+            Err(Error::UnknownConfigKey(key)) if key == "gate.colour"
+        ));
+    }
+
+    #[test]
+    fn profiles_leave_out_keys_instead_of_using_defaults() {
+        let dir = TempDir::new("config");
+        let config = dir.write("ralph.toml", "[gate]\nenabled = true\n");
+        dir.write(
+            "profiles/orders.toml",
+            "feature = \"Order Export\"\n\n[tests]\nbackend = \"pytest -q\"\n",
+        );
+        let loaded = Config::load_with_profile(&config, Some("orders")).unwrap();
+        assert_eq!(loaded.project.profile.as_deref(), Some("orders"));
+        assert_eq!(loaded.project.feature, "Order Export");
+        assert!(loaded.project.paths.is_empty());
+        assert_eq!(loaded.project.promises.for_mode(Mode::Unified), None);
+        assert_eq!(loaded.gate_command(), Some("pytest -q"));
+        assert_eq!(
+            profile_path(&config, "orders"),
+            dir.join("profiles/orders.toml")
+        );
+        assert_eq!(
+            profile_path(&config, "/tmp/x.toml"),
+            Path::new("/tmp/x.toml")
+        );
+        assert!(Config::load_with_profile(&config, Some("missing")).is_err());
+    }
+
+    #[test]
+    fn set_profile_edits_the_project_table_in_place() {
+        let dir = TempDir::new("config");
+        let path = dir.write(
+            "ralph.toml",
+            "[project]\n# which feature\nprofile = \"old\"\n\n[gate]\nenabled = true\n",
+        );
+        set_profile(&path, "orders").unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "[project]\n# which feature\nprofile = \"orders\"\n\n[gate]\nenabled = true\n"
+        );
+
+        let path = dir.write("other.toml", "[gate]\nenabled = true\n");
+        set_profile(&path, "orders").unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "[gate]\nenabled = true\n\n[project]\nprofile = \"orders\"\n"
+        );
+
+        let path = dir.join("new.toml");
+        set_profile(&path, "orders").unwrap();
+        let config = Config::load(&path).unwrap();
+        assert_eq!(config.project.profile.as_deref(), Some("orders"));
+    }
+}
@@ -0,0 +1,90 @@
+use std::io;
+use std::path::PathBuf;
+
+/// Errors produced by the ralph tooling.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("{path}: {source}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+
+    #[error("{path}: invalid JSON: {source}")]
+    Json {
+        path: PathBuf,
+        #[source]
+        source: serde_json::Error,
+    },
+
+    #[error("{path}: invalid TOML: {source}")]
+    Toml {
+        path: PathBuf,
+        #[source]
+        source: toml::de::Error,
+    },
+
+    #[error("{path}: invalid XML: {source}")]
+    Xml {
+        path: PathBuf,
+        #[source]
+        source: quick_xml::Error,
+    },
+
+    #[error("unknown config key `{0}`")]
+    UnknownConfigKey(String),
+
+    #[error("git {command}: {stderr}")]
+    Git { command: String, stderr: String },
+
+    #[error("unknown task `{0}`")]
+    UnknownTask(String),
+
+    #[error("invalid promise `{0}` (expected TAG=EXIT_CODE, with an exit code from 2 to 255)")]
+    InvalidPromise(String),
+
+    #[error("invalid status `{0}` (expected one of: todo, done, in-progress, doing, blocked, or a marker like `x`)")]
+    InvalidStatus(String),
+
+    #[error("{0}")]
+    Archon(String),
+
+    #[error("{0}")]
+    Gate(String),
+
+    #[error("{0}: already exists")]
+    Exists(PathBuf),
+
+    #[error("{path}:{line}: {message}")]
+    Template {
+        path: PathBuf,
+        line: usize,
+        message: String,
+    },
+}
+
+impl Error {
+    pub(crate) fn io(path: impl Into<PathBuf>, source: io::Error) -> Self {
+        Error::Io {
+            path: path.into(),
+            source,
+        }
+    }
+
+    pub(crate) fn json(path: impl Into<PathBuf>, source: serde_json::Error) -> Self {
+        Error::Json {
+            path: path.into(),
+            source,
+        }
+    }
+
+    pub(crate) fn toml(path: impl Into<PathBuf>, source: toml::de::Error) -> Self {
+        Error::Toml {
+            path: path.into(),
+            source,
+        }
+    }
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -0,0 +1,366 @@
+//! Scaffolding for a new feature run: spec skeleton, empty plan, project
+//! profile, and the archive of the previous run.
+//!
+//! Starting a feature replaces the plan the loop works from, so the previous
+//! plan and iteration logs are moved to `ralph/archive/DATE-NAME/` rather
+//! than deleted; `ralph report --logs` can still read the old run there.
+//! State that outlives a feature stays in `ralph/logs`: the spend ledger,
+//! the test gate's baseline, task claims, the circuit breaker and the model
+//! fallback.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::Project;
+use crate::error::{Error, Result};
+use crate::plan::Plan;
+
+/// Default directory of archived runs, relative to the project root.
+pub const DEFAULT_ARCHIVE_DIR: &str = "ralph/archive";
+
+/// `order-export` for `Order Export`: lowercase words joined by dashes.
+pub fn slug(name: &str) -> String {
+    name.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_ascii_lowercase)
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Completion promise of the feature's build mode: `ORDER_EXPORT_COMPLETE`.
+pub fn promise_tag(slug: &str) -> String {
+    format!("{}_COMPLETE", slug.to_ascii_uppercase().replace('-', "_"))
+}
+
+/// Prefix of the spec's requirement IDs: `ORDER-EXPORT` for `ORDER-EXPORT-1`.
+pub fn requirement_prefix(slug: &str) -> String {
+    slug.to_ascii_uppercase()
+}
+
+/// What a new feature run is about.
+#[derive(Debug, Clone)]
+pub struct Feature {
+    pub name: String,
+    pub slug: String,
+    /// Ticket the feature is tracked under, e.g. `AGENT-105`.
+    pub ticket: Option<String>,
+    /// `YYYY-MM-DD` the run was started.
+    pub date: String,
+}
+
+impl Feature {
+    pub fn promise(&self) -> String {
+        promise_tag(&self.slug)
+    }
+
+    fn reference(&self) -> &str {
+        self.ticket.as_deref().unwrap_or("TBD")
+    }
+
+    /// Skeleton of `ralph/specs/SLUG.md`, with one requirement per section
+    /// in the format `ralph trace` reads.
+    pub fn spec(&self) -> String {
+        let prefix = requirement_prefix(&self.slug);
+        format!(
+            "\
+# {name} - Specification
+
+**Task Reference**: {reference}
+**Status**: Draft
+
+## Overview
+
+What the feature does, for whom, and what is out of scope.
+
+## Requirements
+
+Every requirement starts with a stable ID in brackets; plan tasks name the
+requirements they implement on a `covers: {prefix}-1` line.
+
+### Backend
+
+- [{prefix}-1] Describe the first backend requirement
+
+### Frontend
+
+- [{prefix}-2] Describe the first frontend requirement
+
+### Tests
+
+- [{prefix}-3] Describe what the tests must cover
+
+## Open Questions
+
+- None yet
+",
+            name = self.name,
+            reference = self.reference(),
+        )
+    }
+
+    /// An implementation plan with the phase and task structure the loop
+    /// parses but no tasks yet; planning mode fills it in from the spec.
+    pub fn plan(&self, spec: &Path) -> Plan {
+        let text = format!(
+            "\
+# {name} - Implementation Plan
+
+**Task Reference**: {reference}
+**Spec**: `{spec}`
+**Status**: Not Started
+**Last Updated**: {date}
+
+---
+
+## Gap Analysis Summary
+
+Not run yet: `./ralph/loop.sh plan` compares the spec with the code and
+fills in the phases below.
+
+---
+
+## Phase 1: Backend Foundation
+**Status**: [ ] Not Started
+
+### Tasks
+
+Tasks are numbered per phase and name the files they change:
+
+```markdown
+- [ ] 1.1 Create the model (`backend/apps/<module>/models.py`)
+  - covers: {prefix}-1
+```
+
+### Completion Criteria
+- [ ] All phase tasks done and tested
+
+---
+
+## Phase 2: Frontend
+**Status**: [ ] Not Started
+**Dependency**: Phase 1
+
+### Tasks
+
+### Completion Criteria
+- [ ] Page renders and builds
+
+---
+
+## Phase 3: Tests
+**Status**: [ ] Not Started
+**Dependency**: Phases 1 and 2
+
+### Tasks
+
+### Completion Criteria
+- [ ] All tests passing
+
+---
+
+## Progress Summary
+---
+
+## Blockers
+
+**Current blockers: None**
+
+---
+
+## Completion Promise
+
+When ALL phases are complete and verified:
+```
+<promise>{promise}</promise>
+```
+",
+            name = self.name,
+            reference = self.reference(),
+            spec = spec.display(),
+            date = self.date,
+            prefix = requirement_prefix(&self.slug),
+            promise = self.promise(),
+        );
+        let mut plan = Plan::parse(&text);
+        plan.update_summary();
+        plan
+    }
+
+    /// Profile for the feature: the stack settings of `base` (paths, tests,
+    /// limits, URLs) with this feature's name and build promise. The includes
+    /// describe the previous feature, so they are kept but left empty.
+    pub fn profile(&self, base: &Project) -> String {
+        let mut project = base.clone();
+        project.profile = None;
+        project.feature = self.name.clone();
+        project.promises.build = Some(self.promise());
+        project.includes.values_mut().for_each(String::clear);
+        format!(
+            "# Project profile: {name}. Paths, tests, limits and URLs were copied\n\
+             # from the previous project; adjust them to the feature. Point the\n\
+             # includes at files with the feature's own prompt sections.\n\n{body}",
+            name = self.name,
+            body = toml::to_string_pretty(&project).expect("project serializes"),
+        )
+    }
+}
+
+/// Directory for the run of `name` archived on `date`: `DATE-NAME` in
+/// `dir`, with a numeric suffix when that is taken.
+pub fn archive_dir(dir: &Path, date: &str, name: &str) -> PathBuf {
+    let base = format!("{date}-{}", slug(name));
+    let mut path = dir.join(&base);
+    let mut n = 2;
+    while path.exists() {
+        path = dir.join(format!("{base}-{n}"));
+        n += 1;
+    }
+    path
+}
+
+/// Move `paths` that exist into `archive`, keeping their file names.
+/// Returns where each one went.
+pub fn archive(paths: &[&Path], archive: &Path) -> Result<Vec<PathBuf>> {
+    let mut moved = Vec::new();
+    for path in paths.iter().filter(|p| p.exists()) {
+        fs::create_dir_all(archive).map_err(|e| Error::io(archive, e))?;
+        let to = archive.join(path.file_name().unwrap_or(path.as_os_str()));
+        fs::rename(path, &to).map_err(|e| Error::io(*path, e))?;
+        moved.push(to);
+    }
+    Ok(moved)
+}
+
+/// Move the per-iteration files in `logs` (iteration logs, test results,
+/// JUnit reports, gate output and the plan snapshot) to a directory of the
+/// same name in `archive`. Returns where each one went, in name order.
+pub fn archive_logs(logs: &Path, archive: &Path) -> Result<Vec<PathBuf>> {
+    if !logs.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut paths: Vec<PathBuf> = fs::read_dir(logs)
+        .map_err(|e| Error::io(logs, e))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(is_iteration_file)
+        })
+        .collect();
+    paths.sort();
+    let into = archive.join(logs.file_name().unwrap_or(logs.as_os_str()));
+    let paths: Vec<&Path> = paths.iter().map(PathBuf::as_path).collect();
+    self::archive(&paths, &into)
+}
+
+/// `iteration_3.log`, `tests_3.json`, `junit_3`, `gate_3.txt` or
+/// `plan_snapshot.json`.
+fn is_iteration_file(name: &str) -> bool {
+    let numbered = |prefix: &str, suffix: &str| {
+        name.strip_prefix(prefix)
+            .and_then(|rest| rest.strip_suffix(suffix))
+            .is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+    };
+    name == "plan_snapshot.json"
+        || numbered("iteration_", ".log")
+        || numbered("tests_", ".json")
+        || numbered("junit_", "")
+        || numbered("gate_", ".txt")
+}
+
+/// Write `text` to `path`, creating its directory.
+pub fn write(path: &Path, text: &str) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| Error::io(dir, e))?;
+    }
+    fs::write(path, text).map_err(|e| Error::io(path, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spec;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn names_become_slugs_and_tags() {
+        assert_eq!(slug("Order Export"), "order-export");
+        assert_eq!(slug("  CSV/Excel export (v2) "), "csv-excel-export-v2");
+        assert_eq!(slug("--"), "");
+        assert_eq!(promise_tag("order-export"), "ORDER_EXPORT_COMPLETE");
+        assert!(spec::is_requirement_id(&format!(
+            "{}-1",
+            requirement_prefix("order-export-v2")
+        )));
+    }
+
+    #[test]
+    fn spec_skeleton_has_traceable_requirements() {
+        let feature = Feature {
+            name: "Order Export".to_string(),
+            slug: "order-export".to_string(),
+            ticket: None,
+            date: "2024-02-29".to_string(),
+        };
+        let requirements = spec::parse(&feature.spec(), Path::new("order-export.md"));
+        assert!(!requirements.is_empty());
+        assert!(requirements
+            .iter()
+            .all(|r| r.id.starts_with("ORDER-EXPORT-")));
+    }
+
+    #[test]
+    fn iteration_files_are_recognized() {
+        for name in [
+            "iteration_3.log",
+            "tests_12.json",
+            "junit_3",
+            "gate_0.txt",
+            "plan_snapshot.json",
+        ] {
+            assert!(is_iteration_file(name), "{name}");
+        }
+        for name in [
+            "iteration_.log",
+            "iteration_x.log",
+            "ledger.json",
+            "gate.json",
+            "claims",
+            "model.json",
+        ] {
+            assert!(!is_iteration_file(name), "{name}");
+        }
+    }
+
+    #[test]
+    fn runs_are_archived_under_a_free_name() {
+        let dir = TempDir::new("feature");
+        let archived = dir.join("archive");
+        let first = archive_dir(&archived, "2024-02-29", "Artwork Audit");
+        assert_eq!(first, archived.join("2024-02-29-artwork-audit"));
+        fs::create_dir_all(&first).unwrap();
+        assert_eq!(
+            archive_dir(&archived, "2024-02-29", "Artwork Audit"),
+            archived.join("2024-02-29-artwork-audit-2")
+        );
+
+        let plan = dir.write("IMPLEMENTATION_PLAN.md", "# Plan\n");
+        dir.write("logs/iteration_1.log", "{}\n");
+        dir.write("logs/junit_1/report.xml", "<testsuite/>\n");
+        dir.write("logs/ledger.json", "{}\n");
+        assert_eq!(
+            archive(&[&plan, &dir.join("missing.md")], &first).unwrap(),
+            [first.join("IMPLEMENTATION_PLAN.md")]
+        );
+        assert_eq!(
+            archive_logs(&dir.join("logs"), &first).unwrap(),
+            [
+                first.join("logs/iteration_1.log"),
+                first.join("logs/junit_1")
+            ]
+        );
+        assert!(first.join("logs/junit_1/report.xml").exists());
+        assert!(dir.join("logs/ledger.json").exists());
+        assert!(!plan.exists());
+    }
+}
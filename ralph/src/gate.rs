@@ -0,0 +1,412 @@
+//! Test gate run by the loop itself after every round.
+//!
+//! The prompts tell the model not to move on while tests fail, but only the
+//! loop can enforce that. It runs the configured test command, parses
+//! pytest's summary, and compares the result with the last accepted run
+//! (the baseline). When the suite regressed, the round's commits are rolled
+//! back: reverted by a commit that restores the tree as it was, or moved to
+//! a quarantine branch for a human to look at.
+
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::git;
+use crate::stream::Event;
+use crate::supervise::{self, Limits};
+
+/// Exit code of `ralph gate run` when the suite regressed.
+pub const EXIT_REGRESSED: u8 = 8;
+
+/// What the loop does with a round that made the suite worse.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum OnRegression {
+    /// Commit a revert that restores the tree from before the round.
+    Revert,
+    /// Move the round's commits to `ralph/quarantine/BRANCH-N` and reset the
+    /// branch to where the round started.
+    Quarantine,
+    /// Only record the regression.
+    Keep,
+}
+
+impl fmt::Display for OnRegression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            OnRegression::Revert => "revert",
+            OnRegression::Quarantine => "quarantine",
+            OnRegression::Keep => "keep",
+        })
+    }
+}
+
+/// What the gate did with a round.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Action {
+    /// Accepted, or regressed without commits to roll back.
+    None,
+    Reverted,
+    Quarantined,
+    /// Regressed, but kept as `on_regression = "keep"` asks.
+    Kept,
+}
+
+impl Action {
+    /// Whether the round's commits are no longer on the branch.
+    pub fn rolled_back(self) -> bool {
+        matches!(self, Action::Reverted | Action::Quarantined)
+    }
+}
+
+/// The `test_gate` record appended to the logs of the round's iterations.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Record {
+    pub command: String,
+    pub exit_code: u8,
+    /// `None` when the output had no pytest summary.
+    pub results: Option<Results>,
+    /// Results of the baseline the run was compared with.
+    pub baseline: Option<Results>,
+    pub regressions: Vec<String>,
+    /// Changes reported without rolling the round back.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+    pub action: Action,
+    /// Branch holding the commits of a quarantined round.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quarantine: Option<String>,
+    /// File with the full test output.
+    pub output: PathBuf,
+}
+
+impl Record {
+    /// Whether the suite ran and nothing failed.
+    pub fn passed(&self) -> bool {
+        self.results.as_ref().is_some_and(Results::is_green)
+    }
+}
+
+/// The last `test_gate` record of an iteration log.
+pub fn last_record(events: &[Event]) -> Option<Record> {
+    events.iter().rev().find_map(|event| match event {
+        Event::Record(record) if record.event == "test_gate" => {
+            serde_json::from_value(serde_json::Value::Object(record.fields.clone())).ok()
+        }
+        _ => None,
+    })
+}
+
+/// Counts and failing tests from one pytest run.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Results {
+    pub passed: u32,
+    pub failed: u32,
+    pub errors: u32,
+    pub skipped: u32,
+    /// Node IDs from the `FAILED` and `ERROR` lines of the short summary.
+    pub failing: Vec<String>,
+}
+
+impl Results {
+    /// Results from pytest's output, or `None` when it has no final summary
+    /// line (the run crashed, or the command is not pytest).
+    pub fn parse(output: &str) -> Option<Results> {
+        let mut results = output.lines().rev().find_map(summary_line)?;
+        for line in output.lines() {
+            let id = line
+                .strip_prefix("FAILED ")
+                .or_else(|| line.strip_prefix("ERROR "));
+            if let Some(id) = id {
+                let id = id.split(" - ").next().unwrap_or(id).trim();
+                if !results.failing.iter().any(|f| f == id) {
+                    results.failing.push(id.to_string());
+                }
+            }
+        }
+        results.failing.sort();
+        Some(results)
+    }
+
+    pub fn is_green(&self) -> bool {
+        self.failed == 0 && self.errors == 0
+    }
+}
+
+impl fmt::Display for Results {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} passed, {} failed, {} errors",
+            self.passed, self.failed, self.errors
+        )?;
+        if self.skipped > 0 {
+            write!(f, ", {} skipped", self.skipped)?;
+        }
+        Ok(())
+    }
+}
+
+/// Counts of a summary line such as `=== 2 failed, 10 passed in 3.21s ===`
+/// (or the same without the rule, as `pytest -q` prints it).
+fn summary_line(line: &str) -> Option<Results> {
+    let line = line.trim().trim_matches('=').trim();
+    let (counts, duration) = line.rsplit_once(" in ")?;
+    if !duration.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    if counts == "no tests ran" {
+        return Some(Results::default());
+    }
+    let mut results = Results::default();
+    for part in counts.split(", ") {
+        let (count, word) = part.split_once(' ')?;
+        let count: u32 = count.parse().ok()?;
+        match word {
+            "passed" | "xpassed" => results.passed += count,
+            "failed" => results.failed += count,
+            "error" | "errors" => results.errors += count,
+            "skipped" | "xfailed" | "deselected" => results.skipped += count,
+            "warning" | "warnings" | "rerun" | "reruns" => {}
+            _ => return None,
+        }
+    }
+    Some(results)
+}
+
+/// Why `after` is worse than `before`; empty when it is not. Only failures
+/// count: a test that fails now but did not before, more failures and
+/// errors, or, once a baseline exists, a run without a summary. Fewer
+/// passing tests alone is a [`warnings`] matter.
+pub fn regressions(before: &Results, after: Option<&Results>) -> Vec<String> {
+    let Some(after) = after else {
+        return vec!["the test run ended without a pytest summary".to_string()];
+    };
+    let mut reasons = Vec::new();
+    let newly: Vec<&str> = after
+        .failing
+        .iter()
+        .filter(|id| !before.failing.contains(id))
+        .map(String::as_str)
+        .collect();
+    if !newly.is_empty() {
+        reasons.push(format!("newly failing: {}", newly.join(", ")));
+    }
+    let (was, is) = (before.failed + before.errors, after.failed + after.errors);
+    if is > was {
+        reasons.push(format!("failures and errors went from {was} to {is}"));
+    }
+    reasons
+}
+
+/// Changes worth reporting that are not a regression: fewer passing tests
+/// with nothing newly failing, as when a refactor merges or removes tests.
+pub fn warnings(before: &Results, after: Option<&Results>) -> Vec<String> {
+    match after {
+        Some(after) if after.passed < before.passed => vec![format!(
+            "passing tests went from {} to {}",
+            before.passed, after.passed
+        )],
+        _ => Vec::new(),
+    }
+}
+
+/// The last accepted run, kept between rounds in `logs/gate.json`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Baseline {
+    /// Iteration whose run this is; 0 for the run before the first one.
+    pub iteration: u32,
+    /// `None` when that run had no pytest summary.
+    pub results: Option<Results>,
+}
+
+impl Baseline {
+    /// The saved baseline, or `None` before the first run.
+    pub fn load(path: &Path) -> Result<Option<Baseline>> {
+        match fs::read_to_string(path) {
+            Ok(text) => serde_json::from_str(&text)
+                .map(Some)
+                .map_err(|e| Error::json(path, e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(Error::io(path, e)),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self).expect("baseline serializes");
+        fs::write(path, json + "\n").map_err(|e| Error::io(path, e))
+    }
+}
+
+/// Run `command` through the shell under `limits`, with its output in
+/// `output`. Returns its exit code and the results parsed from the output.
+pub fn run(command: &str, limits: Limits, output: &Path) -> Result<(u8, Option<Results>)> {
+    fs::write(output, "").map_err(|e| Error::io(output, e))?;
+    let shell = ["sh".to_string(), "-c".to_string(), command.to_string()];
+    let outcome = supervise::run(&shell, limits, output)?;
+    let text = fs::read_to_string(output).map_err(|e| Error::io(output, e))?;
+    Ok((outcome.exit_code(), Results::parse(&text)))
+}
+
+/// Undo the commits since `head_before` with one commit restoring its tree,
+/// which also works across merge commits. Returns false when there was
+/// nothing to undo. Uncommitted changes survive, see [`keeping_changes`].
+pub fn revert(head_before: &str, message: &str) -> Result<bool> {
+    keeping_changes(head_before, || {
+        git::run(&["read-tree", "-u", "--reset", head_before])?;
+        if git::run(&["diff", "--cached", "--quiet"]).is_ok() {
+            return Ok(false);
+        }
+        git::run(&["commit", "--quiet", "--no-verify", "-m", message])?;
+        Ok(true)
+    })
+}
+
+/// Point `branch` at HEAD and reset the current branch to `head_before`.
+/// Uncommitted changes survive, see [`keeping_changes`].
+pub fn quarantine(head_before: &str, branch: &str) -> Result<()> {
+    keeping_changes(head_before, || {
+        git::run(&["branch", "--force", branch, "HEAD"])?;
+        git::run(&["reset", "--quiet", "--hard", head_before])?;
+        Ok(())
+    })
+}
+
+/// Run `rollback` with uncommitted changes to tracked files stashed, and
+/// restore them afterwards: a rollback undoes the round's commits, not work
+/// that was never committed. Refuses (`Error::Gate`) when those changes touch
+/// files the round's commits changed, since they would not apply on top.
+fn keeping_changes<T>(head_before: &str, rollback: impl FnOnce() -> Result<T>) -> Result<T> {
+    let dirty = git::run(&["diff", "--name-only", "HEAD"])?;
+    if dirty.is_empty() {
+        return rollback();
+    }
+    let round = git::run(&["diff", "--name-only", head_before, "HEAD"])?;
+    let clashes: Vec<&str> = dirty
+        .lines()
+        .filter(|file| round.lines().any(|changed| changed == *file))
+        .collect();
+    if !clashes.is_empty() {
+        return Err(Error::Gate(format!(
+            "uncommitted changes to {} would be lost; commit or stash them",
+            clashes.join(", ")
+        )));
+    }
+    git::run(&[
+        "stash",
+        "push",
+        "--quiet",
+        "--message",
+        "ralph: gate rollback",
+    ])?;
+    let rolled_back = rollback();
+    git::run(&["stash", "pop", "--quiet"])?;
+    rolled_back
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OUTPUT: &str = "\
+tests/unit/reviews/test_views.py ..F.E
+=========================== short test summary info ============================
+FAILED tests/unit/reviews/test_views.py::test_list - AssertionError: 0 != 2
+ERROR tests/unit/reviews/test_views.py::test_create - fixture 'db' not found
+FAILED tests/unit/reviews/test_views.py::test_list - AssertionError: 0 != 2
+=== 1 failed, 3 passed, 1 skipped, 2 warnings, 1 error in 3.21s ===
+";
+
+    #[test]
+    fn summary_lines() {
+        let counts = |line| summary_line(line).map(|r| (r.passed, r.failed, r.errors, r.skipped));
+        assert_eq!(
+            counts("=== 2 failed, 10 passed in 3.21s ==="),
+            Some((10, 2, 0, 0))
+        );
+        assert_eq!(
+            counts("4 passed, 1 xfailed, 2 deselected in 0.50s"),
+            Some((4, 0, 0, 3))
+        );
+        assert_eq!(counts("no tests ran in 0.01s"), Some((0, 0, 0, 0)));
+        assert_eq!(counts("Ran 3 tests in 0.2s"), None);
+        assert_eq!(counts("2 passed in total"), None);
+    }
+
+    #[test]
+    fn parse_collects_failing_tests_once() {
+        let results = Results::parse(OUTPUT).unwrap();
+        assert_eq!(
+            (
+                results.passed,
+                results.failed,
+                results.errors,
+                results.skipped
+            ),
+            (3, 1, 1, 1)
+        );
+        assert_eq!(
+            results.failing,
+            [
+                "tests/unit/reviews/test_views.py::test_create",
+                "tests/unit/reviews/test_views.py::test_list",
+            ]
+        );
+        assert!(!results.is_green());
+        assert_eq!(
+            results.to_string(),
+            "3 passed, 1 failed, 1 errors, 1 skipped"
+        );
+        assert_eq!(Results::parse("Traceback (most recent call last):"), None);
+    }
+
+    #[test]
+    fn regressions_between_runs() {
+        let before = Results {
+            passed: 10,
+            failed: 1,
+            failing: vec!["test_a".to_string()],
+            ..Results::default()
+        };
+        assert!(regressions(&before, Some(&before)).is_empty());
+
+        let fixed = Results {
+            passed: 11,
+            ..Results::default()
+        };
+        assert!(regressions(&before, Some(&fixed)).is_empty());
+
+        let worse = Results {
+            passed: 9,
+            failed: 2,
+            failing: vec!["test_a".to_string(), "test_b".to_string()],
+            ..Results::default()
+        };
+        assert_eq!(
+            regressions(&before, Some(&worse)),
+            [
+                "newly failing: test_b",
+                "failures and errors went from 1 to 2",
+            ]
+        );
+
+        let fewer = Results {
+            passed: 8,
+            ..before.clone()
+        };
+        assert!(regressions(&before, Some(&fewer)).is_empty());
+        assert_eq!(
+            warnings(&before, Some(&fewer)),
+            ["passing tests went from 10 to 8"]
+        );
+        assert!(warnings(&before, Some(&fixed)).is_empty());
+        assert_eq!(
+            regressions(&before, None),
+            ["the test run ended without a pytest summary"]
+        );
+    }
+}
@@ -0,0 +1,85 @@
+//! Thin wrapper around the `git` command line.
+//!
+//! The loop already depends on git being installed, so shelling out keeps
+//! the helper free of a libgit2 dependency and behaves exactly like the
+//! commands a human would run.
+
+use std::path::PathBuf;
+use std::process::Command;
+
+use crate::error::{Error, Result};
+
+/// Run git with `args` in the current directory and return its trimmed
+/// stdout, or its stderr as an error when it exits non-zero.
+pub fn run(args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .args(args)
+        .output()
+        .map_err(|e| Error::io("git", e))?;
+    if !output.status.success() {
+        return Err(Error::Git {
+            command: args.join(" "),
+            stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        });
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Commit id of `rev`, or `None` when it does not resolve.
+pub fn rev_parse(rev: &str) -> Result<Option<String>> {
+    let output = Command::new("git")
+        .args([
+            "rev-parse",
+            "--verify",
+            "--quiet",
+            &format!("{rev}^{{commit}}"),
+        ])
+        .output()
+        .map_err(|e| Error::io("git", e))?;
+    Ok(output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string()))
+}
+
+/// Number of commits reachable from `to` but not from `from`. A `from` of
+/// `none`, which the loop records for an unborn HEAD, counts every commit
+/// of `to`.
+pub fn commits_between(from: &str, to: &str) -> Result<usize> {
+    let range = if from == "none" {
+        if rev_parse(to)?.is_none() {
+            return Ok(0);
+        }
+        to.to_string()
+    } else {
+        format!("{from}..{to}")
+    };
+    let count = run(&["rev-list", "--count", &range])?;
+    Ok(count.parse().unwrap_or_default())
+}
+
+/// The tree of an empty repository, to diff an unborn HEAD against.
+const EMPTY_TREE: &str = "4b825dc642cb6eb9a060e54bf8d69288fbee4904";
+
+/// Files that differ from `rev` in the working tree (committed, staged or
+/// not) plus untracked files that are not ignored, as absolute paths.
+/// `none`, which the loop records for an unborn HEAD, compares with an
+/// empty tree.
+pub fn changed_files(rev: &str) -> Result<Vec<PathBuf>> {
+    let top = PathBuf::from(run(&["rev-parse", "--show-toplevel"])?);
+    let rev = if rev == "none" { EMPTY_TREE } else { rev };
+    let tracked = run(&["diff", "--name-only", rev, "--", ":/"])?;
+    let untracked = run(&[
+        "ls-files",
+        "--others",
+        "--exclude-standard",
+        "--full-name",
+        ":/",
+    ])?;
+    Ok(tracked
+        .lines()
+        .chain(untracked.lines())
+        .filter(|line| !line.is_empty())
+        .map(|line| top.join(line))
+        .collect())
+}
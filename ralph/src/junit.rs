@@ -0,0 +1,585 @@
+//! Test results from JUnit XML reports.
+//!
+//! pytest (`--junitxml`), vitest (`--reporter=junit`) and jest (jest-junit)
+//! all write the same format, so an iteration's test runs can be read back
+//! per suite and per test instead of from the model's account of them. The
+//! loop points the runners at `logs/junit_N/`, and after the iteration the
+//! reports are stored as `logs/tests_N.json` with what changed since the
+//! earlier iterations.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use quick_xml::events::{BytesStart, Event as XmlEvent};
+use quick_xml::Reader;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::stream::Event;
+
+/// Longest failure message kept per test.
+const MESSAGE_LIMIT: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Outcome {
+    Passed,
+    Skipped,
+    Failed,
+    Error,
+}
+
+impl Outcome {
+    pub fn is_failing(self) -> bool {
+        matches!(self, Outcome::Failed | Outcome::Error)
+    }
+}
+
+/// One `<testcase>`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Case {
+    /// `classname::name`, e.g. `tests.test_audit.TestApi::test_list`.
+    pub id: String,
+    pub outcome: Outcome,
+    /// First line of the failure or error message.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// One `<testsuite>` with its test cases.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Suite {
+    pub name: String,
+    /// Report the suite was read from.
+    pub report: PathBuf,
+    pub cases: Vec<Case>,
+}
+
+impl Suite {
+    pub fn counts(&self) -> Counts {
+        Counts::of(&self.cases)
+    }
+}
+
+/// Tests per outcome.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Counts {
+    pub tests: usize,
+    pub passed: usize,
+    pub failed: usize,
+    pub errors: usize,
+    pub skipped: usize,
+}
+
+impl Counts {
+    pub fn of<'a>(cases: impl IntoIterator<Item = &'a Case>) -> Counts {
+        let mut counts = Counts::default();
+        for case in cases {
+            counts.tests += 1;
+            match case.outcome {
+                Outcome::Passed => counts.passed += 1,
+                Outcome::Skipped => counts.skipped += 1,
+                Outcome::Failed => counts.failed += 1,
+                Outcome::Error => counts.errors += 1,
+            }
+        }
+        counts
+    }
+
+    pub fn is_green(&self) -> bool {
+        self.failed == 0 && self.errors == 0
+    }
+}
+
+impl fmt::Display for Counts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} tests, {} passed, {} failed, {} errors",
+            self.tests, self.passed, self.failed, self.errors
+        )?;
+        if self.skipped > 0 {
+            write!(f, ", {} skipped", self.skipped)?;
+        }
+        Ok(())
+    }
+}
+
+/// Suites of one JUnit XML document. Cases outside any `<testsuite>`, and
+/// suites named `pytest` (pytest's default), are named after the report's
+/// file stem, so `backend.xml` gives suite `backend`.
+pub fn parse(xml: &str, report: &Path) -> Result<Vec<Suite>> {
+    let xml_error = |source| Error::Xml {
+        path: report.to_path_buf(),
+        source,
+    };
+    let stem = report
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let mut reader = Reader::from_str(xml);
+    reader.trim_text(true);
+
+    let mut suites: Vec<Suite> = Vec::new();
+    let mut open: Vec<Suite> = Vec::new();
+    let mut case: Option<Case> = None;
+    let mut loose: Vec<Case> = Vec::new();
+    loop {
+        let event = reader.read_event().map_err(xml_error)?;
+        let (element, empty) = match &event {
+            XmlEvent::Start(e) => (e, false),
+            XmlEvent::Empty(e) => (e, true),
+            XmlEvent::End(e) => {
+                match e.name().as_ref() {
+                    b"testsuite" => {
+                        if let Some(suite) = open.pop().filter(|s| !s.cases.is_empty()) {
+                            suites.push(suite);
+                        }
+                    }
+                    b"testcase" => {
+                        if let Some(case) = case.take() {
+                            match open.last_mut() {
+                                Some(suite) => suite.cases.push(case),
+                                None => loose.push(case),
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+            XmlEvent::Eof => break,
+            _ => continue,
+        };
+        match element.name().as_ref() {
+            b"testsuite" if !empty => {
+                let name = attribute(element, "name")
+                    .map_err(xml_error)?
+                    .filter(|name| !name.is_empty() && name != "pytest")
+                    .unwrap_or_else(|| stem.clone());
+                open.push(Suite {
+                    name,
+                    report: report.to_path_buf(),
+                    cases: Vec::new(),
+                });
+            }
+            b"testcase" => {
+                let name = attribute(element, "name").map_err(xml_error)?;
+                let class = attribute(element, "classname").map_err(xml_error)?;
+                let id = match (class.filter(|c| !c.is_empty()), name) {
+                    (Some(class), Some(name)) => format!("{class}::{name}"),
+                    (Some(only), None) | (None, Some(only)) => only,
+                    (None, None) => continue,
+                };
+                let new = Case {
+                    id,
+                    outcome: Outcome::Passed,
+                    message: None,
+                };
+                match (empty, open.last_mut()) {
+                    (false, _) => case = Some(new),
+                    (true, Some(suite)) => suite.cases.push(new),
+                    (true, None) => loose.push(new),
+                }
+            }
+            tag @ (b"failure" | b"error" | b"skipped") => {
+                let Some(case) = case.as_mut() else {
+                    continue;
+                };
+                let outcome = match tag {
+                    b"failure" => Outcome::Failed,
+                    b"error" => Outcome::Error,
+                    _ => Outcome::Skipped,
+                };
+                if outcome > case.outcome {
+                    case.outcome = outcome;
+                    case.message = attribute(element, "message")
+                        .map_err(xml_error)?
+                        .and_then(|m| first_line(&m))
+                        .filter(|_| outcome.is_failing());
+                }
+            }
+            _ => {}
+        }
+    }
+    if !loose.is_empty() {
+        suites.push(Suite {
+            name: stem,
+            report: report.to_path_buf(),
+            cases: loose,
+        });
+    }
+    Ok(suites)
+}
+
+fn attribute(element: &BytesStart, name: &str) -> quick_xml::Result<Option<String>> {
+    match element.try_get_attribute(name)? {
+        Some(attr) => Ok(Some(attr.unescape_value()?.into_owned())),
+        None => Ok(None),
+    }
+}
+
+fn first_line(message: &str) -> Option<String> {
+    let line = message.lines().map(str::trim).find(|l| !l.is_empty())?;
+    Some(match line.char_indices().nth(MESSAGE_LIMIT) {
+        Some((end, _)) => format!("{}...", &line[..end]),
+        None => line.to_string(),
+    })
+}
+
+/// Suites of every `.xml` file in `paths`, which are reports or directories
+/// of reports, in file name order.
+pub fn load(paths: &[PathBuf]) -> Result<Vec<Suite>> {
+    let mut reports = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            let entries = fs::read_dir(path).map_err(|e| Error::io(path, e))?;
+            let mut found = Vec::new();
+            for entry in entries {
+                let file = entry.map_err(|e| Error::io(path, e))?.path();
+                if file.extension().is_some_and(|ext| ext == "xml") {
+                    found.push(file);
+                }
+            }
+            found.sort();
+            reports.extend(found);
+        } else if path.exists() {
+            reports.push(path.clone());
+        }
+    }
+    let mut suites = Vec::new();
+    for report in reports {
+        let xml = fs::read_to_string(&report).map_err(|e| Error::io(&report, e))?;
+        suites.extend(parse(&xml, &report)?);
+    }
+    Ok(suites)
+}
+
+/// How an iteration's tests compare with the earlier iterations of the run.
+///
+/// Each runner's report only covers its last run in an iteration, which may
+/// have been a single file, so the comparison is against the last known
+/// outcome of every test rather than against one earlier iteration, and a
+/// test missing from the reports is "not run" rather than removed.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Diff {
+    /// Latest earlier iteration with results.
+    pub previous: u32,
+    /// Failing now, and last seen not failing (or never seen).
+    pub newly_failing: Vec<String>,
+    /// Passing now, last seen failing.
+    pub newly_passing: Vec<String>,
+    /// Not seen in an earlier iteration.
+    pub added: Vec<String>,
+    /// Seen in an earlier iteration but not run in this one.
+    pub not_run: Vec<String>,
+}
+
+impl Diff {
+    /// Compare `suites` with `earlier` results, oldest first. `None` when
+    /// there are none.
+    pub fn between(earlier: &[Results], suites: &[Suite]) -> Option<Diff> {
+        let mut known: BTreeMap<&str, Outcome> = BTreeMap::new();
+        for case in earlier.iter().flat_map(Results::cases) {
+            known.insert(&case.id, case.outcome);
+        }
+        let mut diff = Diff {
+            previous: earlier.last()?.iteration,
+            ..Diff::default()
+        };
+        let now: Vec<&Case> = suites.iter().flat_map(|s| &s.cases).collect();
+        for case in &now {
+            let was = known.get(case.id.as_str()).copied();
+            if was.is_none() {
+                diff.added.push(case.id.clone());
+            }
+            match (was.is_some_and(Outcome::is_failing), case.outcome) {
+                (false, outcome) if outcome.is_failing() => {
+                    diff.newly_failing.push(case.id.clone());
+                }
+                (true, Outcome::Passed) => diff.newly_passing.push(case.id.clone()),
+                _ => {}
+            }
+        }
+        for id in known.keys() {
+            if !now.iter().any(|c| c.id == *id) {
+                diff.not_run.push(id.to_string());
+            }
+        }
+        Some(diff)
+    }
+
+    /// Whether no test changed outcome or appeared. Tests that were not run
+    /// do not count.
+    pub fn is_empty(&self) -> bool {
+        self.newly_failing.is_empty() && self.newly_passing.is_empty() && self.added.is_empty()
+    }
+}
+
+/// An iteration's test results, kept as `logs/tests_N.json`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Results {
+    pub iteration: u32,
+    /// ID of the loop run the iteration belonged to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run: Option<String>,
+    pub suites: Vec<Suite>,
+    /// `None` when no earlier iteration has results.
+    pub diff: Option<Diff>,
+}
+
+impl Results {
+    pub fn cases(&self) -> impl Iterator<Item = &Case> {
+        self.suites.iter().flat_map(|s| &s.cases)
+    }
+
+    pub fn counts(&self) -> Counts {
+        Counts::of(self.cases())
+    }
+
+    pub fn load(path: &Path) -> Result<Results> {
+        let text = fs::read_to_string(path).map_err(|e| Error::io(path, e))?;
+        serde_json::from_str(&text).map_err(|e| Error::json(path, e))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self).expect("results serialize");
+        fs::write(path, json + "\n").map_err(|e| Error::io(path, e))
+    }
+
+    /// The `test_results` record for the iteration log: counts per suite
+    /// and the diff, without the individual tests.
+    pub fn record(&self, path: &Path) -> Record {
+        Record {
+            suites: self
+                .suites
+                .iter()
+                .map(|s| SuiteCounts {
+                    name: s.name.clone(),
+                    counts: s.counts(),
+                })
+                .collect(),
+            total: self.counts(),
+            diff: self.diff.clone(),
+            results: path.to_path_buf(),
+        }
+    }
+}
+
+/// `tests_N.json` in `dir`.
+pub fn results_path(dir: &Path, iteration: u32) -> PathBuf {
+    dir.join(format!("tests_{iteration}.json"))
+}
+
+/// Results of the iterations of `run` before `iteration`, oldest first.
+/// Results an earlier run left behind are skipped.
+pub fn earlier(dir: &Path, iteration: u32, run: Option<&str>) -> Result<Vec<Results>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(Error::io(dir, e)),
+    };
+    let mut found: Vec<(u32, PathBuf)> = Vec::new();
+    for entry in entries {
+        let path = entry.map_err(|e| Error::io(dir, e))?.path();
+        let n = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_prefix("tests_")?.strip_suffix(".json"))
+            .and_then(|n| n.parse::<u32>().ok());
+        if let Some(n) = n.filter(|&n| n < iteration) {
+            found.push((n, path));
+        }
+    }
+    found.sort();
+    let mut results = Vec::new();
+    for (_, path) in found {
+        let earlier = Results::load(&path)?;
+        if earlier.run.as_deref() == run {
+            results.push(earlier);
+        }
+    }
+    Ok(results)
+}
+
+/// Counts of one suite in a [`Record`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SuiteCounts {
+    pub name: String,
+    #[serde(flatten)]
+    pub counts: Counts,
+}
+
+/// The `test_results` record appended to an iteration log.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Record {
+    pub suites: Vec<SuiteCounts>,
+    pub total: Counts,
+    pub diff: Option<Diff>,
+    /// `tests_N.json` with every test.
+    pub results: PathBuf,
+}
+
+/// The last `test_results` record of an iteration log.
+pub fn last_record(events: &[Event]) -> Option<Record> {
+    events.iter().rev().find_map(|event| match event {
+        Event::Record(record) if record.event == "test_results" => {
+            serde_json::from_value(serde_json::Value::Object(record.fields.clone())).ok()
+        }
+        _ => None,
+    })
+}
+
+/// The "Unit Tests" table of the verification report, one row per suite.
+pub fn table(suites: &[Suite]) -> String {
+    let mut out = String::from(
+        "| Test Suite | Tests | Passed | Failed | Status |\n\
+         |------------|-------|--------|--------|--------|\n",
+    );
+    for suite in suites {
+        let counts = suite.counts();
+        out.push_str(&format!(
+            "| {} | {} | {} | {} | {} |\n",
+            suite.name,
+            counts.tests,
+            counts.passed,
+            counts.failed + counts.errors,
+            if counts.is_green() { "PASS" } else { "FAIL" }
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REPORT: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<testsuites>
+  <testsuite name="pytest" tests="4">
+    <testcase classname="tests.test_audit" name="test_list"/>
+    <testcase classname="tests.test_audit" name="test_create">
+      <failure message="AssertionError: 0 != 2&#10;details">trace</failure>
+    </testcase>
+    <testcase classname="tests.test_audit" name="test_upload">
+      <skipped message="needs drive"/>
+    </testcase>
+    <testcase classname="tests.test_audit" name="test_delete">
+      <skipped/>
+      <error message="fixture 'db' not found"/>
+    </testcase>
+  </testsuite>
+  <testsuite name="frontend" tests="0"/>
+</testsuites>
+"#;
+
+    fn case(id: &str, outcome: Outcome) -> Case {
+        Case {
+            id: id.to_string(),
+            outcome,
+            message: None,
+        }
+    }
+
+    fn results(iteration: u32, cases: Vec<Case>) -> Results {
+        Results {
+            iteration,
+            run: None,
+            suites: vec![Suite {
+                name: "backend".to_string(),
+                report: PathBuf::from("backend.xml"),
+                cases,
+            }],
+            diff: None,
+        }
+    }
+
+    #[test]
+    fn parse_reads_outcomes_and_messages() {
+        let suites = parse(REPORT, Path::new("logs/junit_3/backend.xml")).unwrap();
+        assert_eq!(suites.len(), 1);
+        assert_eq!(suites[0].name, "backend");
+        let cases: Vec<(&str, Outcome, Option<&str>)> = suites[0]
+            .cases
+            .iter()
+            .map(|c| (c.id.as_str(), c.outcome, c.message.as_deref()))
+            .collect();
+        assert_eq!(
+            cases,
+            [
+                ("tests.test_audit::test_list", Outcome::Passed, None),
+                (
+                    "tests.test_audit::test_create",
+                    Outcome::Failed,
+                    Some("AssertionError: 0 != 2")
+                ),
+                ("tests.test_audit::test_upload", Outcome::Skipped, None),
+                (
+                    "tests.test_audit::test_delete",
+                    Outcome::Error,
+                    Some("fixture 'db' not found")
+                ),
+            ]
+        );
+        assert_eq!(
+            suites[0].counts().to_string(),
+            "4 tests, 1 passed, 1 failed, 1 errors, 1 skipped"
+        );
+    }
+
+    #[test]
+    fn loose_cases_are_named_after_the_report() {
+        let xml = r#"<testcase name="renders"/><testcase classname="" name="loads"/>"#;
+        let suites = parse(xml, Path::new("vitest.xml")).unwrap();
+        assert_eq!(suites[0].name, "vitest");
+        assert_eq!(suites[0].cases[1].id, "loads");
+        assert!(parse("<testsuite><testcase", Path::new("bad.xml")).is_err());
+    }
+
+    #[test]
+    fn diff_uses_the_last_known_outcome() {
+        let earlier = [
+            results(
+                1,
+                vec![
+                    case("a", Outcome::Failed),
+                    case("b", Outcome::Passed),
+                    case("c", Outcome::Passed),
+                ],
+            ),
+            // Iteration 2 only ran `c`, which now fails.
+            results(2, vec![case("c", Outcome::Failed)]),
+        ];
+        let now = results(
+            3,
+            vec![
+                case("a", Outcome::Passed),
+                case("c", Outcome::Failed),
+                case("d", Outcome::Error),
+            ],
+        );
+        let diff = Diff::between(&earlier, &now.suites).unwrap();
+        assert_eq!(diff.previous, 2);
+        assert_eq!(diff.newly_failing, ["d"]);
+        assert_eq!(diff.newly_passing, ["a"]);
+        assert_eq!(diff.added, ["d"]);
+        assert_eq!(diff.not_run, ["b"]);
+        assert!(!diff.is_empty());
+
+        assert_eq!(Diff::between(&[], &now.suites), None);
+    }
+
+    #[test]
+    fn tests_not_run_leave_the_diff_empty() {
+        let earlier = [results(
+            1,
+            vec![case("a", Outcome::Passed), case("b", Outcome::Passed)],
+        )];
+        let now = results(2, vec![case("a", Outcome::Passed)]);
+        let diff = Diff::between(&earlier, &now.suites).unwrap();
+        assert_eq!(diff.not_run, ["b"]);
+        assert!(diff.is_empty());
+    }
+}
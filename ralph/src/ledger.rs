@@ -0,0 +1,256 @@
+//! Token and cost ledger kept next to the iteration logs.
+//!
+//! After every iteration the loop copies the usage and cost from the final
+//! stream-json `result` message into `logs/ledger.json`. Totals are kept per
+//! run (one invocation of `loop.sh`) and per branch, and a budget on either
+//! scope stops the loop once exceeded.
+
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::stream::{Event, Usage};
+
+/// Exit code of `ralph ledger add` when the budget is exceeded.
+pub const EXIT_OVER_BUDGET: u8 = 5;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entry {
+    pub run: String,
+    pub branch: String,
+    pub iteration: u32,
+    pub recorded_at: u64,
+    pub cost_usd: f64,
+    pub usage: Usage,
+    /// False when the log had no `result` message (killed or crashed
+    /// iteration), so the real spend is unknown and likely higher.
+    pub complete: bool,
+}
+
+impl Entry {
+    /// Build an entry from a decoded iteration log.
+    pub fn from_events(
+        run: &str,
+        branch: &str,
+        iteration: u32,
+        recorded_at: u64,
+        events: &[Event],
+    ) -> Entry {
+        let result = events.iter().rev().find_map(|event| match event {
+            Event::Result(result) => Some(result),
+            _ => None,
+        });
+        Entry {
+            run: run.to_string(),
+            branch: branch.to_string(),
+            iteration,
+            recorded_at,
+            cost_usd: result.and_then(|r| r.total_cost_usd).unwrap_or_default(),
+            usage: result.and_then(|r| r.usage).unwrap_or_default(),
+            complete: result.is_some(),
+        }
+    }
+}
+
+/// Cumulative spend over a set of entries.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct Spend {
+    pub iterations: usize,
+    pub cost_usd: f64,
+    pub usage: Usage,
+}
+
+impl Spend {
+    /// All tokens billed, including cache reads and writes.
+    pub fn tokens(&self) -> u64 {
+        self.usage.total()
+    }
+
+    fn add(&mut self, entry: &Entry) {
+        self.iterations += 1;
+        self.cost_usd += entry.cost_usd;
+        self.usage.input_tokens += entry.usage.input_tokens;
+        self.usage.output_tokens += entry.usage.output_tokens;
+        self.usage.cache_creation_input_tokens += entry.usage.cache_creation_input_tokens;
+        self.usage.cache_read_input_tokens += entry.usage.cache_read_input_tokens;
+    }
+}
+
+impl fmt::Display for Spend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "${:.2}, {} tokens ({} in, {} out, {} cache write, {} cache read) over {} iteration(s)",
+            self.cost_usd,
+            self.tokens(),
+            self.usage.input_tokens,
+            self.usage.output_tokens,
+            self.usage.cache_creation_input_tokens,
+            self.usage.cache_read_input_tokens,
+            self.iterations
+        )
+    }
+}
+
+/// Which entries a budget is measured against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Scope {
+    /// The current invocation of the loop.
+    Run,
+    /// Every run recorded for the branch.
+    Branch,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Budget {
+    pub max_usd: Option<f64>,
+    pub max_tokens: Option<u64>,
+}
+
+impl Budget {
+    /// Description of the first limit `spend` exceeds.
+    pub fn exceeded_by(&self, spend: &Spend) -> Option<String> {
+        if let Some(max) = self.max_usd.filter(|max| spend.cost_usd > *max) {
+            return Some(format!("${:.2} spent, budget ${max:.2}", spend.cost_usd));
+        }
+        if let Some(max) = self.max_tokens.filter(|max| spend.tokens() > *max) {
+            return Some(format!("{} tokens used, budget {max}", spend.tokens()));
+        }
+        None
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Ledger {
+    pub entries: Vec<Entry>,
+}
+
+impl Ledger {
+    /// Load the ledger, starting empty when the file does not exist yet.
+    pub fn load(path: &Path) -> Result<Ledger> {
+        match fs::read_to_string(path) {
+            Ok(text) => serde_json::from_str(&text).map_err(|e| Error::json(path, e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Ledger::default()),
+            Err(e) => Err(Error::io(path, e)),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let text = serde_json::to_string_pretty(self).expect("ledger serializes");
+        fs::write(path, text + "\n").map_err(|e| Error::io(path, e))
+    }
+
+    /// Record `entry`, replacing an earlier one for the same run and
+    /// iteration, so adding a log twice does not count its spend twice.
+    pub fn add(&mut self, entry: Entry) {
+        match self
+            .entries
+            .iter_mut()
+            .find(|e| e.run == entry.run && e.iteration == entry.iteration)
+        {
+            Some(existing) => *existing = entry,
+            None => self.entries.push(entry),
+        }
+    }
+
+    pub fn run_spend(&self, run: &str) -> Spend {
+        self.spend(|e| e.run == run)
+    }
+
+    pub fn branch_spend(&self, branch: &str) -> Spend {
+        self.spend(|e| e.branch == branch)
+    }
+
+    fn spend(&self, include: impl Fn(&Entry) -> bool) -> Spend {
+        let mut spend = Spend::default();
+        for entry in self.entries.iter().filter(|e| include(e)) {
+            spend.add(entry);
+        }
+        spend
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stream;
+
+    fn entry(run: &str, branch: &str, iteration: u32, cost_usd: f64, tokens: u64) -> Entry {
+        Entry {
+            run: run.to_string(),
+            branch: branch.to_string(),
+            iteration,
+            recorded_at: 0,
+            cost_usd,
+            usage: Usage {
+                input_tokens: tokens,
+                ..Usage::default()
+            },
+            complete: true,
+        }
+    }
+
+    #[test]
+    fn entries_come_from_the_last_result() {
+        let log = r#"{"type":"result","is_error":false,"total_cost_usd":0.5,"usage":{"input_tokens":10,"output_tokens":5}}"#;
+        let entry = Entry::from_events("r1", "main", 3, 100, &stream::decode(log));
+        assert!(entry.complete);
+        assert_eq!(entry.cost_usd, 0.5);
+        assert_eq!(entry.usage.total(), 15);
+
+        let killed = Entry::from_events("r1", "main", 4, 100, &[]);
+        assert!(!killed.complete);
+        assert_eq!(killed.cost_usd, 0.0);
+    }
+
+    #[test]
+    fn adding_an_iteration_again_replaces_it() {
+        let mut ledger = Ledger::default();
+        ledger.add(entry("r1", "main", 1, 1.0, 100));
+        ledger.add(entry("r1", "main", 2, 2.0, 200));
+        ledger.add(entry("r1", "main", 2, 2.5, 250));
+        ledger.add(entry("r2", "main", 1, 4.0, 400));
+        assert_eq!(ledger.entries.len(), 3);
+
+        let run = ledger.run_spend("r1");
+        assert_eq!(run.iterations, 2);
+        assert_eq!(run.cost_usd, 3.5);
+        assert_eq!(run.tokens(), 350);
+        assert_eq!(ledger.branch_spend("main").cost_usd, 7.5);
+        assert_eq!(ledger.branch_spend("other").iterations, 0);
+    }
+
+    #[test]
+    fn budgets_are_exceeded_only_above_the_limit() {
+        let mut ledger = Ledger::default();
+        ledger.add(entry("r1", "main", 1, 3.0, 1000));
+        ledger.add(entry("r2", "main", 1, 3.0, 1000));
+        let dollars = Budget {
+            max_usd: Some(5.0),
+            max_tokens: None,
+        };
+        assert_eq!(dollars.exceeded_by(&ledger.run_spend("r2")), None);
+        assert_eq!(
+            dollars.exceeded_by(&ledger.branch_spend("main")).as_deref(),
+            Some("$6.00 spent, budget $5.00")
+        );
+
+        let tokens = Budget {
+            max_usd: None,
+            max_tokens: Some(1000),
+        };
+        assert_eq!(tokens.exceeded_by(&ledger.run_spend("r1")), None);
+        assert_eq!(
+            tokens.exceeded_by(&ledger.branch_spend("main")).as_deref(),
+            Some("2000 tokens used, budget 1000")
+        );
+        assert_eq!(
+            Budget::default().exceeded_by(&ledger.branch_spend("main")),
+            None
+        );
+    }
+}
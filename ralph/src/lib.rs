@@ -0,0 +1,34 @@
+//! Tooling for the Ralph Wiggum loop.
+//!
+//! `loop.sh` stays the orchestrator; this crate gives it (and humans) a
+//! structured view of the files the loop shares between iterations.
+
+pub mod archon;
+pub mod breaker;
+pub mod burndown;
+pub mod claim;
+pub mod config;
+pub mod error;
+pub mod feature;
+pub mod gate;
+pub mod git;
+pub mod junit;
+pub mod ledger;
+pub mod lifecycle;
+pub mod model;
+pub mod plan;
+pub mod promise;
+pub mod prompt;
+pub mod push;
+pub mod record;
+pub mod report;
+pub mod spec;
+pub mod stream;
+pub mod supervise;
+pub mod timestamp;
+pub mod worktree;
+
+#[cfg(test)]
+mod testing;
+
+pub use error::{Error, Result};
@@ -0,0 +1,208 @@
+//! Task status transitions owned by the loop.
+//!
+//! Before an iteration the loop picks the highest-priority ready task, claims
+//! it and marks it doing, in the plan and in Archon. Afterwards it marks the
+//! task from what it can verify (whether a commit landed, whether the last
+//! test run passed), so task state never depends on the model remembering to
+//! update it.
+
+use std::fmt;
+
+use serde::Serialize;
+use serde_json::json;
+
+use crate::archon::sync::RemoteTask;
+use crate::archon::TaskStatus;
+use crate::error::Result;
+use crate::gate;
+use crate::git;
+use crate::plan::{Status, Task};
+use crate::stream::Event;
+
+/// Where an iteration left its task.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Outcome {
+    /// Committed, and the last test run passed.
+    Done,
+    /// Committed, but the tests failed or never ran.
+    Review,
+    /// The iteration marked the task `[!]`.
+    Blocked,
+    /// Nothing landed; the task goes back to the queue.
+    Todo,
+}
+
+impl Outcome {
+    /// Judge an iteration from the task's checkbox after it, whether its
+    /// commits reached the working branch, and its last test run (the gate's,
+    /// when the loop ran one).
+    pub fn judge(
+        status: Status,
+        landed: bool,
+        tests_passed: Option<bool>,
+    ) -> (Outcome, &'static str) {
+        match (status, landed, tests_passed) {
+            (Status::Blocked, _, _) => (Outcome::Blocked, "the iteration marked it blocked"),
+            (_, false, _) => (Outcome::Todo, "no commit landed"),
+            (_, true, Some(true)) => (Outcome::Done, "committed and the last test run passed"),
+            (_, true, Some(false)) => (Outcome::Review, "committed but the last test run failed"),
+            (_, true, None) => (Outcome::Review, "committed but no tests ran"),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Outcome::Done => "done",
+            Outcome::Review => "review",
+            Outcome::Blocked => "blocked",
+            Outcome::Todo => "todo",
+        }
+    }
+
+    /// Plan checkbox for the outcome. A task that goes back to the queue gets
+    /// the checkbox it had before the iteration (`before`), so `[~]` partial
+    /// work stays marked.
+    pub fn plan_status(self, before: Option<Status>) -> Status {
+        match self {
+            Outcome::Done => Status::Done,
+            Outcome::Review => Status::InProgress,
+            Outcome::Blocked => Status::Blocked,
+            Outcome::Todo => match before {
+                Some(Status::InProgress) => Status::InProgress,
+                _ => Status::Todo,
+            },
+        }
+    }
+
+    /// Archon status for the outcome; Archon has no blocked state, so a
+    /// blocked task stays `todo` with the blocker in its description.
+    pub fn archon_status(self) -> TaskStatus {
+        match self {
+            Outcome::Done => TaskStatus::Done,
+            Outcome::Review => TaskStatus::Review,
+            Outcome::Blocked | Outcome::Todo => TaskStatus::Todo,
+        }
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Ready tasks by Archon priority (highest `task_order` first), keeping plan
+/// order among equals and for tasks Archon does not track.
+pub fn prioritize<'a>(mut ready: Vec<&'a Task>, remote: &[RemoteTask]) -> Vec<&'a Task> {
+    let order = |task: &Task| {
+        remote
+            .iter()
+            .find(|r| r.plan_task().as_deref() == task.id.as_deref())
+            .map_or(0, |r| r.task_order)
+    };
+    ready.sort_by_key(|task| std::cmp::Reverse(order(task)));
+    ready
+}
+
+/// Whether the iteration's commits reached the working branch and stayed
+/// there: its scratch branch was merged (the log's `worktree` record), or,
+/// without worktrees, HEAD moved past `head_before` (`none` when HEAD was
+/// unborn); and the test gate did not roll them back.
+pub fn landed(events: &[Event], head_before: &str) -> Result<bool> {
+    if gate::last_record(events).is_some_and(|r| r.action.rolled_back()) {
+        return Ok(false);
+    }
+    let merged = events.iter().rev().find_map(|event| match event {
+        Event::Record(record) if record.event == "worktree" => {
+            Some(record.fields.get("result") == Some(&json!("merged")))
+        }
+        _ => None,
+    });
+    if let Some(merged) = merged {
+        return Ok(merged);
+    }
+    Ok(git::commits_between(head_before, "HEAD")? > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plan::Plan;
+    use crate::stream;
+
+    #[test]
+    fn judge() {
+        let outcome = |status, landed, tests| Outcome::judge(status, landed, tests).0;
+        assert_eq!(outcome(Status::Doing, true, Some(true)), Outcome::Done);
+        assert_eq!(outcome(Status::Doing, true, Some(false)), Outcome::Review);
+        assert_eq!(outcome(Status::Done, true, None), Outcome::Review);
+        assert_eq!(outcome(Status::Done, false, Some(true)), Outcome::Todo);
+        assert_eq!(outcome(Status::Blocked, true, Some(true)), Outcome::Blocked);
+        assert_eq!(
+            Outcome::judge(Status::Doing, true, None).1,
+            "committed but no tests ran"
+        );
+    }
+
+    #[test]
+    fn requeued_tasks_keep_partial_work() {
+        assert_eq!(
+            Outcome::Todo.plan_status(Some(Status::InProgress)),
+            Status::InProgress
+        );
+        assert_eq!(Outcome::Todo.plan_status(Some(Status::Doing)), Status::Todo);
+        assert_eq!(Outcome::Review.plan_status(None), Status::InProgress);
+        assert_eq!(Outcome::Blocked.archon_status(), TaskStatus::Todo);
+        assert_eq!(Outcome::Review.archon_status(), TaskStatus::Review);
+    }
+
+    #[test]
+    fn prioritize_by_archon_order() {
+        let plan = Plan::parse(
+            "\
+## Phase 1: Backend
+
+### Tasks
+- [ ] 1.1 Model
+- [ ] 1.2 Views
+- [ ] 1.3 Tests
+",
+        );
+        let remote: Vec<RemoteTask> = serde_json::from_value(json!([
+            { "id": "task-1", "title": "1.1 Model", "status": "todo", "task_order": 1 },
+            { "id": "task-3", "title": "1.3 Tests", "status": "todo", "task_order": 5 },
+        ]))
+        .unwrap();
+        let ready = prioritize(plan.tasks().collect(), &remote);
+        let ids: Vec<&str> = ready.iter().filter_map(|t| t.id.as_deref()).collect();
+        assert_eq!(ids, ["1.3", "1.1", "1.2"]);
+    }
+
+    #[test]
+    fn landed_follows_the_last_worktree_and_gate_records() {
+        let events = stream::decode(concat!(
+            r#"{"type":"ralph","event":"worktree","result":"conflict"}"#,
+            "\n",
+            r#"{"type":"ralph","event":"worktree","result":"merged"}"#,
+            "\n",
+        ));
+        assert!(landed(&events, "HEAD").unwrap());
+
+        let events = stream::decode(concat!(
+            r#"{"type":"ralph","event":"worktree","result":"merged"}"#,
+            "\n",
+            r#"{"type":"ralph","event":"worktree","result":"conflict"}"#,
+            "\n",
+        ));
+        assert!(!landed(&events, "HEAD").unwrap());
+
+        let events = stream::decode(concat!(
+            r#"{"type":"ralph","event":"worktree","result":"merged"}"#,
+            "\n",
+            r#"{"type":"ralph","event":"test_gate","command":"pytest","exit_code":1,"results":null,"baseline":null,"regressions":[],"action":"reverted","output":"gate_1.txt"}"#,
+            "\n",
+        ));
+        assert!(!landed(&events, "HEAD").unwrap());
+    }
+}
@@ -0,0 +1,204 @@
+//! Model selection with a fallback chain.
+//!
+//! Each mode starts on its configured model. When an iteration log shows a
+//! rate-limit or overload error, the next iteration moves one step down
+//! `[fallback] models`; after `retry_primary_after` iterations on a fallback
+//! the mode's own model is tried again. The current choice is kept in a small
+//! state file next to the logs so it survives between `ralph` invocations.
+
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::{Config, Mode};
+use crate::error::{Error, Result};
+use crate::stream::Event;
+
+/// Case-insensitive markers of a throttled API call.
+const THROTTLE_MARKERS: &[&str] = &[
+    "rate_limit_error",
+    "rate limit",
+    "rate-limit",
+    "overloaded_error",
+    "overloaded",
+    "usage limit reached",
+    "api error: 429",
+    "api error: 529",
+];
+
+/// The first sign of a rate-limit or overload error in an iteration log.
+///
+/// Only the final result, API error messages and non-JSON output (claude's
+/// stderr) are checked, so a tool result that merely mentions rate limits
+/// does not count.
+pub fn throttled(events: &[Event]) -> Option<String> {
+    events.iter().find_map(|event| {
+        let text = match event {
+            Event::Result(result) if result.is_error => result.result.as_deref()?,
+            Event::AssistantText { text } if text.starts_with("API Error") => text,
+            Event::Raw { text } => text,
+            _ => return None,
+        };
+        let lower = text.to_lowercase();
+        THROTTLE_MARKERS
+            .iter()
+            .any(|marker| lower.contains(marker))
+            .then(|| text.lines().next().unwrap_or_default().trim().to_string())
+    })
+}
+
+/// A change of model between two iterations.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Switch {
+    pub from: String,
+    pub to: String,
+    pub reason: String,
+}
+
+/// Model in use for the current run, persisted between iterations.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Selection {
+    /// Fallback model in use, `None` while on the mode's own model.
+    pub fallback: Option<String>,
+    /// Iterations run on `fallback` so far.
+    pub iterations: u32,
+}
+
+impl Selection {
+    /// Load the selection, starting on the primary model when the file does
+    /// not exist yet.
+    pub fn load(path: &Path) -> Result<Selection> {
+        match fs::read_to_string(path) {
+            Ok(text) => serde_json::from_str(&text).map_err(|e| Error::json(path, e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Selection::default()),
+            Err(e) => Err(Error::io(path, e)),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let text = serde_json::to_string_pretty(self).expect("selection serializes");
+        fs::write(path, text + "\n").map_err(|e| Error::io(path, e))
+    }
+
+    /// Model the next iteration should use.
+    pub fn model<'a>(&'a self, config: &'a Config, mode: Mode) -> &'a str {
+        self.fallback
+            .as_deref()
+            .unwrap_or_else(|| config.models.for_mode(mode))
+    }
+
+    /// Account for a finished iteration and return the switch it caused.
+    pub fn observe(&mut self, config: &Config, mode: Mode, events: &[Event]) -> Option<Switch> {
+        let primary = config.models.for_mode(mode);
+        let current = self.model(config, mode).to_string();
+
+        if let Some(reason) = throttled(events) {
+            let chain: Vec<&str> = config
+                .fallback
+                .models
+                .iter()
+                .map(String::as_str)
+                .filter(|model| *model != primary)
+                .collect();
+            let next = match chain.iter().position(|model| *model == current) {
+                Some(i) => chain.get(i + 1),
+                None if current == primary => chain.first(),
+                None => None,
+            };
+            let next = next?.to_string();
+            self.fallback = Some(next.clone());
+            self.iterations = 0;
+            return Some(Switch {
+                from: current,
+                to: next,
+                reason,
+            });
+        }
+
+        if self.fallback.is_some() {
+            self.iterations += 1;
+            let retry = config.fallback.retry_primary_after;
+            if retry > 0 && self.iterations >= retry {
+                *self = Selection::default();
+                return Some(Switch {
+                    from: current,
+                    to: primary.to_string(),
+                    reason: format!("retrying after {retry} iteration(s) on fallback"),
+                });
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stream;
+
+    const THROTTLED: &str = r#"{"type":"result","is_error":true,"result":"API Error: 429 rate_limit_error\nretry later"}"#;
+
+    fn config() -> Config {
+        let mut config = Config::default();
+        config.models.default = "opus".to_string();
+        config.models.verify = Some("sonnet".to_string());
+        config.fallback.models = ["opus", "sonnet", "haiku"].map(str::to_string).to_vec();
+        config.fallback.retry_primary_after = 2;
+        config
+    }
+
+    #[test]
+    fn only_api_errors_count_as_throttling() {
+        assert_eq!(
+            throttled(&stream::decode(THROTTLED)).as_deref(),
+            Some("API Error: 429 rate_limit_error")
+        );
+        let quoted = r#"{"type":"user","message":{"content":[{"type":"tool_result","tool_use_id":"t1","content":"handle rate_limit_error"}]}}"#;
+        assert_eq!(throttled(&stream::decode(quoted)), None);
+        let overloaded = "Error: Overloaded, try again\n";
+        assert!(throttled(&stream::decode(overloaded)).is_some());
+    }
+
+    #[test]
+    fn rate_limits_walk_the_fallback_chain() {
+        let config = config();
+        let throttled = stream::decode(THROTTLED);
+        let mut selection = Selection::default();
+        assert_eq!(selection.model(&config, Mode::Build), "opus");
+
+        let switch = selection.observe(&config, Mode::Build, &throttled).unwrap();
+        assert_eq!(
+            (switch.from.as_str(), switch.to.as_str()),
+            ("opus", "sonnet")
+        );
+        let switch = selection.observe(&config, Mode::Build, &throttled).unwrap();
+        assert_eq!(switch.to, "haiku");
+        assert_eq!(selection.observe(&config, Mode::Build, &throttled), None);
+        assert_eq!(selection.model(&config, Mode::Build), "haiku");
+
+        // A mode whose own model is in the chain skips it.
+        let mut verify = Selection::default();
+        let switch = verify.observe(&config, Mode::Verify, &throttled).unwrap();
+        assert_eq!(
+            (switch.from.as_str(), switch.to.as_str()),
+            ("sonnet", "opus")
+        );
+    }
+
+    #[test]
+    fn the_primary_model_is_retried() {
+        let config = config();
+        let mut selection = Selection {
+            fallback: Some("sonnet".to_string()),
+            iterations: 0,
+        };
+        assert_eq!(selection.observe(&config, Mode::Build, &[]), None);
+        let switch = selection.observe(&config, Mode::Build, &[]).unwrap();
+        assert_eq!(
+            (switch.from.as_str(), switch.to.as_str()),
+            ("sonnet", "opus")
+        );
+        assert_eq!(selection, Selection::default());
+    }
+}
@@ -0,0 +1,119 @@
+//! Phase dependencies read from `**Dependency**:` lines.
+//!
+//! The line is prose written for humans ("Phase 5 (must complete artwork
+//! viewer first)", "All previous phases"), so only phase references are
+//! picked out of it. A phase without the line depends on nothing.
+
+use super::{Graph, Phase, Plan, Status, Task};
+
+impl Phase {
+    /// Every task is done, or, for a phase without tasks, its status is.
+    pub fn is_complete(&self) -> bool {
+        if self.tasks.is_empty() {
+            self.status == Some(Status::Done)
+        } else {
+            self.tasks.iter().all(|t| t.status == Status::Done)
+        }
+    }
+}
+
+/// Numbers of the phases mentioned in a dependency line, in order.
+///
+/// Recognises `Phase 3`, `Phases 4, 5 and 9`, and `all previous phases`
+/// (every phase in `earlier`).
+pub fn references<'a>(dependency: &str, earlier: impl Iterator<Item = &'a str>) -> Vec<String> {
+    let lower = dependency.to_lowercase();
+    if lower.contains("all previous") {
+        return earlier.map(str::to_string).collect();
+    }
+
+    // Whether the next word may be a phase number: right after "phase",
+    // a comma or "and".
+    let mut expect = false;
+    let mut after_number = false;
+    let mut refs: Vec<String> = Vec::new();
+    for word in lower.split_whitespace() {
+        let token = word.trim_matches(|c: char| matches!(c, ',' | ';' | ':' | '.' | '(' | ')'));
+        if token == "phase" || token == "phases" {
+            (expect, after_number) = (true, false);
+        } else if expect && token.starts_with(|c: char| c.is_ascii_digit()) {
+            if !refs.iter().any(|r| r == token) {
+                refs.push(token.to_string());
+            }
+            (expect, after_number) = (word.ends_with(','), !word.ends_with(','));
+        } else if after_number && matches!(token, "and" | "&" | "or") {
+            (expect, after_number) = (true, false);
+        } else {
+            (expect, after_number) = (false, false);
+        }
+    }
+    refs
+}
+
+impl Plan {
+    /// Phases `phase` depends on, ignoring references to phases that do not
+    /// exist.
+    pub fn dependencies(&self, phase: &Phase) -> Vec<&Phase> {
+        let Some(dependency) = &phase.dependency else {
+            return Vec::new();
+        };
+        let earlier = self
+            .phases
+            .iter()
+            .take_while(|p| p.number != phase.number)
+            .map(|p| p.number.as_str());
+        references(dependency, earlier)
+            .iter()
+            .filter_map(|number| self.phases.iter().find(|p| &p.number == number))
+            .collect()
+    }
+
+    /// Tasks that can be worked on now: the first task that is neither done
+    /// nor blocked in every phase whose dependencies are complete, provided
+    /// the tasks on its `depends:` line are done. Tasks of one phase tend to
+    /// build on each other, so at most one per phase is ready at a time.
+    pub fn ready_tasks(&self) -> Vec<&Task> {
+        let graph = Graph::of(self);
+        let ready = graph.ready();
+        self.phases
+            .iter()
+            .filter(|phase| self.dependencies(phase).iter().all(|dep| dep.is_complete()))
+            .filter_map(|phase| phase.tasks.iter().find(|t| t.status.is_actionable()))
+            .filter(|task| {
+                task.id.is_none() || ready.iter().any(|n| Some(&n.id) == task.id.as_ref())
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn phase_references_in_prose() {
+        let none = std::iter::empty();
+        assert_eq!(
+            references("Phase 5 (must complete viewer first)", none),
+            ["5"]
+        );
+        let none = std::iter::empty();
+        assert_eq!(references("Phases 4, 5 and 9", none), ["4", "5", "9"]);
+        let earlier = ["1", "2"].into_iter();
+        assert_eq!(references("All previous phases", earlier), ["1", "2"]);
+        assert!(references("None", std::iter::empty()).is_empty());
+    }
+
+    #[test]
+    fn next_task_respects_dependencies() {
+        let plan = Plan::parse(
+            "## Phase 1: A\n\n### Tasks\n- [ ] 1.1 First\n  depends: 3.1\n- [ ] 1.2 Second\n\n\
+             ## Phase 2: B\n**Dependency**: Phase 1\n\n### Tasks\n- [ ] 2.1 Later\n\n\
+             ## Phase 3: C\n\n### Tasks\n- [ ] 3.1 Base\n",
+        );
+        let ready: Vec<&str> = plan.ready_tasks().iter().map(|t| t.label()).collect();
+        assert_eq!(ready, ["3.1"]);
+        assert_eq!(plan.next_task().map(Task::label), Some("3.1"));
+        assert_eq!(plan.first_open_task().map(Task::label), Some("1.1"));
+    }
+}
@@ -0,0 +1,429 @@
+//! Task dependency graph built from `depends:` notes.
+//!
+//! Every numbered task is a node; an edge runs from a task to each task that
+//! waits for it. The graph is checked for cycles and for references to tasks
+//! that do not exist, and can be rendered as Graphviz DOT or Mermaid.
+
+use std::fmt::Write;
+
+use serde::Serialize;
+
+use super::{Plan, Status};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Node {
+    pub id: String,
+    pub title: String,
+    pub status: Status,
+    /// Number of the phase the task belongs to.
+    pub phase: String,
+}
+
+/// `from` has to be done before `to` can start.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Edge {
+    pub from: String,
+    pub to: String,
+}
+
+/// A `depends:` entry that matches no task.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UnknownRef {
+    pub task: String,
+    pub reference: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Graph {
+    pub nodes: Vec<Node>,
+    pub edges: Vec<Edge>,
+    pub unknown: Vec<UnknownRef>,
+    /// Each cycle in edge order, e.g. `["2.1", "3.1"]` for 2.1 -> 3.1 -> 2.1.
+    pub cycles: Vec<Vec<String>>,
+    /// Indices of the nodes each node depends on.
+    #[serde(skip)]
+    deps: Vec<Vec<usize>>,
+    /// Number and title of every phase with numbered tasks.
+    #[serde(skip)]
+    phases: Vec<(String, String)>,
+}
+
+impl Graph {
+    pub fn of(plan: &Plan) -> Graph {
+        let nodes: Vec<Node> = plan
+            .phases
+            .iter()
+            .flat_map(|phase| {
+                phase.tasks.iter().filter_map(|task| {
+                    Some(Node {
+                        id: task.id.clone()?,
+                        title: task.title.clone(),
+                        status: task.status,
+                        phase: phase.number.clone(),
+                    })
+                })
+            })
+            .collect();
+        let phases = plan
+            .phases
+            .iter()
+            .filter(|phase| phase.tasks.iter().any(|t| t.id.is_some()))
+            .map(|phase| (phase.number.clone(), phase.title.clone()))
+            .collect();
+
+        let mut deps = vec![Vec::new(); nodes.len()];
+        let mut unknown = Vec::new();
+        for (i, task) in plan.tasks().filter(|t| t.id.is_some()).enumerate() {
+            for reference in &task.depends {
+                let targets: Vec<usize> = match reference.strip_suffix(".*") {
+                    Some(phase) => (0..nodes.len())
+                        .filter(|&j| j != i && nodes[j].phase == phase)
+                        .collect(),
+                    None => nodes
+                        .iter()
+                        .position(|n| &n.id == reference)
+                        .into_iter()
+                        .collect(),
+                };
+                if targets.is_empty() {
+                    unknown.push(UnknownRef {
+                        task: nodes[i].id.clone(),
+                        reference: reference.clone(),
+                    });
+                }
+                for j in targets {
+                    if !deps[i].contains(&j) {
+                        deps[i].push(j);
+                    }
+                }
+            }
+        }
+
+        let edges = deps
+            .iter()
+            .enumerate()
+            .flat_map(|(i, targets)| targets.iter().map(move |&j| (j, i)))
+            .map(|(from, to)| Edge {
+                from: nodes[from].id.clone(),
+                to: nodes[to].id.clone(),
+            })
+            .collect();
+        let cycles = find_cycles(&deps)
+            .into_iter()
+            .map(|cycle| cycle.into_iter().map(|i| nodes[i].id.clone()).collect())
+            .collect();
+
+        Graph {
+            nodes,
+            edges,
+            unknown,
+            cycles,
+            deps,
+            phases,
+        }
+    }
+
+    /// No cycles and no unknown references.
+    pub fn is_valid(&self) -> bool {
+        self.unknown.is_empty() && self.cycles.is_empty()
+    }
+
+    /// Tasks that are not done or blocked and whose dependencies are all done.
+    pub fn ready(&self) -> Vec<&Node> {
+        (0..self.nodes.len())
+            .filter(|&i| self.nodes[i].status.is_actionable())
+            .filter(|&i| {
+                self.deps[i]
+                    .iter()
+                    .all(|&j| self.nodes[j].status == Status::Done)
+            })
+            .map(|i| &self.nodes[i])
+            .collect()
+    }
+
+    /// Longest chain of unfinished tasks, first task first. It follows the
+    /// dependency edges only, so it is a lower bound on the iterations left:
+    /// the loop also starts at most one task per phase at a time, and more
+    /// workers than phases with ready tasks do not help. Empty when the graph
+    /// has a cycle.
+    pub fn critical_path(&self) -> Vec<&Node> {
+        if !self.cycles.is_empty() {
+            return Vec::new();
+        }
+        let open = |i: usize| self.nodes[i].status != Status::Done;
+        let mut length: Vec<Option<usize>> = vec![None; self.nodes.len()];
+        for i in 0..self.nodes.len() {
+            self.chain_length(i, &open, &mut length);
+        }
+
+        // Among `candidates`, the first with the longest chain.
+        let longest = |candidates: &mut dyn Iterator<Item = usize>| {
+            candidates.fold(None, |best: Option<usize>, i| match best {
+                Some(b) if length[b] >= length[i] => Some(b),
+                _ => Some(i),
+            })
+        };
+        let mut path = Vec::new();
+        let mut next = longest(&mut (0..self.nodes.len()).filter(|&i| open(i)));
+        while let Some(i) = next {
+            path.push(&self.nodes[i]);
+            next = longest(&mut self.deps[i].iter().copied().filter(|&j| open(j)));
+        }
+        path.reverse();
+        path
+    }
+
+    fn chain_length(
+        &self,
+        i: usize,
+        open: &impl Fn(usize) -> bool,
+        length: &mut [Option<usize>],
+    ) -> usize {
+        if let Some(known) = length[i] {
+            return known;
+        }
+        let mut longest = 0;
+        for &j in &self.deps[i] {
+            if open(j) {
+                longest = longest.max(self.chain_length(j, open, length));
+            }
+        }
+        let total = longest + usize::from(open(i));
+        length[i] = Some(total);
+        total
+    }
+
+    /// Graphviz DOT, one cluster per phase, critical path in red.
+    pub fn to_dot(&self) -> String {
+        let path = self.critical_path();
+        let mut out = String::from("digraph plan {\n    rankdir=LR;\n");
+        out.push_str("    node [shape=box, style=\"rounded,filled\", fillcolor=white];\n");
+        for (number, title) in &self.phases {
+            let _ = writeln!(out, "    subgraph \"cluster_{}\" {{", dot_escape(number));
+            let _ = writeln!(
+                out,
+                "        label=\"{}\";",
+                dot_escape(&phase_label(number, title))
+            );
+            for node in self.nodes.iter().filter(|n| &n.phase == number) {
+                let fill = match node.status {
+                    Status::Done => "palegreen",
+                    Status::InProgress | Status::Doing => "lightyellow",
+                    Status::Blocked => "lightpink",
+                    _ => "white",
+                };
+                let _ = writeln!(
+                    out,
+                    "        \"{}\" [label=\"{}\", fillcolor={fill}];",
+                    dot_escape(&node.id),
+                    dot_escape(&node_label(node))
+                );
+            }
+            out.push_str("    }\n");
+        }
+        for edge in &self.edges {
+            let style = if on_path(&path, edge) {
+                " [color=red, penwidth=2]"
+            } else {
+                ""
+            };
+            let _ = writeln!(
+                out,
+                "    \"{}\" -> \"{}\"{style};",
+                dot_escape(&edge.from),
+                dot_escape(&edge.to)
+            );
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// Mermaid flowchart, one subgraph per phase, critical path in red.
+    pub fn to_mermaid(&self) -> String {
+        let path = self.critical_path();
+        let mut out = String::from("flowchart LR\n");
+        for (number, title) in &self.phases {
+            let _ = writeln!(
+                out,
+                "    subgraph phase{}[\"{}\"]",
+                mermaid_id(number),
+                mermaid_escape(&phase_label(number, title))
+            );
+            for node in self.nodes.iter().filter(|n| &n.phase == number) {
+                let class = match node.status {
+                    Status::Done => ":::done",
+                    Status::InProgress | Status::Doing => ":::doing",
+                    Status::Blocked => ":::blocked",
+                    _ => "",
+                };
+                let _ = writeln!(
+                    out,
+                    "        t{}[\"{}\"]{class}",
+                    mermaid_id(&node.id),
+                    mermaid_escape(&node_label(node))
+                );
+            }
+            out.push_str("    end\n");
+        }
+        for (index, edge) in self.edges.iter().enumerate() {
+            let _ = writeln!(
+                out,
+                "    t{} --> t{}",
+                mermaid_id(&edge.from),
+                mermaid_id(&edge.to)
+            );
+            if on_path(&path, edge) {
+                let _ = writeln!(out, "    linkStyle {index} stroke:red,stroke-width:3px");
+            }
+        }
+        out.push_str("    classDef done fill:#c8e6c9\n");
+        out.push_str("    classDef doing fill:#fff9c4\n");
+        out.push_str("    classDef blocked fill:#ffcdd2\n");
+        out
+    }
+}
+
+/// Cycles found by depth-first search, each listed in edge order.
+fn find_cycles(deps: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    #[derive(Clone, Copy, PartialEq)]
+    enum Mark {
+        New,
+        OnPath,
+        Finished,
+    }
+
+    fn visit(
+        i: usize,
+        deps: &[Vec<usize>],
+        marks: &mut [Mark],
+        path: &mut Vec<usize>,
+        cycles: &mut Vec<Vec<usize>>,
+    ) {
+        marks[i] = Mark::OnPath;
+        path.push(i);
+        for &j in &deps[i] {
+            match marks[j] {
+                Mark::New => visit(j, deps, marks, path, cycles),
+                Mark::OnPath => {
+                    let start = path.iter().position(|&p| p == j).expect("on path");
+                    // The path follows "depends on"; edges run the other way.
+                    let mut cycle = path[start..].to_vec();
+                    cycle.reverse();
+                    cycles.push(cycle);
+                }
+                Mark::Finished => {}
+            }
+        }
+        path.pop();
+        marks[i] = Mark::Finished;
+    }
+
+    let mut marks = vec![Mark::New; deps.len()];
+    let mut cycles = Vec::new();
+    for i in 0..deps.len() {
+        if marks[i] == Mark::New {
+            visit(i, deps, &mut marks, &mut Vec::new(), &mut cycles);
+        }
+    }
+    cycles
+}
+
+fn on_path(path: &[&Node], edge: &Edge) -> bool {
+    path.windows(2)
+        .any(|pair| pair[0].id == edge.from && pair[1].id == edge.to)
+}
+
+fn phase_label(number: &str, title: &str) -> String {
+    if title.is_empty() {
+        format!("Phase {number}")
+    } else {
+        format!("Phase {number}: {title}")
+    }
+}
+
+fn node_label(node: &Node) -> String {
+    format!("{} {}", node.id, node.title.replace('`', ""))
+}
+
+fn dot_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn mermaid_id(text: &str) -> String {
+    text.replace(|c: char| !c.is_ascii_alphanumeric(), "_")
+}
+
+fn mermaid_escape(text: &str) -> String {
+    text.replace('"', "#quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph(tasks: &str) -> Graph {
+        Graph::of(&Plan::parse(&format!(
+            "## Phase 1: Work\n\n### Tasks\n{tasks}"
+        )))
+    }
+
+    fn ids(nodes: Vec<&Node>) -> Vec<&str> {
+        nodes.into_iter().map(|n| n.id.as_str()).collect()
+    }
+
+    #[test]
+    fn edges_and_phase_references() {
+        let plan = Plan::parse(
+            "## Phase 1: A\n\n### Tasks\n- [ ] 1.1 One\n- [ ] 1.2 Two\n\n\
+             ## Phase 2: B\n\n### Tasks\n- [ ] 2.1 Three (depends: 1.*)\n",
+        );
+        let graph = Graph::of(&plan);
+        let edges: Vec<(&str, &str)> = graph
+            .edges
+            .iter()
+            .map(|e| (e.from.as_str(), e.to.as_str()))
+            .collect();
+        assert_eq!(edges, [("1.1", "2.1"), ("1.2", "2.1")]);
+        assert!(graph.is_valid());
+    }
+
+    #[test]
+    fn unknown_references() {
+        let graph = graph("- [ ] 1.1 One\n  depends: 1.9, 4.*\n");
+        let unknown: Vec<&str> = graph.unknown.iter().map(|u| u.reference.as_str()).collect();
+        assert_eq!(unknown, ["1.9", "4.*"]);
+        assert!(!graph.is_valid());
+    }
+
+    #[test]
+    fn cycles_in_edge_order() {
+        let graph = graph(
+            "- [ ] 1.1 One\n  depends: 1.2\n- [ ] 1.2 Two\n  depends: 1.1\n- [ ] 1.3 Three\n",
+        );
+        assert_eq!(graph.cycles, [["1.2", "1.1"]]);
+        assert!(!graph.is_valid());
+        assert!(graph.critical_path().is_empty());
+    }
+
+    #[test]
+    fn ready_tasks_wait_for_their_dependencies() {
+        let graph = graph(
+            "- [x] 1.1 One\n- [ ] 1.2 Two\n  depends: 1.1\n\
+             - [ ] 1.3 Three\n  depends: 1.2\n- [!] 1.4 Four\n",
+        );
+        assert_eq!(ids(graph.ready()), ["1.2"]);
+    }
+
+    #[test]
+    fn critical_path_is_the_longest_open_chain() {
+        let graph = graph(
+            "- [x] 1.1 Done\n- [ ] 1.2 A\n  depends: 1.1\n- [ ] 1.3 B\n  depends: 1.2\n\
+             - [ ] 1.4 C\n  depends: 1.3\n- [ ] 1.5 D\n  depends: 1.2\n",
+        );
+        assert_eq!(ids(graph.critical_path()), ["1.2", "1.3", "1.4"]);
+
+        let dot = graph.to_dot();
+        assert!(dot.contains("\"1.2\" -> \"1.3\" [color=red, penwidth=2];"));
+        assert!(dot.contains("\"1.2\" -> \"1.5\";"));
+    }
+}
@@ -0,0 +1,401 @@
+//! Checks for the conventions `PROMPT_plan.md` asks the planner to follow.
+//!
+//! Errors are things the loop trips over (unknown markers, duplicate task
+//! numbers, a phase marked complete with open tasks, broken dependencies);
+//! warnings are planning advice (file paths, blocker notes, tests for
+//! endpoints).
+
+use std::fmt;
+
+use serde::Serialize;
+
+use super::{Graph, Plan, Status, Task};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Rule {
+    /// An open task names no file to change.
+    NoFilePath,
+    /// `[!]` without a `Blocked: ...` note.
+    BlockedWithoutReason,
+    /// `**Status**: [x]` on a phase with tasks that are not done.
+    CompletePhaseOpenTasks,
+    /// A checkbox marker other than ` `, `x`, `~`, `>` or `!`.
+    UnknownMarker,
+    /// The same task number twice.
+    DuplicateTask,
+    /// A backend endpoint task that no test task covers.
+    UntestedEndpoint,
+    /// A `depends:` reference to a missing task, or a cycle.
+    Dependency,
+}
+
+impl Rule {
+    pub fn name(self) -> &'static str {
+        match self {
+            Rule::NoFilePath => "no-file-path",
+            Rule::BlockedWithoutReason => "blocked-without-reason",
+            Rule::CompletePhaseOpenTasks => "complete-phase-open-tasks",
+            Rule::UnknownMarker => "unknown-marker",
+            Rule::DuplicateTask => "duplicate-task",
+            Rule::UntestedEndpoint => "untested-endpoint",
+            Rule::Dependency => "dependency",
+        }
+    }
+
+    pub fn severity(self) -> Severity {
+        match self {
+            Rule::NoFilePath | Rule::BlockedWithoutReason | Rule::UntestedEndpoint => {
+                Severity::Warning
+            }
+            Rule::CompletePhaseOpenTasks
+            | Rule::UnknownMarker
+            | Rule::DuplicateTask
+            | Rule::Dependency => Severity::Error,
+        }
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Finding {
+    pub rule: Rule,
+    pub severity: Severity,
+    /// One-based line in the plan file.
+    pub line: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub task: Option<String>,
+    pub message: String,
+}
+
+impl Finding {
+    fn new(rule: Rule, line: usize, task: Option<&Task>, message: String) -> Finding {
+        Finding {
+            rule,
+            severity: rule.severity(),
+            line: line + 1,
+            task: task.map(|t| t.label().to_string()),
+            message,
+        }
+    }
+}
+
+/// Words that say nothing about what an endpoint serves.
+const GENERIC_WORDS: &[&str] = &[
+    "add",
+    "and",
+    "api",
+    "create",
+    "done",
+    "endpoint",
+    "endpoints",
+    "exists",
+    "for",
+    "implement",
+    "new",
+    "the",
+    "update",
+    "with",
+];
+
+impl Plan {
+    /// Every finding, in plan order.
+    pub fn lint(&self) -> Vec<Finding> {
+        let mut findings = Vec::new();
+        let mut seen: Vec<&str> = Vec::new();
+
+        for phase in &self.phases {
+            if let Some(Status::Unknown(marker)) = phase.status {
+                findings.push(Finding::new(
+                    Rule::UnknownMarker,
+                    phase.line,
+                    None,
+                    format!(
+                        "Phase {} has unknown status marker [{marker}]",
+                        phase.number
+                    ),
+                ));
+            }
+            let open = phase.tasks.len() - phase.done_count();
+            if phase.status == Some(Status::Done) && open > 0 {
+                findings.push(Finding::new(
+                    Rule::CompletePhaseOpenTasks,
+                    phase.line,
+                    None,
+                    format!(
+                        "Phase {} is marked complete but has {open} open task(s)",
+                        phase.number
+                    ),
+                ));
+            }
+            for criterion in &phase.criteria {
+                if let Status::Unknown(marker) = criterion.status {
+                    findings.push(Finding::new(
+                        Rule::UnknownMarker,
+                        criterion.line,
+                        None,
+                        format!("Unknown status marker [{marker}] on criterion"),
+                    ));
+                }
+            }
+
+            for task in &phase.tasks {
+                let label = task.label();
+                if let Status::Unknown(marker) = task.status {
+                    findings.push(Finding::new(
+                        Rule::UnknownMarker,
+                        task.line,
+                        Some(task),
+                        format!("Unknown status marker [{marker}] on {label}"),
+                    ));
+                }
+                if let Some(id) = &task.id {
+                    if seen.contains(&id.as_str()) {
+                        findings.push(Finding::new(
+                            Rule::DuplicateTask,
+                            task.line,
+                            Some(task),
+                            format!("Task number {id} is used more than once"),
+                        ));
+                    }
+                    seen.push(id);
+                }
+                if task.status == Status::Blocked && task.blocker.is_none() {
+                    findings.push(Finding::new(
+                        Rule::BlockedWithoutReason,
+                        task.line,
+                        Some(task),
+                        format!("{label} is blocked but has no `Blocked: <reason>` note"),
+                    ));
+                }
+                if task.status != Status::Done && task.files.is_empty() {
+                    findings.push(Finding::new(
+                        Rule::NoFilePath,
+                        task.line,
+                        Some(task),
+                        format!("{label} names no file path"),
+                    ));
+                }
+                if is_endpoint(task) && !self.tasks().any(|t| covers(t, task)) {
+                    findings.push(Finding::new(
+                        Rule::UntestedEndpoint,
+                        task.line,
+                        Some(task),
+                        format!("{label} looks like a backend endpoint but no test task covers it"),
+                    ));
+                }
+            }
+        }
+
+        let graph = Graph::of(self);
+        let line_of = |id: &str| self.task(id).map(|t| (t.line, t));
+        for unknown in &graph.unknown {
+            if let Some((line, task)) = line_of(&unknown.task) {
+                findings.push(Finding::new(
+                    Rule::Dependency,
+                    line,
+                    Some(task),
+                    format!(
+                        "{} depends on {}, which matches no task",
+                        unknown.task, unknown.reference
+                    ),
+                ));
+            }
+        }
+        for cycle in &graph.cycles {
+            if let Some((line, task)) = line_of(&cycle[0]) {
+                findings.push(Finding::new(
+                    Rule::Dependency,
+                    line,
+                    Some(task),
+                    format!("Dependency cycle: {} -> {}", cycle.join(" -> "), cycle[0]),
+                ));
+            }
+        }
+
+        findings.sort_by_key(|f| f.line);
+        findings
+    }
+}
+
+/// Title and notes, lowercased.
+fn text(task: &Task) -> String {
+    std::iter::once(&task.title)
+        .chain(&task.notes)
+        .map(|line| line.to_lowercase())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn is_test(task: &Task) -> bool {
+    task.title.to_lowercase().contains("test") || task.files.iter().any(|f| f.path.contains("test"))
+}
+
+/// A task that adds or changes a backend endpoint: it says so, names a
+/// ViewSet or an HTTP route, or touches a Python view module.
+fn is_endpoint(task: &Task) -> bool {
+    if is_test(task) {
+        return false;
+    }
+    let text = text(task);
+    let says_so = ["endpoint", "viewset", "apiview"]
+        .iter()
+        .any(|word| text.contains(word));
+    let route = ["get /", "post /", "put /", "patch /", "delete /"]
+        .iter()
+        .any(|method| text.contains(method));
+    let view_module = task.files.iter().any(|f| {
+        f.path.ends_with(".py") && (f.path.contains("views") || f.path.ends_with("api.py"))
+    });
+    says_so || route || view_module
+}
+
+/// Whether test task `test` mentions something `endpoint` is about: a word of
+/// its title, a file it touches, or a segment of its route.
+fn covers(test: &Task, endpoint: &Task) -> bool {
+    if !is_test(test) {
+        return false;
+    }
+    let normalize = |s: &str| -> String {
+        s.chars()
+            .filter(char::is_ascii_alphanumeric)
+            .collect::<String>()
+            .to_lowercase()
+    };
+    let haystack = normalize(&text(test));
+
+    let mut subjects: Vec<String> = endpoint
+        .title
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .map(str::to_lowercase)
+        .filter(|w| w.len() >= 3 && !GENERIC_WORDS.contains(&w.as_str()))
+        .collect();
+    for file in &endpoint.files {
+        let name = file.path.rsplit('/').next().unwrap_or(&file.path);
+        subjects.push(normalize(name.split('.').next().unwrap_or(name)));
+    }
+    for word in text(endpoint).split_whitespace() {
+        if word.starts_with('/') || word.starts_with("`/") {
+            subjects.extend(
+                word.split('/')
+                    .filter(|s| !s.contains('{') && !s.contains('<'))
+                    .map(normalize)
+                    .filter(|s| s.len() >= 3 && !GENERIC_WORDS.contains(&s.as_str())),
+            );
+        }
+    }
+    subjects.retain(|s| !s.is_empty() && s != "init");
+    subjects.is_empty() || subjects.iter().any(|s| haystack.contains(s.as_str()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(plan: &str) -> Vec<(Rule, usize)> {
+        Plan::parse(plan)
+            .lint()
+            .into_iter()
+            .map(|f| (f.rule, f.line))
+            .collect()
+    }
+
+    #[test]
+    fn clean_plan_has_no_findings() {
+        let plan = "\
+## Phase 1: Backend
+
+### Tasks
+- [x] 1.1 Add the `AuditViewSet` endpoint (`views/audit.py`)
+- [ ] 1.2 Test the audit endpoint (`tests/test_audit.py`)
+  - depends: 1.1
+";
+        assert!(rules(plan).is_empty());
+    }
+
+    #[test]
+    fn errors() {
+        let plan = "\
+## Phase 1: Backend
+**Status**: [x] Complete
+
+### Tasks
+- [?] 1.1 Model (`models.py`)
+- [ ] 1.1 Serializer (`serializers.py`)
+- [x] 1.2 Admin (`admin.py`)
+  - depends: 1.9
+";
+        assert_eq!(
+            rules(plan),
+            [
+                (Rule::CompletePhaseOpenTasks, 1),
+                (Rule::UnknownMarker, 5),
+                (Rule::DuplicateTask, 6),
+                (Rule::Dependency, 7),
+            ]
+        );
+        assert!(Plan::parse(plan)
+            .lint()
+            .iter()
+            .all(|f| f.severity == Severity::Error));
+    }
+
+    #[test]
+    fn warnings() {
+        let plan = "\
+## Phase 1: Backend
+
+### Tasks
+- [!] 1.1 Model (`models.py`)
+- [ ] 1.2 Write the docs
+- [ ] 1.3 Add GET /api/orders/ endpoint (`views/orders.py`)
+- [ ] 1.4 Test the invoices (`tests/test_invoices.py`)
+";
+        assert_eq!(
+            rules(plan),
+            [
+                (Rule::BlockedWithoutReason, 4),
+                (Rule::NoFilePath, 5),
+                (Rule::UntestedEndpoint, 6),
+            ]
+        );
+    }
+
+    #[test]
+    fn dependency_cycle() {
+        let plan = "\
+## Phase 1: Backend
+
+### Tasks
+- [ ] 1.1 Model (`models.py`)
+  - depends: 1.2
+- [ ] 1.2 Views (`urls.py`)
+  - depends: 1.1
+";
+        let findings = Plan::parse(plan).lint();
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].rule, Rule::Dependency);
+        assert_eq!(findings[0].message, "Dependency cycle: 1.2 -> 1.1 -> 1.2");
+    }
+}
@@ -0,0 +1,335 @@
+//! Typed model of `ralph/IMPLEMENTATION_PLAN.md`.
+//!
+//! The plan is kept as its original lines alongside the parsed phases, so a
+//! status change rewrites a single checkbox marker and every other byte of
+//! the file round-trips untouched.
+
+mod deps;
+mod graph;
+mod lint;
+mod parse;
+mod progress;
+mod summary;
+
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::error::{Error, Result};
+
+pub use deps::references;
+pub use graph::{Edge, Graph, Node, UnknownRef};
+pub use lint::{Finding, Rule, Severity};
+pub use progress::{code_changed, Progress, Snapshot, Transition, EXIT_NO_PROGRESS};
+pub use summary::{Row, Summary};
+
+/// Default location of the plan, relative to the project root.
+pub const DEFAULT_PLAN_PATH: &str = "ralph/IMPLEMENTATION_PLAN.md";
+
+/// Checkbox state of a task or completion criterion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Status {
+    /// `[ ]`
+    Todo,
+    /// `[x]`
+    Done,
+    /// `[~]`, used by planning mode for partially implemented work.
+    InProgress,
+    /// `[>]`, the task currently being worked on (Archon `doing`).
+    Doing,
+    /// `[!]`, blocked; the task should carry a blocker note.
+    Blocked,
+    /// Any other character between the brackets.
+    Unknown(char),
+}
+
+impl Status {
+    pub fn from_marker(marker: char) -> Status {
+        match marker {
+            ' ' => Status::Todo,
+            'x' | 'X' => Status::Done,
+            '~' => Status::InProgress,
+            '>' => Status::Doing,
+            '!' => Status::Blocked,
+            other => Status::Unknown(other),
+        }
+    }
+
+    pub fn marker(self) -> char {
+        match self {
+            Status::Todo => ' ',
+            Status::Done => 'x',
+            Status::InProgress => '~',
+            Status::Doing => '>',
+            Status::Blocked => '!',
+            Status::Unknown(c) => c,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Status::Todo => "todo",
+            Status::Done => "done",
+            Status::InProgress => "in-progress",
+            Status::Doing => "doing",
+            Status::Blocked => "blocked",
+            Status::Unknown(_) => "unknown",
+        }
+    }
+
+    /// Whether a task in this state can be picked up by an iteration.
+    pub fn is_actionable(self) -> bool {
+        matches!(self, Status::Todo | Status::InProgress | Status::Doing)
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}]", self.marker())
+    }
+}
+
+impl FromStr for Status {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Status> {
+        let status = match s.to_ascii_lowercase().as_str() {
+            "todo" | " " | "[ ]" => Status::Todo,
+            "done" | "x" | "[x]" => Status::Done,
+            "in-progress" | "~" | "[~]" => Status::InProgress,
+            "doing" | ">" | "[>]" => Status::Doing,
+            "blocked" | "!" | "[!]" => Status::Blocked,
+            _ => {
+                let marker = s
+                    .strip_prefix('[')
+                    .and_then(|rest| rest.strip_suffix(']'))
+                    .filter(|inner| inner.chars().count() == 1)
+                    .and_then(|inner| inner.chars().next());
+                return marker
+                    .map(Status::from_marker)
+                    .ok_or_else(|| Error::InvalidStatus(s.to_string()));
+            }
+        };
+        Ok(status)
+    }
+}
+
+/// Known states serialize by name; unknown markers as `[c]` so they survive
+/// a round trip.
+impl Serialize for Status {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Status::Unknown(_) => serializer.collect_str(self),
+            known => serializer.serialize_str(known.name()),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Status {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Status, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        text.parse().map_err(de::Error::custom)
+    }
+}
+
+/// A file path mentioned in backticks, e.g. `` `sidebar.tsx:163-167` ``.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileRef {
+    pub path: String,
+    /// Line or line range suffix, without the leading colon.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lines: Option<String>,
+}
+
+/// A checkbox item under a phase's `### Tasks` heading.
+#[derive(Debug, Clone, Serialize)]
+pub struct Task {
+    /// Task number such as `1.3`, when the item is numbered.
+    pub id: Option<String>,
+    pub title: String,
+    pub status: Status,
+    /// Indented lines following the checkbox, with their indentation kept.
+    pub notes: Vec<String>,
+    /// Reason recorded for a blocked task (`Blocked: ...` / `Blocker: ...`).
+    pub blocker: Option<String>,
+    pub files: Vec<FileRef>,
+    /// Tasks this one waits for, from a `depends: 2.1, 3.*` note; `3.*`
+    /// stands for every task of phase 3.
+    pub depends: Vec<String>,
+    /// Spec requirement IDs from a `covers: AUDIT-3, AUDIT-4` note.
+    pub covers: Vec<String>,
+    /// Zero-based line of the checkbox in the plan file.
+    pub line: usize,
+}
+
+impl Task {
+    /// Identifier used in CLI output: the task number, or the title for
+    /// unnumbered items.
+    pub fn label(&self) -> &str {
+        self.id.as_deref().unwrap_or(&self.title)
+    }
+}
+
+/// A checkbox item under a phase's `### Completion Criteria` heading.
+#[derive(Debug, Clone, Serialize)]
+pub struct Criterion {
+    pub text: String,
+    pub status: Status,
+    pub line: usize,
+}
+
+/// A `## Phase N: Title` section.
+#[derive(Debug, Clone, Serialize)]
+pub struct Phase {
+    pub number: String,
+    pub title: String,
+    /// Marker from the `**Status**: [x] Complete` line.
+    pub status: Option<Status>,
+    /// Raw text of the `**Dependency**:` line.
+    pub dependency: Option<String>,
+    pub tasks: Vec<Task>,
+    pub criteria: Vec<Criterion>,
+    /// Zero-based line of the `## Phase` heading.
+    pub line: usize,
+}
+
+impl Phase {
+    pub fn done_count(&self) -> usize {
+        self.tasks
+            .iter()
+            .filter(|t| t.status == Status::Done)
+            .count()
+    }
+}
+
+/// A parsed implementation plan that can be written back losslessly.
+#[derive(Debug, Clone, Serialize)]
+pub struct Plan {
+    pub phases: Vec<Phase>,
+    #[serde(skip)]
+    lines: Vec<String>,
+}
+
+impl Plan {
+    pub fn parse(text: &str) -> Plan {
+        let lines: Vec<String> = text.split('\n').map(str::to_string).collect();
+        let phases = parse::phases(&lines);
+        Plan { phases, lines }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Plan> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path).map_err(|e| Error::io(path, e))?;
+        Ok(Plan::parse(&text))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        fs::write(path, self.to_string()).map_err(|e| Error::io(path, e))
+    }
+
+    pub fn lines(&self) -> &[String] {
+        &self.lines
+    }
+
+    pub fn tasks(&self) -> impl Iterator<Item = &Task> {
+        self.phases.iter().flat_map(|p| p.tasks.iter())
+    }
+
+    pub fn task(&self, id: &str) -> Option<&Task> {
+        self.tasks().find(|t| t.id.as_deref() == Some(id))
+    }
+
+    /// The first task in document order that is ready to start (see
+    /// [`Plan::ready_tasks`]).
+    pub fn next_task(&self) -> Option<&Task> {
+        self.ready_tasks().into_iter().next()
+    }
+
+    /// The first task in document order that is neither done nor blocked,
+    /// whatever it depends on.
+    pub fn first_open_task(&self) -> Option<&Task> {
+        self.tasks().find(|t| t.status.is_actionable())
+    }
+
+    /// Rewrite the checkbox marker of task `id`, leaving the rest of the
+    /// line untouched.
+    pub fn set_status(&mut self, id: &str, status: Status) -> Result<()> {
+        let task = self
+            .phases
+            .iter_mut()
+            .flat_map(|p| p.tasks.iter_mut())
+            .find(|t| t.id.as_deref() == Some(id))
+            .ok_or_else(|| Error::UnknownTask(id.to_string()))?;
+
+        let line = &mut self.lines[task.line];
+        let open = line.find('[').expect("task line has a checkbox");
+        let mut rewritten = String::with_capacity(line.len());
+        rewritten.push_str(&line[..=open]);
+        rewritten.push(status.marker());
+        let close = open + 1 + line[open + 1..].chars().next().map_or(0, char::len_utf8);
+        rewritten.push_str(&line[close..]);
+        *line = rewritten;
+        task.status = status;
+        Ok(())
+    }
+}
+
+impl fmt::Display for Plan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.lines.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAN: &str = "\
+# Plan\r
+\r
+## Phase 1: Backend\r
+\r
+### Tasks\r
+- [ ] 1.1 Create the model   \r
+  * [x] nested, not a task\r
+- [~] 1.2 Add the serializer\r
+";
+
+    #[test]
+    fn set_status_rewrites_only_the_marker() {
+        let mut plan = Plan::parse(PLAN);
+        plan.set_status("1.1", Status::Done).unwrap();
+        plan.set_status("1.2", Status::Blocked).unwrap();
+
+        let expected = PLAN
+            .replace("- [ ] 1.1", "- [x] 1.1")
+            .replace("- [~] 1.2", "- [!] 1.2");
+        assert_eq!(plan.to_string(), expected);
+        assert_eq!(plan.task("1.1").unwrap().status, Status::Done);
+
+        let reparsed = Plan::parse(&plan.to_string());
+        assert_eq!(reparsed.task("1.2").unwrap().status, Status::Blocked);
+    }
+
+    #[test]
+    fn set_status_of_unknown_task_fails() {
+        let mut plan = Plan::parse(PLAN);
+        assert!(matches!(
+            plan.set_status("3.1", Status::Done),
+            Err(Error::UnknownTask(id)) if id == "3.1"
+        ));
+        assert_eq!(plan.to_string(), PLAN);
+    }
+
+    #[test]
+    fn status_parses_names_and_markers() {
+        assert_eq!("done".parse::<Status>().unwrap(), Status::Done);
+        assert_eq!("[>]".parse::<Status>().unwrap(), Status::Doing);
+        assert_eq!("[?]".parse::<Status>().unwrap(), Status::Unknown('?'));
+        assert!("finished".parse::<Status>().is_err());
+    }
+}
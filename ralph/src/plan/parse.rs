@@ -0,0 +1,389 @@
+//! Line-oriented parser for the plan markdown.
+//!
+//! Only `## Phase N: Title` sections are interpreted. Within a phase, top-level
+//! checkbox items under `### Tasks` become tasks and those under
+//! `### Completion Criteria` become criteria; indented lines that follow a
+//! task are attached to it as notes. Fenced code blocks are never parsed for
+//! checkboxes.
+
+use super::{Criterion, FileRef, Phase, Status, Task};
+use crate::spec;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Section {
+    Other,
+    Tasks,
+    Criteria,
+}
+
+pub(super) fn phases(lines: &[String]) -> Vec<Phase> {
+    let mut phases = Vec::new();
+    let mut current: Option<Phase> = None;
+    let mut section = Section::Other;
+    let mut in_fence = false;
+    let mut task_open = false;
+
+    for (i, line) in lines.iter().enumerate() {
+        let trimmed = line.trim_start();
+
+        if trimmed.starts_with("```") || in_fence {
+            if trimmed.starts_with("```") {
+                in_fence = !in_fence;
+            }
+            if task_open {
+                push_note(current.as_mut(), line);
+            }
+            continue;
+        }
+
+        if let Some(heading) = line.strip_prefix("## ") {
+            phases.extend(current.take().map(finish_phase));
+            current = phase_heading(heading, i);
+            section = Section::Other;
+            task_open = false;
+            continue;
+        }
+
+        let Some(phase) = current.as_mut() else {
+            continue;
+        };
+
+        if let Some(heading) = line.strip_prefix("### ") {
+            let heading = heading.trim().to_ascii_lowercase();
+            section = if heading == "tasks" {
+                Section::Tasks
+            } else if heading.starts_with("completion criteria") {
+                Section::Criteria
+            } else {
+                Section::Other
+            };
+            task_open = false;
+            continue;
+        }
+
+        if let Some(value) = field(line, "Status") {
+            phase.status = checkbox_marker(value).map(Status::from_marker);
+            continue;
+        }
+        if let Some(value) = field(line, "Dependency").or_else(|| field(line, "Dependencies")) {
+            phase.dependency = Some(value.trim().to_string());
+            continue;
+        }
+
+        if let Some((0, status, text)) = checkbox(line) {
+            match section {
+                Section::Tasks => {
+                    phase.tasks.push(task(status, text, i));
+                    task_open = true;
+                    continue;
+                }
+                Section::Criteria => {
+                    phase.criteria.push(Criterion {
+                        text: text.trim().to_string(),
+                        status,
+                        line: i,
+                    });
+                }
+                Section::Other => {}
+            }
+            task_open = false;
+            continue;
+        }
+
+        if line.trim().is_empty() {
+            continue;
+        }
+        if task_open && line.starts_with(char::is_whitespace) {
+            push_note(Some(phase), line);
+        } else {
+            task_open = false;
+        }
+    }
+
+    phases.extend(current.map(finish_phase));
+    phases
+}
+
+fn phase_heading(heading: &str, line: usize) -> Option<Phase> {
+    let rest = heading.trim().strip_prefix("Phase ")?;
+    let (number, title) = match rest.split_once(':') {
+        Some((number, title)) => (number.trim(), title.trim()),
+        None => (rest.trim(), ""),
+    };
+    Some(Phase {
+        number: number.to_string(),
+        title: title.to_string(),
+        status: None,
+        dependency: None,
+        tasks: Vec::new(),
+        criteria: Vec::new(),
+        line,
+    })
+}
+
+fn push_note(phase: Option<&mut Phase>, line: &str) {
+    if let Some(task) = phase.and_then(|p| p.tasks.last_mut()) {
+        task.notes.push(line.to_string());
+    }
+}
+
+fn finish_phase(mut phase: Phase) -> Phase {
+    for task in &mut phase.tasks {
+        task.blocker = blocker_note(task);
+        task.files = file_refs(task);
+        task.depends = depends(task);
+        task.covers = covers(task);
+    }
+    phase
+}
+
+/// Value of a `**Name**: value` line.
+fn field<'a>(line: &'a str, name: &str) -> Option<&'a str> {
+    line.trim_start()
+        .strip_prefix("**")?
+        .strip_prefix(name)?
+        .strip_prefix("**:")
+}
+
+/// Marker character of the first `[c]` in `text`.
+fn checkbox_marker(text: &str) -> Option<char> {
+    let rest = &text[text.find('[')? + 1..];
+    let mut chars = rest.chars();
+    let marker = chars.next()?;
+    (chars.next() == Some(']')).then_some(marker)
+}
+
+/// Split a `- [c] text` list item into its indentation, status and text.
+fn checkbox(line: &str) -> Option<(usize, Status, &str)> {
+    let body = line.trim_start();
+    let indent = line.len() - body.len();
+    let rest = body
+        .strip_prefix("- ")
+        .or_else(|| body.strip_prefix("* "))
+        .or_else(|| body.strip_prefix("+ "))?
+        .strip_prefix('[')?;
+    let mut chars = rest.chars();
+    let marker = chars.next()?;
+    let text = chars.as_str().strip_prefix(']')?;
+    if !(text.is_empty() || text.starts_with(' ')) {
+        return None;
+    }
+    Some((indent, Status::from_marker(marker), text))
+}
+
+fn task(status: Status, text: &str, line: usize) -> Task {
+    let text = text.trim();
+    let (first, rest) = text.split_once(' ').unwrap_or((text, ""));
+    let number = first.trim_end_matches('.');
+    let is_number = number.starts_with(|c: char| c.is_ascii_digit())
+        && number.chars().all(|c| c.is_ascii_digit() || c == '.');
+
+    let (id, title) = if is_number {
+        (Some(number.to_string()), rest.trim().to_string())
+    } else {
+        (None, text.to_string())
+    };
+
+    Task {
+        id,
+        title,
+        status,
+        notes: Vec::new(),
+        blocker: None,
+        files: Vec::new(),
+        depends: Vec::new(),
+        covers: Vec::new(),
+        line,
+    }
+}
+
+fn blocker_note(task: &Task) -> Option<String> {
+    std::iter::once(task.title.as_str())
+        .chain(task.notes.iter().map(String::as_str))
+        .find_map(blocker_in)
+}
+
+fn blocker_in(text: &str) -> Option<String> {
+    let plain = text.replace("**", "");
+    let lower = plain.to_ascii_lowercase();
+    ["blocker:", "blocked:", "blocked by "]
+        .iter()
+        .find_map(|key| {
+            let at = lower.find(key)?;
+            let note = plain[at + key.len()..].trim();
+            (!note.is_empty()).then(|| note.to_string())
+        })
+}
+
+/// Task references after `depends:` in the title or a note: `2.1`, `3.*`.
+fn depends(task: &Task) -> Vec<String> {
+    listed(task, "depends:", |token| {
+        let number = token.strip_suffix(".*").unwrap_or(token);
+        number.starts_with(|c: char| c.is_ascii_digit())
+            && number.chars().all(|c| c.is_ascii_digit() || c == '.')
+    })
+}
+
+/// Spec requirement IDs after `covers:` in the title or a note.
+fn covers(task: &Task) -> Vec<String> {
+    listed(task, "covers:", spec::is_requirement_id)
+}
+
+/// Tokens of the comma- or space-separated list after `key` (up to a closing
+/// parenthesis) that pass `is_ref`, without duplicates.
+fn listed(task: &Task, key: &str, is_ref: impl Fn(&str) -> bool) -> Vec<String> {
+    let mut refs: Vec<String> = Vec::new();
+    for text in std::iter::once(&task.title).chain(&task.notes) {
+        let plain = text.replace("**", "");
+        let Some(at) = plain.to_ascii_lowercase().find(key) else {
+            continue;
+        };
+        let list = plain[at + key.len()..].split(')').next().unwrap_or("");
+        for token in list.split(|c: char| c == ',' || c.is_whitespace()) {
+            let token = token.trim_matches(|c: char| matches!(c, '`' | ';' | '.'));
+            if is_ref(token) && !refs.iter().any(|r| r == token) {
+                refs.push(token.to_string());
+            }
+        }
+    }
+    refs
+}
+
+/// Paths quoted in backticks in the title and notes, outside code fences.
+fn file_refs(task: &Task) -> Vec<FileRef> {
+    let mut refs: Vec<FileRef> = Vec::new();
+    let mut in_fence = false;
+
+    for text in std::iter::once(&task.title).chain(&task.notes) {
+        if text.trim_start().starts_with("```") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+        for span in text.split('`').skip(1).step_by(2) {
+            if let Some(file) = file_ref(span) {
+                if !refs.contains(&file) {
+                    refs.push(file);
+                }
+            }
+        }
+    }
+    refs
+}
+
+fn file_ref(span: &str) -> Option<FileRef> {
+    if span.is_empty()
+        || span.starts_with('/')
+        || span.starts_with("http")
+        || span.contains(|c: char| c.is_whitespace() || "(){}<>\"'=".contains(c))
+    {
+        return None;
+    }
+
+    let (path, lines) = match span.split_once(':') {
+        Some((path, lines))
+            if !lines.is_empty() && lines.chars().all(|c| c.is_ascii_digit() || c == '-') =>
+        {
+            (path, Some(lines.to_string()))
+        }
+        Some(_) => return None,
+        None => (span, None),
+    };
+
+    let file_name = path.rsplit('/').next().unwrap_or(path);
+    let has_extension = file_name.rsplit_once('.').is_some_and(|(stem, ext)| {
+        !stem.is_empty()
+            && ext.len() <= 5
+            && ext.chars().all(|c| c.is_ascii_alphanumeric())
+            && ext.chars().any(|c| c.is_ascii_alphabetic())
+    });
+
+    (path.contains('/') || has_extension).then(|| FileRef {
+        path: path.to_string(),
+        lines,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Plan, Status};
+
+    const PLAN: &str = "\
+# Plan
+
+## Phase 1: Backend
+**Status**: [~] In progress
+**Dependency**: None
+
+### Tasks
+- [x] 1.1 Create the model (`models.py:10-20`)
+- [!] 1.2 Add the serializer
+  - Blocked: waiting for the schema
+  - depends: 1.1
+- [ ] Write docs
+  ```
+  - [ ] 9.9 not a task
+  ```
+
+### Completion Criteria
+- [ ] Endpoints return data
+
+## Notes
+- [ ] 7.1 outside any phase
+
+## Phase 2: Frontend
+**Dependency**: Phase 1
+
+### Tasks
+- [>] 2.1 Page (covers: AUDIT-1, AUDIT-2)
+";
+
+    #[test]
+    fn phases_tasks_and_criteria() {
+        let plan = Plan::parse(PLAN);
+        assert_eq!(plan.phases.len(), 2);
+
+        let backend = &plan.phases[0];
+        assert_eq!(
+            (backend.number.as_str(), backend.title.as_str()),
+            ("1", "Backend")
+        );
+        assert_eq!(backend.status, Some(Status::InProgress));
+        assert_eq!(backend.dependency.as_deref(), Some("None"));
+        let labels: Vec<&str> = backend.tasks.iter().map(|t| t.label()).collect();
+        assert_eq!(labels, ["1.1", "1.2", "Write docs"]);
+        assert_eq!(backend.criteria.len(), 1);
+        assert_eq!(backend.criteria[0].text, "Endpoints return data");
+
+        let frontend = &plan.phases[1];
+        assert_eq!(frontend.dependency.as_deref(), Some("Phase 1"));
+        assert_eq!(frontend.tasks[0].status, Status::Doing);
+        assert!(plan.task("7.1").is_none());
+        assert!(plan.task("9.9").is_none());
+    }
+
+    #[test]
+    fn task_notes_and_references() {
+        let plan = Plan::parse(PLAN);
+
+        let model = plan.task("1.1").unwrap();
+        assert_eq!(model.title, "Create the model (`models.py:10-20`)");
+        assert_eq!(model.files[0].path, "models.py");
+        assert_eq!(model.files[0].lines.as_deref(), Some("10-20"));
+        assert_eq!(model.line, 7);
+
+        let serializer = plan.task("1.2").unwrap();
+        assert_eq!(serializer.status, Status::Blocked);
+        assert_eq!(serializer.notes.len(), 2);
+        assert_eq!(
+            serializer.blocker.as_deref(),
+            Some("waiting for the schema")
+        );
+        assert_eq!(serializer.depends, ["1.1"]);
+
+        let page = plan.task("2.1").unwrap();
+        assert_eq!(page.covers, ["AUDIT-1", "AUDIT-2"]);
+    }
+}
@@ -0,0 +1,280 @@
+//! Task status snapshots taken before and after an iteration.
+//!
+//! Comparing two snapshots tells the loop which tasks moved (e.g. `[ ]` to
+//! `[x]`), so an iteration that edits code without advancing any task, or
+//! that changes nothing at all, can be told apart from real progress.
+
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::{Plan, Status};
+use crate::error::{Error, Result};
+
+/// Exit code of `ralph plan progress` when no task moved.
+pub const EXIT_NO_PROGRESS: u8 = 4;
+
+/// What the loop writes into its own directory: logs, archived runs and the
+/// local Archon store. Changes there are not code changes.
+pub const LOOP_STATE: [&str; 4] = ["logs", "archive", "archon.json", "archon.json.lock"];
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskState {
+    /// Task number, or the title for unnumbered tasks.
+    pub key: String,
+    pub title: String,
+    pub status: Status,
+}
+
+/// Status of every task in the plan, in document order.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub tasks: Vec<TaskState>,
+}
+
+impl Snapshot {
+    pub fn of(plan: &Plan) -> Snapshot {
+        let tasks = plan
+            .tasks()
+            .map(|t| TaskState {
+                key: t.label().to_string(),
+                title: t.title.clone(),
+                status: t.status,
+            })
+            .collect();
+        Snapshot { tasks }
+    }
+
+    pub fn load(path: &Path) -> Result<Snapshot> {
+        let text = fs::read_to_string(path).map_err(|e| Error::io(path, e))?;
+        serde_json::from_str(&text).map_err(|e| Error::json(path, e))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let text = serde_json::to_string_pretty(self).expect("snapshot serializes");
+        fs::write(path, text + "\n").map_err(|e| Error::io(path, e))
+    }
+
+    fn get(&self, key: &str) -> Option<&TaskState> {
+        self.tasks.iter().find(|t| t.key == key)
+    }
+
+    /// Tasks whose status differs in `after`, including tasks that were
+    /// added or removed.
+    pub fn transitions(&self, after: &Snapshot) -> Vec<Transition> {
+        let mut moved: Vec<Transition> = after
+            .tasks
+            .iter()
+            .filter_map(|task| {
+                let from = self.get(&task.key).map(|t| t.status);
+                (from != Some(task.status)).then(|| Transition {
+                    task: task.key.clone(),
+                    title: task.title.clone(),
+                    from,
+                    to: Some(task.status),
+                })
+            })
+            .collect();
+        moved.extend(
+            self.tasks
+                .iter()
+                .filter(|task| after.get(&task.key).is_none())
+                .map(|task| Transition {
+                    task: task.key.clone(),
+                    title: task.title.clone(),
+                    from: Some(task.status),
+                    to: None,
+                }),
+        );
+        moved
+    }
+}
+
+/// A task whose status changed between two snapshots. `None` means the task
+/// did not exist on that side.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Transition {
+    pub task: String,
+    pub title: String,
+    pub from: Option<Status>,
+    pub to: Option<Status>,
+}
+
+impl Transition {
+    /// Whether the move counts as progress. The loop itself marks its tasks
+    /// doing (`[>]`) before an iteration and blocked (`[!]`) after a failed
+    /// one, so neither does.
+    pub fn advances(&self) -> bool {
+        !matches!(self.to, Some(Status::Doing | Status::Blocked))
+    }
+}
+
+impl fmt::Display for Transition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let side = |status: Option<Status>, missing: &str| {
+            status.map_or_else(|| missing.to_string(), |s| s.to_string())
+        };
+        write!(
+            f,
+            "{} {} -> {}",
+            self.task,
+            side(self.from, "(new)"),
+            side(self.to, "(removed)")
+        )?;
+        if self.task != self.title {
+            write!(f, " {}", self.title)?;
+        }
+        Ok(())
+    }
+}
+
+/// How an iteration affected the plan.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Progress {
+    /// At least one task changed status, other than being started or
+    /// blocked.
+    Advanced,
+    /// Code changed but no task moved.
+    CodeOnly,
+    /// Neither code nor any task changed.
+    Idle,
+}
+
+impl Progress {
+    pub fn classify(transitions: &[Transition], code_changed: bool) -> Progress {
+        if transitions.iter().any(Transition::advances) {
+            Progress::Advanced
+        } else if code_changed {
+            Progress::CodeOnly
+        } else {
+            Progress::Idle
+        }
+    }
+
+    /// Whether the iteration spun without advancing the plan.
+    pub fn is_spin(self) -> bool {
+        self != Progress::Advanced
+    }
+}
+
+/// Whether any of the `changed` files is code: neither the plan nor the
+/// loop's state under `state_dir`. All paths must be absolute.
+pub fn code_changed(changed: &[PathBuf], plan: &Path, state_dir: &Path) -> bool {
+    changed.iter().any(|path| {
+        path != plan
+            && !LOOP_STATE
+                .iter()
+                .any(|state| path.starts_with(state_dir.join(state)))
+    })
+}
+
+impl fmt::Display for Progress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Progress::Advanced => "plan advanced",
+            Progress::CodeOnly => "code changed but no task moved",
+            Progress::Idle => "nothing changed",
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(tasks: &[(&str, Status)]) -> Snapshot {
+        Snapshot {
+            tasks: tasks
+                .iter()
+                .map(|&(key, status)| TaskState {
+                    key: key.to_string(),
+                    title: format!("Task {key}"),
+                    status,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn transitions_cover_moved_added_and_removed_tasks() {
+        let before = snapshot(&[
+            ("1.1", Status::Todo),
+            ("1.2", Status::Todo),
+            ("1.3", Status::Done),
+        ]);
+        let after = snapshot(&[
+            ("1.1", Status::Done),
+            ("1.2", Status::Todo),
+            ("1.4", Status::Todo),
+        ]);
+        let moved = before.transitions(&after);
+        let summary: Vec<(&str, Option<Status>, Option<Status>)> = moved
+            .iter()
+            .map(|t| (t.task.as_str(), t.from, t.to))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("1.1", Some(Status::Todo), Some(Status::Done)),
+                ("1.4", None, Some(Status::Todo)),
+                ("1.3", Some(Status::Done), None),
+            ]
+        );
+        assert_eq!(moved[0].to_string(), "1.1 [ ] -> [x] Task 1.1");
+        assert_eq!(moved[2].to_string(), "1.3 [x] -> (removed) Task 1.3");
+    }
+
+    #[test]
+    fn classify() {
+        let before = snapshot(&[("1.1", Status::Todo)]);
+        let done = before.transitions(&snapshot(&[("1.1", Status::Done)]));
+        assert_eq!(Progress::classify(&done, false), Progress::Advanced);
+        assert_eq!(Progress::classify(&[], true), Progress::CodeOnly);
+        assert_eq!(Progress::classify(&[], false), Progress::Idle);
+        assert!(Progress::CodeOnly.is_spin());
+        assert!(!Progress::Advanced.is_spin());
+    }
+
+    #[test]
+    fn started_or_blocked_tasks_are_not_progress() {
+        let before = snapshot(&[("1.1", Status::Todo), ("1.2", Status::Todo)]);
+        let after = snapshot(&[("1.1", Status::Doing), ("1.2", Status::Blocked)]);
+        let moved = before.transitions(&after);
+        assert_eq!(moved.len(), 2);
+        assert_eq!(Progress::classify(&moved, true), Progress::CodeOnly);
+
+        let partial = before.transitions(&snapshot(&[
+            ("1.1", Status::InProgress),
+            ("1.2", Status::Todo),
+        ]));
+        assert_eq!(Progress::classify(&partial, false), Progress::Advanced);
+    }
+
+    #[test]
+    fn loop_state_is_not_code() {
+        let ralph = Path::new("/project/ralph");
+        let plan = ralph.join("IMPLEMENTATION_PLAN.md");
+        let changed = |paths: &[&str]| {
+            let paths: Vec<PathBuf> = paths.iter().map(PathBuf::from).collect();
+            code_changed(&paths, &plan, ralph)
+        };
+        assert!(!changed(&[]));
+        assert!(!changed(&[
+            "/project/ralph/IMPLEMENTATION_PLAN.md",
+            "/project/ralph/logs/iteration_3.log",
+            "/project/ralph/logs/plan_snapshot.json",
+            "/project/ralph/archive/2026-10-17-audit/IMPLEMENTATION_PLAN.md",
+            "/project/ralph/archon.json",
+            "/project/ralph/archon.json.lock",
+        ]));
+        assert!(changed(&["/project/ralph/logs.md"]));
+        assert!(changed(&["/project/ralph/PROMPT_build.md"]));
+        assert!(changed(&[
+            "/project/ralph/logs/ledger.json",
+            "/project/backend/apps/reviews/models.py",
+        ]));
+    }
+}
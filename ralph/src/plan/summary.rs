@@ -0,0 +1,259 @@
+//! The `## Progress Summary` section, recomputed from the checkboxes.
+//!
+//! Only the section's table and its `**Overall Progress**:` line are
+//! rewritten. Descriptions already in the table are kept, since they are
+//! often shorter than the phase titles; new phases use their title.
+
+use super::{Phase, Plan, Status};
+
+const HEADING: &str = "## Progress Summary";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Row {
+    pub phase: String,
+    pub description: String,
+    pub status: &'static str,
+    pub tasks: usize,
+    pub done: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Summary {
+    pub rows: Vec<Row>,
+    pub tasks: usize,
+    pub done: usize,
+}
+
+impl Summary {
+    /// Counts from `plan`, with descriptions taken from its current table.
+    pub fn of(plan: &Plan) -> Summary {
+        let existing = section(plan.lines())
+            .map(|(start, end)| &plan.lines()[start..end])
+            .unwrap_or_default();
+        let rows: Vec<Row> = plan
+            .phases
+            .iter()
+            .map(|phase| Row {
+                phase: phase.number.clone(),
+                description: description(existing, &phase.number)
+                    .unwrap_or_else(|| phase.title.clone()),
+                status: phase_status(phase),
+                tasks: phase.tasks.len(),
+                done: phase.done_count(),
+            })
+            .collect();
+        Summary {
+            tasks: rows.iter().map(|r| r.tasks).sum(),
+            done: rows.iter().map(|r| r.done).sum(),
+            rows,
+        }
+    }
+
+    /// Whole percent done, rounded down so 100% means every task.
+    pub fn percent(&self) -> usize {
+        (self.done * 100).checked_div(self.tasks).unwrap_or(0)
+    }
+
+    /// The table and overall line, as they appear in the plan.
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![
+            "| Phase | Description | Status | Tasks | Done |".to_string(),
+            "|-------|-------------|--------|-------|------|".to_string(),
+        ];
+        for row in &self.rows {
+            lines.push(format!(
+                "| {} | {} | {} | {} | {} |",
+                row.phase, row.description, row.status, row.tasks, row.done
+            ));
+        }
+        lines.push(format!(
+            "| **Total** | | | **{}** | **{}** |",
+            self.tasks, self.done
+        ));
+        lines.push(String::new());
+        lines.push(format!(
+            "**Overall Progress**: {}% complete ({}/{} tasks)",
+            self.percent(),
+            self.done,
+            self.tasks
+        ));
+        lines
+    }
+}
+
+impl Plan {
+    /// Rewrite the Progress Summary from the checkboxes, appending the
+    /// section when the plan has none. Returns the old and new lines of
+    /// whatever changed; both are empty when the summary was up to date.
+    pub fn update_summary(&mut self) -> (Vec<String>, Vec<String>) {
+        let summary = Summary::of(self).lines();
+        let mut lines = self.lines.clone();
+        let old: Vec<String> = match section(&lines) {
+            Some((start, end)) => {
+                // Replace from the table through the overall line, keeping
+                // any prose around them.
+                let body = &lines[start..end];
+                let first = body.iter().position(|l| l.starts_with('|'));
+                let last = body
+                    .iter()
+                    .rposition(|l| is_overall(l) || l.starts_with('|'));
+                let (from, to) = match (first, last) {
+                    (Some(first), Some(last)) => (start + first, start + last + 1),
+                    _ => (start, start),
+                };
+                let mut replacement = summary.clone();
+                if from == to {
+                    replacement.insert(0, String::new());
+                    replacement.push(String::new());
+                }
+                lines.splice(from..to, replacement).collect()
+            }
+            None => {
+                while lines.last().is_some_and(|l| l.trim().is_empty()) {
+                    lines.pop();
+                }
+                lines.extend(["", "---", "", HEADING, ""].map(String::from));
+                lines.extend(summary.iter().cloned());
+                lines.push(String::new());
+                Vec::new()
+            }
+        };
+        if lines == self.lines {
+            return (Vec::new(), Vec::new());
+        }
+        *self = Plan::parse(&lines.join("\n"));
+
+        let changed = |line: &String, other: &[String]| !line.is_empty() && !other.contains(line);
+        let removed = old
+            .iter()
+            .filter(|l| changed(l, &summary))
+            .cloned()
+            .collect();
+        let added = summary
+            .iter()
+            .filter(|l| changed(l, &old))
+            .cloned()
+            .collect();
+        (removed, added)
+    }
+}
+
+/// Lines between the Progress Summary heading and the next heading or
+/// horizontal rule.
+fn section(lines: &[String]) -> Option<(usize, usize)> {
+    let heading = lines.iter().position(|l| l.trim_end() == HEADING)?;
+    let start = heading + 1;
+    let end = lines[start..]
+        .iter()
+        .position(|l| l.starts_with('#') || l.trim() == "---")
+        .map_or(lines.len(), |offset| start + offset);
+    Some((start, end))
+}
+
+fn is_overall(line: &str) -> bool {
+    line.trim_start().starts_with("**Overall Progress**")
+}
+
+/// Description cell of the table row for `phase`.
+fn description(section: &[String], phase: &str) -> Option<String> {
+    section.iter().find_map(|line| {
+        let mut cells = line.trim().strip_prefix('|')?.split('|').map(str::trim);
+        (cells.next()? == phase)
+            .then(|| cells.next().unwrap_or_default().to_string())
+            .filter(|d| !d.is_empty())
+    })
+}
+
+fn phase_status(phase: &Phase) -> &'static str {
+    let any = |status: Status| phase.tasks.iter().any(|t| t.status == status);
+    if phase.tasks.is_empty() {
+        return match phase.status {
+            Some(Status::Done) => "[x] Complete",
+            _ => "[ ] Not Started",
+        };
+    }
+    if phase.done_count() == phase.tasks.len() {
+        "[x] Complete"
+    } else if any(Status::Blocked) {
+        "[!] Blocked"
+    } else if any(Status::Done) || any(Status::InProgress) || any(Status::Doing) {
+        "[~] In Progress"
+    } else {
+        "[ ] Not Started"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PHASES: &str = "\
+## Phase 1: Backend foundation
+
+### Tasks
+- [x] 1.1 Model
+- [ ] 1.2 Serializer
+
+## Phase 2: Frontend
+
+### Tasks
+- [ ] 2.1 Page
+- [!] 2.2 Dialog
+";
+
+    #[test]
+    fn rewrites_the_table_and_keeps_descriptions() {
+        let text = format!(
+            "# Plan\n\n## Progress Summary\n\nCounts below.\n\n\
+             | Phase | Description | Status | Tasks | Done |\n\
+             |-------|-------------|--------|-------|------|\n\
+             | 1 | Backend | [ ] Not Started | 2 | 0 |\n\n\
+             **Overall Progress**: 0% complete (0/2 tasks)\n\n---\n\n{PHASES}"
+        );
+        let mut plan = Plan::parse(&text);
+        let (removed, added) = plan.update_summary();
+        assert_eq!(
+            removed,
+            [
+                "| 1 | Backend | [ ] Not Started | 2 | 0 |",
+                "**Overall Progress**: 0% complete (0/2 tasks)",
+            ]
+        );
+        assert!(added.contains(&"| 1 | Backend | [~] In Progress | 2 | 1 |".to_string()));
+        assert!(added.contains(&"| 2 | Frontend | [!] Blocked | 2 | 0 |".to_string()));
+        assert!(added.contains(&"**Overall Progress**: 25% complete (1/4 tasks)".to_string()));
+
+        let updated = plan.to_string();
+        assert!(updated.contains("## Progress Summary\n\nCounts below.\n\n| Phase |"));
+        assert!(updated.ends_with(PHASES));
+        assert_eq!(plan.update_summary(), (Vec::new(), Vec::new()));
+    }
+
+    #[test]
+    fn appends_a_missing_section() {
+        let mut plan = Plan::parse(PHASES);
+        let (removed, added) = plan.update_summary();
+        assert!(removed.is_empty());
+        assert_eq!(added.len(), 6);
+        let updated = plan.to_string();
+        assert!(updated.starts_with(PHASES.trim_end()));
+        assert!(updated.contains("\n---\n\n## Progress Summary\n\n| Phase |"));
+        assert_eq!(plan.phases.len(), 2);
+    }
+
+    #[test]
+    fn percent_rounds_down() {
+        let summary = Summary {
+            rows: Vec::new(),
+            tasks: 3,
+            done: 2,
+        };
+        assert_eq!(summary.percent(), 66);
+        let empty = Summary {
+            rows: Vec::new(),
+            tasks: 0,
+            done: 0,
+        };
+        assert_eq!(empty.percent(), 0);
+    }
+}
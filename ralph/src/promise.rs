@@ -0,0 +1,138 @@
+//! Completion promises such as `<promise>ALL_TASKS_COMPLETE</promise>`.
+//!
+//! Only text written by the model counts: assistant text blocks and the
+//! final `result` message of an iteration. Tool results are ignored because
+//! the model routinely reads files (the prompts, the plan) that quote the
+//! promise tags themselves.
+
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::error::{Error, Result};
+use crate::stream::{self, Event};
+
+const OPEN: &str = "<promise>";
+const CLOSE: &str = "</promise>";
+
+/// A promise tag the loop watches for and the exit code it stops with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Promise {
+    pub tag: String,
+    pub exit_code: u8,
+}
+
+impl FromStr for Promise {
+    type Err = Error;
+
+    /// Parse `TAG=CODE`, e.g. `ALL_TASKS_COMPLETE=10`. Codes 0 and 1 are
+    /// taken: they mean no promise was emitted and an error.
+    fn from_str(s: &str) -> Result<Promise> {
+        let invalid = || Error::InvalidPromise(s.to_string());
+        let (tag, code) = s.split_once('=').ok_or_else(invalid)?;
+        let exit_code: u8 = code.trim().parse().map_err(|_| invalid())?;
+        let tag = tag.trim();
+        if tag.is_empty() || exit_code < 2 {
+            return Err(invalid());
+        }
+        Ok(Promise {
+            tag: tag.to_string(),
+            exit_code,
+        })
+    }
+}
+
+/// Tags of every `<promise>...</promise>` in `text`, in order.
+pub fn tags(text: &str) -> Vec<&str> {
+    let mut found = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find(OPEN) {
+        let after = &rest[start + OPEN.len()..];
+        let Some(end) = after.find(CLOSE) else { break };
+        found.push(after[..end].trim());
+        rest = &after[end + CLOSE.len()..];
+    }
+    found
+}
+
+/// Text the model itself produced.
+fn model_text(event: &Event) -> Option<&str> {
+    match event {
+        Event::AssistantText { text } => Some(text),
+        Event::Result(result) => result.result.as_deref(),
+        _ => None,
+    }
+}
+
+/// The first watched promise in a decoded iteration log.
+pub fn find<'a>(events: &[Event], promises: &'a [Promise]) -> Option<&'a Promise> {
+    events
+        .iter()
+        .filter_map(model_text)
+        .flat_map(tags)
+        .find_map(|tag| promises.iter().find(|p| p.tag == tag))
+}
+
+/// The first promise tag the model emitted, watched or not.
+pub fn emitted(events: &[Event]) -> Option<&str> {
+    events.iter().filter_map(model_text).flat_map(tags).next()
+}
+
+/// The first watched promise emitted in a stream-json iteration log.
+pub fn scan<'a>(log: &Path, promises: &'a [Promise]) -> Result<Option<&'a Promise>> {
+    Ok(find(&stream::read(log)?, promises))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn promises() -> Vec<Promise> {
+        ["ALL_TASKS_COMPLETE=10", "VERIFICATION_COMPLETE=12"]
+            .iter()
+            .map(|p| p.parse().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn promises_parse_with_an_exit_code_above_one() {
+        assert_eq!(
+            " DONE = 20".parse::<Promise>().unwrap(),
+            Promise {
+                tag: "DONE".to_string(),
+                exit_code: 20,
+            }
+        );
+        for invalid in ["DONE", "=20", "DONE=x", "DONE=256", "DONE=0", "DONE=1"] {
+            assert!(invalid.parse::<Promise>().is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn tags_are_found_in_order() {
+        let text = "a <promise> ONE </promise> b <promise>TWO</promise> <promise>open";
+        assert_eq!(tags(text), ["ONE", "TWO"]);
+        assert!(tags("no promise here").is_empty());
+    }
+
+    #[test]
+    fn only_model_text_counts() {
+        let log = concat!(
+            r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"t1","name":"Read","input":{"file_path":"PROMPT_build.md"}}]}}"#,
+            "\n",
+            r#"{"type":"user","message":{"content":[{"type":"tool_result","tool_use_id":"t1","content":"emit <promise>ALL_TASKS_COMPLETE</promise>"}]}}"#,
+            "\n",
+        );
+        let promises = promises();
+        let events = stream::decode(log);
+        assert_eq!(find(&events, &promises), None);
+        assert_eq!(emitted(&events), None);
+
+        let done = format!(
+            "{log}{}\n",
+            r#"{"type":"assistant","message":{"content":[{"type":"text","text":"<promise>OTHER</promise> <promise>VERIFICATION_COMPLETE</promise>"}]}}"#
+        );
+        let events = stream::decode(&done);
+        assert_eq!(find(&events, &promises), Some(&promises[1]));
+        assert_eq!(emitted(&events), Some("OTHER"));
+    }
+}
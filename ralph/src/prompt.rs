@@ -0,0 +1,167 @@
+//! Prompt templates: `PROMPT_*.md` files with variables and includes.
+//!
+//! `{{ name }}` is replaced by a variable, e.g. `{{ iteration }}` or
+//! `{{ paths.backend }}`; `{{> prompts/rls-check.md }}` is replaced by that
+//! file, itself rendered, with the path relative to the including file.
+//! `{{> includes.build }}` includes the file a variable names instead, so a
+//! profile can bring its own sections; an empty variable includes nothing. A
+//! literal `{{` is written `\{{`. Unknown variables and missing includes are
+//! errors, so a typo never reaches the model as raw template text.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::{Error, Result};
+
+/// Includes nested deeper than this are taken for a cycle.
+const MAX_DEPTH: usize = 8;
+
+/// Variables available to a template.
+pub type Vars = BTreeMap<String, String>;
+
+/// Render the template at `path`.
+pub fn render(path: &Path, vars: &Vars) -> Result<String> {
+    render_file(path, vars, &mut Vec::new())
+}
+
+fn render_file(path: &Path, vars: &Vars, stack: &mut Vec<PathBuf>) -> Result<String> {
+    let text = fs::read_to_string(path).map_err(|e| Error::io(path, e))?;
+    stack.push(path.to_path_buf());
+    let rendered = render_text(path, &text, vars, stack);
+    stack.pop();
+    rendered
+}
+
+fn render_text(path: &Path, text: &str, vars: &Vars, stack: &mut Vec<PathBuf>) -> Result<String> {
+    let error = |offset: usize, message: String| Error::Template {
+        path: path.to_path_buf(),
+        line: text[..offset].matches('\n').count() + 1,
+        message,
+    };
+
+    let mut out = String::with_capacity(text.len());
+    let mut rest = 0;
+    while let Some(found) = text[rest..].find("{{") {
+        let open = rest + found;
+        if text[..open].ends_with('\\') {
+            out.push_str(&text[rest..open - 1]);
+            out.push_str("{{");
+            rest = open + 2;
+            continue;
+        }
+        out.push_str(&text[rest..open]);
+        let close = text[open..]
+            .find("}}")
+            .map(|i| open + i)
+            .ok_or_else(|| error(open, "`{{` is never closed".to_string()))?;
+        let tag = text[open + 2..close].trim();
+
+        if let Some(include) = tag.strip_prefix('>') {
+            let include = match vars.get(include.trim()) {
+                Some(file) if file.is_empty() => {
+                    rest = close + 2;
+                    continue;
+                }
+                Some(file) => PathBuf::from(file),
+                None => path.parent().unwrap_or(Path::new(".")).join(include.trim()),
+            };
+            if stack.len() >= MAX_DEPTH || stack.contains(&include) {
+                return Err(error(
+                    open,
+                    format!("include cycle through {}", include.display()),
+                ));
+            }
+            let included = render_file(&include, vars, stack)?;
+            out.push_str(included.strip_suffix('\n').unwrap_or(&included));
+        } else {
+            let value = vars
+                .get(tag)
+                .ok_or_else(|| error(open, format!("unknown variable `{tag}`")))?;
+            out.push_str(value);
+        }
+        rest = close + 2;
+    }
+    out.push_str(&text[rest..]);
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    /// A fresh directory holding `files`.
+    fn dir(files: &[(&str, &str)]) -> TempDir {
+        let dir = TempDir::new("prompt");
+        for (file, text) in files {
+            dir.write(file, text);
+        }
+        dir
+    }
+
+    fn vars(pairs: &[(&str, &str)]) -> Vars {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn variables_and_escapes() {
+        let dir = dir(&[("PROMPT.md", "Iteration {{ iteration }} of \\{{ max }}\n")]);
+        let text = render(&dir.join("PROMPT.md"), &vars(&[("iteration", "3")])).unwrap();
+        assert_eq!(text, "Iteration 3 of {{ max }}\n");
+    }
+
+    #[test]
+    fn unknown_variable_names_the_line() {
+        let dir = dir(&[("PROMPT.md", "# Build\n\nRun {{ paths.tset }}\n")]);
+        let err = render(&dir.join("PROMPT.md"), &Vars::new()).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::Template { line: 3, message, .. } if message == "unknown variable `paths.tset`"
+        ));
+    }
+
+    #[test]
+    fn includes_are_relative_to_the_including_file() {
+        let dir = dir(&[
+            ("PROMPT.md", "Start\n{{> prompts/rls.md }}\nEnd\n"),
+            ("prompts/rls.md", "Check {{ feature }}\n"),
+        ]);
+        let text = render(&dir.join("PROMPT.md"), &vars(&[("feature", "audit")])).unwrap();
+        assert_eq!(text, "Start\nCheck audit\nEnd\n");
+    }
+
+    #[test]
+    fn variable_includes() {
+        let dir = dir(&[
+            ("PROMPT.md", "A\n{{> includes.build }}\nB\n"),
+            ("profile/build.md", "Profile section\n"),
+        ]);
+        let build = dir.join("profile/build.md");
+        let path = dir.join("PROMPT.md");
+        let with = vars(&[("includes.build", build.to_str().unwrap())]);
+        assert_eq!(render(&path, &with).unwrap(), "A\nProfile section\nB\n");
+        let empty = vars(&[("includes.build", "")]);
+        assert_eq!(render(&path, &empty).unwrap(), "A\n\nB\n");
+    }
+
+    #[test]
+    fn include_cycle_is_an_error() {
+        let dir = dir(&[("a.md", "{{> b.md }}\n"), ("b.md", "{{> a.md }}\n")]);
+        let err = render(&dir.join("a.md"), &Vars::new()).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::Template { message, .. } if message.starts_with("include cycle through")
+        ));
+    }
+
+    #[test]
+    fn unclosed_tag_is_an_error() {
+        let dir = dir(&[("PROMPT.md", "Run {{ iteration\n")]);
+        let err = render(&dir.join("PROMPT.md"), &Vars::new()).unwrap_err();
+        assert!(matches!(err, Error::Template { line: 1, .. }));
+    }
+}
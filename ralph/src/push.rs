@@ -0,0 +1,151 @@
+//! When and where the loop pushes its commits.
+//!
+//! New work is detected by comparing `HEAD` with the remote-tracking branch,
+//! so commits made by the model are pushed even when the working tree is
+//! clean. Protected branches are never pushed, and a failed push is reported
+//! with git's own error instead of being retried blindly.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+use crate::git;
+
+/// Exit code of `ralph push` when git refused the push.
+pub const EXIT_PUSH_FAILED: u8 = 6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Policy {
+    /// Never push; the branch stays local.
+    Never,
+    /// Push after every iteration that added commits.
+    AfterCommit,
+    /// Push once when the loop ends.
+    AtEnd,
+    /// Push only when the model emitted a completion promise.
+    OnPromise,
+}
+
+impl fmt::Display for Policy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Policy::Never => "never",
+            Policy::AfterCommit => "after-commit",
+            Policy::AtEnd => "at-end",
+            Policy::OnPromise => "on-promise",
+        })
+    }
+}
+
+/// Point in the loop at which a push is considered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Moment {
+    /// After an iteration finished.
+    Iteration,
+    /// A completion promise was received.
+    Promise,
+    /// The loop is about to exit, for any reason.
+    End,
+}
+
+impl Policy {
+    pub fn applies(self, moment: Moment) -> bool {
+        match self {
+            Policy::Never => false,
+            // The end push catches commits of an iteration the loop aborted.
+            Policy::AfterCommit => matches!(moment, Moment::Iteration | Moment::End),
+            Policy::AtEnd => moment == Moment::End,
+            Policy::OnPromise => moment == Moment::Promise,
+        }
+    }
+}
+
+/// Whether `branch` matches one of `patterns`: an exact name, or a prefix
+/// followed by `*` such as `release/*`.
+pub fn is_protected(branch: &str, patterns: &[String]) -> bool {
+    patterns
+        .iter()
+        .any(|pattern| match pattern.strip_suffix('*') {
+            Some(prefix) => branch.starts_with(prefix),
+            None => branch == pattern,
+        })
+}
+
+/// Local commits not yet on `remote`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Pending {
+    pub head: String,
+    /// Commit of the remote-tracking branch, `None` if it does not exist yet.
+    pub upstream: Option<String>,
+    pub commits: usize,
+}
+
+/// Compare `HEAD` with `remote/branch`. `None` when there is nothing to push.
+pub fn pending(remote: &str, branch: &str) -> Result<Option<Pending>> {
+    let Some(head) = git::rev_parse("HEAD")? else {
+        return Ok(None);
+    };
+    let upstream = git::rev_parse(&format!("refs/remotes/{remote}/{branch}"))?;
+    if upstream.as_deref() == Some(head.as_str()) {
+        return Ok(None);
+    }
+    let commits = match &upstream {
+        Some(upstream) => git::commits_between(upstream, "HEAD")?,
+        None => git::run(&[
+            "rev-list",
+            "--count",
+            "HEAD",
+            "--not",
+            &format!("--remotes={remote}"),
+        ])?
+        .parse()
+        .unwrap_or_default(),
+    };
+    Ok((commits > 0).then_some(Pending {
+        head,
+        upstream,
+        commits,
+    }))
+}
+
+/// Push `branch` to `remote`, setting the upstream when it is new.
+pub fn push(remote: &str, branch: &str, pending: &Pending) -> Result<()> {
+    let mut args = vec!["push"];
+    if pending.upstream.is_none() {
+        args.push("--set-upstream");
+    }
+    args.extend([remote, branch]);
+    git::run(&args).map(drop)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn policies_apply_at_their_moments() {
+        use Moment::*;
+        let moments = |policy: Policy| -> Vec<Moment> {
+            [Iteration, Promise, End]
+                .into_iter()
+                .filter(|&m| policy.applies(m))
+                .collect()
+        };
+        assert_eq!(moments(Policy::Never), []);
+        assert_eq!(moments(Policy::AfterCommit), [Iteration, End]);
+        assert_eq!(moments(Policy::AtEnd), [End]);
+        assert_eq!(moments(Policy::OnPromise), [Promise]);
+    }
+
+    #[test]
+    fn protected_branches() {
+        let patterns = ["main".to_string(), "release/*".to_string()];
+        assert!(is_protected("main", &patterns));
+        assert!(is_protected("release/1.2", &patterns));
+        assert!(!is_protected("main-fix", &patterns));
+        assert!(!is_protected("feature/release", &patterns));
+        assert!(!is_protected("main", &[]));
+    }
+}
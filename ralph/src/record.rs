@@ -0,0 +1,96 @@
+//! Loop-level records appended to an iteration log.
+//!
+//! The loop writes its own observations (timeouts, plan progress, ...) into
+//! `logs/iteration_N.log` as stream-json lines with `"type": "ralph"`, so the
+//! log stays the single record of what happened in an iteration.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use serde_json::{Map, Value};
+
+use crate::error::{Error, Result};
+
+/// `type` of every line written by the loop rather than by claude.
+pub const RECORD_TYPE: &str = "ralph";
+
+/// Serialize one record. `fields` must be a JSON object.
+pub fn line(event: &str, fields: Value) -> String {
+    let mut record = Map::new();
+    record.insert("type".into(), RECORD_TYPE.into());
+    record.insert("event".into(), event.into());
+    if let Value::Object(fields) = fields {
+        record.extend(fields);
+    }
+    Value::Object(record).to_string()
+}
+
+/// Append one record to the iteration log, creating it if needed. When the
+/// log ends mid-line (a killed child's last output), the record starts on a
+/// new line rather than being glued to the fragment.
+pub fn append(log: &Path, event: &str, fields: Value) -> Result<()> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .read(true)
+        .append(true)
+        .open(log)
+        .map_err(|e| Error::io(log, e))?;
+    let mut text = line(event, fields);
+    if !ends_with_newline(&mut file).map_err(|e| Error::io(log, e))? {
+        text.insert(0, '\n');
+    }
+    writeln!(file, "{text}").map_err(|e| Error::io(log, e))
+}
+
+/// Whether `file` is empty or its last byte is a newline.
+fn ends_with_newline(file: &mut File) -> io::Result<bool> {
+    let len = file.metadata()?.len();
+    if len == 0 {
+        return Ok(true);
+    }
+    file.seek(SeekFrom::Start(len - 1))?;
+    let mut last = [0u8];
+    file.read_exact(&mut last)?;
+    Ok(last[0] == b'\n')
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn line_puts_type_and_event_first() {
+        assert_eq!(
+            line("plan_progress", json!({ "moved": 1 })),
+            r#"{"type":"ralph","event":"plan_progress","moved":1}"#
+        );
+    }
+
+    #[test]
+    fn append_starts_records_on_a_new_line() {
+        let dir = TempDir::new("record");
+        let log = dir.join("iteration_1.log");
+
+        append(&log, "iteration_start", json!({})).unwrap();
+        fs::write(&log, fs::read_to_string(&log).unwrap() + "partial output").unwrap();
+        append(&log, "timeout", json!({ "seconds": 5 })).unwrap();
+
+        let text = fs::read_to_string(&log).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                r#"{"type":"ralph","event":"iteration_start"}"#,
+                "partial output",
+                r#"{"type":"ralph","event":"timeout","seconds":5}"#,
+            ]
+        );
+        assert!(text.ends_with('\n'));
+    }
+}
@@ -0,0 +1,389 @@
+//! Per-iteration summaries built from `logs/iteration_N.log`.
+//!
+//! Everything here is derived from the decoded log: claude's own messages
+//! say which tools ran, and the `iteration_start`/`iteration_end` records
+//! written by the loop add the mode, timing, exit status, and the HEAD
+//! before and after the iteration, between which git counts its commits.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::error::{Error, Result};
+use crate::gate;
+use crate::git;
+use crate::junit;
+use crate::promise;
+use crate::stream::{self, Event, ToolUse};
+
+/// Tools whose `file_path` input is a file the model changed.
+const EDIT_TOOLS: &[&str] = &["Edit", "MultiEdit", "Write", "NotebookEdit"];
+
+/// Leading words that mark a shell command as a test run.
+const TEST_COMMANDS: &[&[&str]] = &[
+    &["pytest"],
+    &["npm", "test"],
+    &["npm", "run", "test"],
+    &["vitest"],
+    &["jest"],
+    &["cargo", "test"],
+    &["go", "test"],
+];
+
+/// A shell command that ran a test suite, and whether it succeeded.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TestRun {
+    pub command: String,
+    pub passed: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct IterationSummary {
+    pub iteration: u32,
+    pub log: PathBuf,
+    /// ID of the loop run the iteration belonged to.
+    pub run: Option<String>,
+    pub mode: Option<String>,
+    pub model: Option<String>,
+    /// Unix timestamps from the loop's start/end records.
+    pub started_at: Option<u64>,
+    pub ended_at: Option<u64>,
+    pub duration_secs: Option<u64>,
+    pub turns: Option<u32>,
+    pub cost_usd: Option<f64>,
+    /// Number of calls per tool name.
+    pub tools: BTreeMap<String, usize>,
+    pub files_edited: Vec<String>,
+    pub tests: Vec<TestRun>,
+    /// Results read from the JUnit reports of the iteration's test runs.
+    pub junit: Option<junit::Record>,
+    /// The loop's own test run after the iteration's round.
+    pub gate: Option<gate::Record>,
+    /// Commits between HEAD before and after the iteration, in its worktree
+    /// when it had one.
+    pub commits: usize,
+    pub promise: Option<String>,
+    /// `None` when the iteration ended cleanly, otherwise a short reason.
+    pub error: Option<String>,
+}
+
+impl IterationSummary {
+    /// Whether the tests passed: the loop's gate run when there was one,
+    /// else the iteration's JUnit reports, else the model's last test run.
+    /// `None` when nothing ran tests.
+    pub fn tests_passed(&self) -> Option<bool> {
+        match (&self.gate, &self.junit) {
+            (Some(gate), _) => Some(gate.passed()),
+            (None, Some(junit)) => Some(junit.total.is_green()),
+            (None, None) => self.tests.last().map(|run| run.passed),
+        }
+    }
+}
+
+/// Summarize one decoded iteration log.
+pub fn summarize(iteration: u32, log: &Path, events: &[Event]) -> IterationSummary {
+    let mut summary = IterationSummary {
+        iteration,
+        log: log.to_path_buf(),
+        ..IterationSummary::default()
+    };
+    let mut cwd: Option<String> = None;
+    let mut pending: BTreeMap<&str, &ToolUse> = BTreeMap::new();
+    let mut heads: Option<(String, String)> = None;
+
+    for event in events {
+        match event {
+            Event::SystemInit(init) => {
+                cwd = init.cwd.clone();
+                summary.model = summary.model.take().or_else(|| init.model.clone());
+            }
+            Event::ToolUse(tool) => {
+                *summary.tools.entry(tool.name.clone()).or_default() += 1;
+                if EDIT_TOOLS.contains(&tool.name.as_str()) {
+                    if let Some(path) = tool
+                        .input_str("file_path")
+                        .or_else(|| tool.input_str("notebook_path"))
+                    {
+                        let path = relative(path, cwd.as_deref());
+                        if !summary.files_edited.contains(&path) {
+                            summary.files_edited.push(path);
+                        }
+                    }
+                }
+                pending.insert(tool.id.as_str(), tool);
+            }
+            Event::ToolResult(result) => {
+                let Some(tool) = pending.remove(result.tool_use_id.as_str()) else {
+                    continue;
+                };
+                let Some(command) = tool.input_str("command") else {
+                    continue;
+                };
+                if is_test_command(command) {
+                    summary.tests.push(TestRun {
+                        command: command.to_string(),
+                        passed: !result.is_error,
+                    });
+                }
+            }
+            Event::Result(result) => {
+                summary.turns = result.num_turns;
+                summary.cost_usd = result.total_cost_usd;
+                if summary.duration_secs.is_none() {
+                    summary.duration_secs = result.duration_ms.map(|ms| ms / 1000);
+                }
+                if result.is_error {
+                    summary.error.get_or_insert_with(|| {
+                        format!("error: {}", result.subtype.as_deref().unwrap_or("result"))
+                    });
+                }
+            }
+            Event::Record(record) => match record.event.as_str() {
+                "iteration_start" => {
+                    summary.run = str_field(&record.fields, "run");
+                    summary.mode = str_field(&record.fields, "mode");
+                    summary.started_at = record.fields.get("started_at").and_then(Value::as_u64);
+                    if let Some(model) = str_field(&record.fields, "model") {
+                        summary.model = Some(model);
+                    }
+                }
+                "iteration_end" => {
+                    summary.ended_at = record.fields.get("ended_at").and_then(Value::as_u64);
+                    heads = str_field(&record.fields, "head_before")
+                        .zip(str_field(&record.fields, "head_after"));
+                    match record.fields.get("exit_code").and_then(Value::as_i64) {
+                        Some(0) | None => {}
+                        Some(code) => {
+                            summary.error.get_or_insert_with(|| format!("exit {code}"));
+                        }
+                    }
+                }
+                "timeout" | "stall" | "interrupted" => summary.error = Some(record.event.clone()),
+                _ => {}
+            },
+            Event::Truncated { .. } => {
+                summary.error.get_or_insert_with(|| "truncated".to_string());
+            }
+            _ => {}
+        }
+    }
+
+    if let (Some(start), Some(end)) = (summary.started_at, summary.ended_at) {
+        summary.duration_secs = Some(end.saturating_sub(start));
+    }
+    if let Some((before, after)) = heads.filter(|(before, after)| before != after) {
+        summary.commits = git::commits_between(&before, &after).unwrap_or_default();
+    }
+    summary.junit = junit::last_record(events);
+    summary.gate = gate::last_record(events);
+    summary.promise = promise::emitted(events).map(str::to_string);
+    summary
+}
+
+/// Summaries of the `iteration_N.log` files in `dir` that belong to `run`,
+/// or to the run that started last, in iteration order. Logs an earlier,
+/// longer run left behind are not part of it.
+pub fn load_run(dir: &Path, run: Option<&str>) -> Result<Vec<IterationSummary>> {
+    let entries = fs::read_dir(dir).map_err(|e| Error::io(dir, e))?;
+    let mut logs: Vec<(u32, PathBuf)> = Vec::new();
+    for entry in entries {
+        let path = entry.map_err(|e| Error::io(dir, e))?.path();
+        if let Some(n) = iteration_number(&path) {
+            logs.push((n, path));
+        }
+    }
+    logs.sort();
+
+    let summaries = logs
+        .into_iter()
+        .map(|(n, path)| Ok(summarize(n, &path, &stream::read(&path)?)))
+        .collect::<Result<Vec<_>>>()?;
+    let run = match run {
+        Some(run) => Some(run.to_string()),
+        None => summaries
+            .iter()
+            .max_by_key(|s| (s.started_at, s.iteration))
+            .and_then(|s| s.run.clone()),
+    };
+    Ok(summaries.into_iter().filter(|s| s.run == run).collect())
+}
+
+/// `N` of a path named `iteration_N.log`.
+pub fn iteration_number(path: &Path) -> Option<u32> {
+    path.file_name()?
+        .to_str()?
+        .strip_prefix("iteration_")?
+        .strip_suffix(".log")?
+        .parse()
+        .ok()
+}
+
+/// Totals across a run.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Totals {
+    pub iterations: usize,
+    pub duration_secs: u64,
+    pub turns: u64,
+    pub cost_usd: f64,
+    pub tools: BTreeMap<String, usize>,
+    pub files_edited: usize,
+    pub tests_run: usize,
+    pub tests_failed: usize,
+    pub commits: usize,
+    pub errors: usize,
+}
+
+impl Totals {
+    pub fn of(summaries: &[IterationSummary]) -> Totals {
+        let mut totals = Totals {
+            iterations: summaries.len(),
+            ..Totals::default()
+        };
+        let mut files: Vec<&str> = Vec::new();
+        for s in summaries {
+            totals.duration_secs += s.duration_secs.unwrap_or_default();
+            totals.turns += u64::from(s.turns.unwrap_or_default());
+            totals.cost_usd += s.cost_usd.unwrap_or_default();
+            for (tool, count) in &s.tools {
+                *totals.tools.entry(tool.clone()).or_default() += count;
+            }
+            for file in &s.files_edited {
+                if !files.contains(&file.as_str()) {
+                    files.push(file);
+                }
+            }
+            totals.tests_run += s.tests.len();
+            totals.tests_failed += s.tests.iter().filter(|t| !t.passed).count();
+            totals.commits += s.commits;
+            totals.errors += usize::from(s.error.is_some());
+        }
+        totals.files_edited = files.len();
+        totals
+    }
+}
+
+/// Whether any simple command in `command` (split at `&&`, `;`, `|`) starts
+/// with a test runner, after environment assignments and a wrapper such as
+/// `npx`, `poetry run` or `python -m`. A runner named in an argument, as in
+/// `cat jest.config.js`, does not count.
+fn is_test_command(command: &str) -> bool {
+    command.split(['&', ';', '|', '\n']).any(|part| {
+        let words: Vec<&str> = part
+            .split_whitespace()
+            .skip_while(|word| word.contains('=') && !word.starts_with('-'))
+            .collect();
+        let mut words = words.as_slice();
+        loop {
+            words = match words {
+                ["npx", rest @ ..] | ["poetry" | "uv" | "pipenv", "run", rest @ ..] => rest,
+                [python, "-m", rest @ ..] if python.starts_with("python") => rest,
+                _ => break,
+            };
+        }
+        let Some((program, args)) = words.split_first() else {
+            return false;
+        };
+        let program = Path::new(program)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or(program);
+        TEST_COMMANDS
+            .iter()
+            .any(|runner| runner[0] == program && args.starts_with(&runner[1..]))
+    })
+}
+
+fn relative(path: &str, cwd: Option<&str>) -> String {
+    cwd.and_then(|cwd| path.strip_prefix(cwd))
+        .map(|rest| rest.trim_start_matches('/'))
+        .unwrap_or(path)
+        .to_string()
+}
+
+fn str_field(fields: &serde_json::Map<String, Value>, key: &str) -> Option<String> {
+    fields.get(key).and_then(Value::as_str).map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_commands_are_matched_on_the_program() {
+        for command in [
+            "pytest -x tests/",
+            "cd backend && poetry run pytest -q",
+            "python -m pytest",
+            "DJANGO_SETTINGS_MODULE=app.settings .venv/bin/pytest",
+            "npx vitest run",
+            "npm test -- --watch=false",
+            "cargo fmt; cargo test -q 2>&1 | tail",
+            "go test ./...",
+        ] {
+            assert!(is_test_command(command), "{command}");
+        }
+        for command in [
+            "cat jest.config.js",
+            "ls tests/",
+            "grep pytest pyproject.toml",
+            "npm install",
+            "cargo build --tests",
+            "echo 'run pytest later'",
+        ] {
+            assert!(!is_test_command(command), "{command}");
+        }
+    }
+
+    #[test]
+    fn summarizes_tools_edits_and_test_runs() {
+        let log = concat!(
+            r#"{"type":"system","subtype":"init","session_id":"s1","model":"opus","cwd":"/work"}"#,
+            "\n",
+            r#"{"type":"ralph","event":"iteration_start","run":"r1","mode":"build","started_at":100}"#,
+            "\n",
+            r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"t1","name":"Edit","input":{"file_path":"/work/src/app.py"}}]}}"#,
+            "\n",
+            r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"t2","name":"Bash","input":{"command":"pytest"}}]}}"#,
+            "\n",
+            r#"{"type":"user","message":{"content":[{"type":"tool_result","tool_use_id":"t2","is_error":true,"content":"1 failed"}]}}"#,
+            "\n",
+            r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"t3","name":"Bash","input":{"command":"ls tests/"}}]}}"#,
+            "\n",
+            r#"{"type":"user","message":{"content":[{"type":"tool_result","tool_use_id":"t3","content":"test_app.py"}]}}"#,
+            "\n",
+            r#"{"type":"result","is_error":false,"num_turns":3,"total_cost_usd":0.25}"#,
+            "\n",
+            r#"{"type":"ralph","event":"iteration_end","exit_code":0,"ended_at":160}"#,
+            "\n",
+        );
+        let summary = summarize(2, Path::new("logs/iteration_2.log"), &stream::decode(log));
+        assert_eq!(summary.run.as_deref(), Some("r1"));
+        assert_eq!(summary.model.as_deref(), Some("opus"));
+        assert_eq!(summary.duration_secs, Some(60));
+        assert_eq!(summary.turns, Some(3));
+        assert_eq!(summary.tools["Bash"], 2);
+        assert_eq!(summary.files_edited, ["src/app.py"]);
+        assert_eq!(
+            summary.tests,
+            [TestRun {
+                command: "pytest".to_string(),
+                passed: false,
+            }]
+        );
+        assert_eq!(summary.tests_passed(), Some(false));
+        assert_eq!(summary.error, None);
+    }
+
+    #[test]
+    fn iteration_numbers_come_from_the_file_name() {
+        assert_eq!(
+            iteration_number(Path::new("logs/iteration_12.log")),
+            Some(12)
+        );
+        assert_eq!(iteration_number(Path::new("logs/iteration_x.log")), None);
+        assert_eq!(iteration_number(Path::new("logs/gate.log")), None);
+    }
+}
@@ -0,0 +1,318 @@
+//! Spec requirements and their traceability to plan tasks.
+//!
+//! A requirement in `ralph/specs/*.md` is a list item or heading that starts
+//! with a bracketed ID, e.g. `- [AUDIT-3] Annotations store a bounding box`.
+//! Plan tasks name the requirements they implement on a `covers: AUDIT-3`
+//! line, which ties every requirement to the tasks that satisfy it.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::error::{Error, Result};
+use crate::plan::{Plan, Status, Task};
+
+/// Default directory of the spec files, relative to the project root.
+pub const DEFAULT_SPECS_DIR: &str = "ralph/specs";
+
+/// Whether `token` looks like a requirement ID: uppercase words joined by
+/// dashes, ending in a number (`AUDIT-3`, `AUDIT-UI-12`).
+pub fn is_requirement_id(token: &str) -> bool {
+    let parts: Vec<&str> = token.split('-').collect();
+    parts.len() >= 2
+        && parts[0].starts_with(|c: char| c.is_ascii_uppercase())
+        && parts.iter().all(|p| {
+            !p.is_empty()
+                && p.chars()
+                    .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+        })
+        && parts[parts.len() - 1].chars().all(|c| c.is_ascii_digit())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Requirement {
+    pub id: String,
+    pub text: String,
+    pub file: PathBuf,
+    /// One-based line in `file`.
+    pub line: usize,
+}
+
+/// Requirements of every `*.md` file in `dir`, by file name then line. A
+/// project without a specs directory has no requirements.
+pub fn load(dir: &Path) -> Result<Vec<Requirement>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(Error::io(dir, e)),
+    };
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "md"))
+        .collect();
+    files.sort();
+
+    let mut requirements = Vec::new();
+    for file in files {
+        let text = fs::read_to_string(&file).map_err(|e| Error::io(&file, e))?;
+        requirements.extend(parse(&text, &file));
+    }
+    Ok(requirements)
+}
+
+/// Requirements defined in one spec file, skipping fenced code blocks.
+pub fn parse(text: &str, file: &Path) -> Vec<Requirement> {
+    let mut in_fence = false;
+    let mut requirements = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+        if let Some((id, text)) = requirement(trimmed) {
+            requirements.push(Requirement {
+                id: id.to_string(),
+                text: text.to_string(),
+                file: file.to_path_buf(),
+                line: i + 1,
+            });
+        }
+    }
+    requirements
+}
+
+/// ID and text of a `- [ID] text` item or `### [ID] text` heading.
+fn requirement(line: &str) -> Option<(&str, &str)> {
+    let body = if line.starts_with('#') {
+        line.trim_start_matches('#')
+    } else if let Some(rest) = ["- ", "* ", "+ "].iter().find_map(|m| line.strip_prefix(m)) {
+        rest
+    } else {
+        let digits = line.find(|c: char| !c.is_ascii_digit())?;
+        line[digits..].strip_prefix(". ").filter(|_| digits > 0)?
+    };
+    let body = body.trim_start();
+    let body = body.strip_prefix("**").unwrap_or(body);
+    let (id, text) = body.strip_prefix('[')?.split_once(']')?;
+    let text = text.trim_start_matches("**").trim();
+    let text = text.trim_start_matches([':', '-']).trim();
+    is_requirement_id(id).then_some((id, text))
+}
+
+/// How well a requirement is covered by plan tasks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Coverage {
+    /// Every covering task is done.
+    Done,
+    /// Some covering tasks are done.
+    Partial,
+    /// Covered only by tasks that are not done yet.
+    Open,
+    /// No task covers it.
+    Missing,
+}
+
+impl Coverage {
+    pub fn name(self) -> &'static str {
+        match self {
+            Coverage::Done => "done",
+            Coverage::Partial => "partial",
+            Coverage::Open => "open",
+            Coverage::Missing => "missing",
+        }
+    }
+}
+
+/// A task in the matrix, by label and status.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TaskRef {
+    pub task: String,
+    pub title: String,
+    pub status: Status,
+}
+
+impl TaskRef {
+    fn of(task: &Task) -> TaskRef {
+        TaskRef {
+            task: task.label().to_string(),
+            title: task.title.clone(),
+            status: task.status,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Row {
+    #[serde(flatten)]
+    pub requirement: Requirement,
+    pub tasks: Vec<TaskRef>,
+    pub coverage: Coverage,
+}
+
+/// A `covers:` entry naming a requirement that no spec defines.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UnknownRef {
+    pub task: String,
+    pub requirement: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Matrix {
+    pub rows: Vec<Row>,
+    /// Tasks that cover no requirement.
+    pub untraced: Vec<TaskRef>,
+    pub unknown: Vec<UnknownRef>,
+    /// Requirement IDs defined more than once.
+    pub duplicates: Vec<String>,
+}
+
+impl Matrix {
+    pub fn build(requirements: &[Requirement], plan: &Plan) -> Matrix {
+        let rows = requirements
+            .iter()
+            .map(|requirement| {
+                let tasks: Vec<TaskRef> = plan
+                    .tasks()
+                    .filter(|t| t.covers.contains(&requirement.id))
+                    .map(TaskRef::of)
+                    .collect();
+                let done = tasks.iter().filter(|t| t.status == Status::Done).count();
+                let coverage = match done {
+                    _ if tasks.is_empty() => Coverage::Missing,
+                    0 => Coverage::Open,
+                    n if n == tasks.len() => Coverage::Done,
+                    _ => Coverage::Partial,
+                };
+                Row {
+                    requirement: requirement.clone(),
+                    tasks,
+                    coverage,
+                }
+            })
+            .collect();
+
+        let untraced = plan
+            .tasks()
+            .filter(|t| t.covers.is_empty())
+            .map(TaskRef::of)
+            .collect();
+        let unknown = plan
+            .tasks()
+            .flat_map(|task| {
+                task.covers
+                    .iter()
+                    .filter(|id| !requirements.iter().any(|r| &r.id == *id))
+                    .map(|id| UnknownRef {
+                        task: task.label().to_string(),
+                        requirement: id.clone(),
+                    })
+            })
+            .collect();
+        let mut duplicates: Vec<String> = Vec::new();
+        for (i, requirement) in requirements.iter().enumerate() {
+            if requirements[..i].iter().any(|r| r.id == requirement.id)
+                && !duplicates.contains(&requirement.id)
+            {
+                duplicates.push(requirement.id.clone());
+            }
+        }
+
+        Matrix {
+            rows,
+            untraced,
+            unknown,
+            duplicates,
+        }
+    }
+
+    pub fn with_coverage(&self, coverage: Coverage) -> impl Iterator<Item = &Row> {
+        self.rows.iter().filter(move |r| r.coverage == coverage)
+    }
+
+    /// Every requirement has a task, and every reference and ID is sound.
+    pub fn is_complete(&self) -> bool {
+        self.with_coverage(Coverage::Missing).next().is_none()
+            && self.unknown.is_empty()
+            && self.duplicates.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn requirements_are_items_and_headings_outside_fences() {
+        let text = "# Audit\n\n- [AUDIT-1] Upload images\n### [AUDIT-2]: Tag images\n\
+                    1. **[AUDIT-UI-3]** Show tags\n- [ ] not a requirement\n\
+                    ```\n- [AUDIT-4] example\n```\n";
+        let file = Path::new("ralph/specs/audit.md");
+        let requirements = parse(text, file);
+        let found: Vec<(&str, &str, usize)> = requirements
+            .iter()
+            .map(|r| (r.id.as_str(), r.text.as_str(), r.line))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("AUDIT-1", "Upload images", 3),
+                ("AUDIT-2", "Tag images", 4),
+                ("AUDIT-UI-3", "Show tags", 5),
+            ]
+        );
+        assert!(!is_requirement_id("audit-1"));
+        assert!(!is_requirement_id("AUDIT"));
+        assert!(!is_requirement_id("AUDIT-1a"));
+    }
+
+    #[test]
+    fn missing_specs_dir_has_no_requirements() {
+        let dir = TempDir::new("specs");
+        assert!(load(&dir.join("specs")).unwrap().is_empty());
+        dir.write("specs/b.md", "- [B-1] Second\n");
+        dir.write("specs/a.md", "- [A-1] First\n");
+        dir.write("specs/notes.txt", "- [C-1] Ignored\n");
+        let ids: Vec<String> = load(&dir.join("specs"))
+            .unwrap()
+            .into_iter()
+            .map(|r| r.id)
+            .collect();
+        assert_eq!(ids, ["A-1", "B-1"]);
+    }
+
+    #[test]
+    fn matrix_traces_requirements_to_tasks() {
+        let file = Path::new("spec.md");
+        let requirements = parse(
+            "- [R-1] Done\n- [R-2] Partly\n- [R-3] Open\n- [R-4] Missing\n- [R-1] Again\n",
+            file,
+        );
+        let plan = Plan::parse(
+            "## Phase 1: A\n\n### Tasks\n- [x] 1.1 One\n  covers: R-1, R-2\n\
+             - [ ] 1.2 Two\n  covers: R-2, R-3, R-9\n- [ ] 1.3 Three\n",
+        );
+        let matrix = Matrix::build(&requirements, &plan);
+        let coverage: Vec<Coverage> = matrix.rows.iter().map(|r| r.coverage).collect();
+        assert_eq!(
+            coverage,
+            [
+                Coverage::Done,
+                Coverage::Partial,
+                Coverage::Open,
+                Coverage::Missing,
+                Coverage::Done
+            ]
+        );
+        assert_eq!(matrix.untraced.len(), 1);
+        assert_eq!(matrix.unknown[0].requirement, "R-9");
+        assert_eq!(matrix.duplicates, ["R-1"]);
+        assert!(!matrix.is_complete());
+    }
+}
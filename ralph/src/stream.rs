@@ -0,0 +1,321 @@
+//! Typed decoder for `claude --output-format=stream-json --verbose` logs.
+//!
+//! Each line of `logs/iteration_N.log` is one JSON message from claude, a
+//! record appended by the loop (`"type": "ralph"`), or stray stderr text.
+//! Assistant and user messages carry several content blocks; they are
+//! flattened into one event per block so callers can simply iterate.
+//!
+//! A log cut off mid-write (killed iteration, full disk) ends with a partial
+//! JSON line; that line decodes to [`Event::Truncated`] instead of an error.
+
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::error::{Error, Result};
+use crate::record::RECORD_TYPE;
+
+/// The `system`/`init` message that opens every session.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SystemInit {
+    pub session_id: Option<String>,
+    pub model: Option<String>,
+    pub cwd: Option<String>,
+    pub tools: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolUse {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub input: Value,
+}
+
+impl ToolUse {
+    /// String field of the tool input, e.g. `command` for Bash or
+    /// `file_path` for Edit.
+    pub fn input_str(&self, key: &str) -> Option<&str> {
+        self.input.get(key).and_then(Value::as_str)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ToolResult {
+    pub tool_use_id: String,
+    /// Text of the result, with multi-part content joined by newlines.
+    pub content: String,
+    pub is_error: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Usage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_creation_input_tokens: u64,
+    pub cache_read_input_tokens: u64,
+}
+
+impl Usage {
+    pub fn total(&self) -> u64 {
+        self.input_tokens
+            + self.output_tokens
+            + self.cache_creation_input_tokens
+            + self.cache_read_input_tokens
+    }
+}
+
+/// The final `result` message of a session.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ResultEvent {
+    pub subtype: Option<String>,
+    pub is_error: bool,
+    pub duration_ms: Option<u64>,
+    pub duration_api_ms: Option<u64>,
+    pub num_turns: Option<u32>,
+    pub total_cost_usd: Option<f64>,
+    pub usage: Option<Usage>,
+    pub result: Option<String>,
+    pub session_id: Option<String>,
+}
+
+/// A record appended by the loop itself (see [`crate::record`]).
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Record {
+    pub event: String,
+    pub fields: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Event {
+    SystemInit(SystemInit),
+    AssistantText {
+        text: String,
+    },
+    ToolUse(ToolUse),
+    ToolResult(ToolResult),
+    Result(ResultEvent),
+    Record(Record),
+    /// Valid JSON of a type this decoder does not model.
+    Other {
+        value: Value,
+    },
+    /// A line that is not JSON, typically stderr merged into the log.
+    Raw {
+        text: String,
+    },
+    /// A partial JSON line: the last line of a log cut off mid-write, or a
+    /// killed child's last output with the loop's records after it.
+    Truncated {
+        text: String,
+    },
+}
+
+/// Content block of an assistant or user message, as it appears on the wire.
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Block {
+    Text {
+        text: String,
+    },
+    ToolUse(ToolUse),
+    ToolResult {
+        tool_use_id: String,
+        #[serde(default)]
+        content: Value,
+        #[serde(default)]
+        is_error: bool,
+    },
+    #[serde(other)]
+    Other,
+}
+
+/// Decode a whole log.
+pub fn decode(text: &str) -> Vec<Event> {
+    let complete = text.ends_with('\n');
+    let lines: Vec<&str> = text.lines().filter(|l| !l.trim().is_empty()).collect();
+
+    let mut events = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        let cut_off = match lines.get(i + 1) {
+            None => !complete,
+            Some(next) => is_record(next),
+        };
+        match serde_json::from_str::<Value>(line) {
+            Ok(value) => decode_value(value, &mut events),
+            Err(_) if cut_off && line.trim_start().starts_with('{') => {
+                events.push(Event::Truncated {
+                    text: line.to_string(),
+                })
+            }
+            Err(_) => events.push(Event::Raw {
+                text: line.to_string(),
+            }),
+        }
+    }
+    events
+}
+
+/// Whether `line` is a record written by the loop.
+fn is_record(line: &str) -> bool {
+    serde_json::from_str::<Value>(line).is_ok_and(|value| value["type"] == RECORD_TYPE)
+}
+
+/// Read and decode a log file. Invalid UTF-8 is replaced rather than fatal.
+pub fn read(path: &Path) -> Result<Vec<Event>> {
+    let bytes = fs::read(path).map_err(|e| Error::io(path, e))?;
+    Ok(decode(&String::from_utf8_lossy(&bytes)))
+}
+
+fn decode_value(value: Value, events: &mut Vec<Event>) {
+    let kind = value["type"].as_str().unwrap_or_default().to_string();
+    match kind.as_str() {
+        "system" if value["subtype"] == "init" => match serde_json::from_value(value.clone()) {
+            Ok(init) => events.push(Event::SystemInit(init)),
+            Err(_) => events.push(Event::Other { value }),
+        },
+        "assistant" | "user" => {
+            let blocks = match &value["message"]["content"] {
+                Value::Array(blocks) => blocks.clone(),
+                Value::String(text) => vec![serde_json::json!({ "type": "text", "text": text })],
+                _ => Vec::new(),
+            };
+            for block in blocks {
+                match serde_json::from_value(block) {
+                    // User text is the prompt echoed back, not model output.
+                    Ok(Block::Text { text }) if kind == "assistant" => {
+                        events.push(Event::AssistantText { text })
+                    }
+                    Ok(Block::ToolUse(tool_use)) => events.push(Event::ToolUse(tool_use)),
+                    Ok(Block::ToolResult {
+                        tool_use_id,
+                        content,
+                        is_error,
+                    }) => events.push(Event::ToolResult(ToolResult {
+                        tool_use_id,
+                        content: content_text(&content),
+                        is_error,
+                    })),
+                    _ => {}
+                }
+            }
+        }
+        "result" => match serde_json::from_value(value.clone()) {
+            Ok(result) => events.push(Event::Result(result)),
+            Err(_) => events.push(Event::Other { value }),
+        },
+        RECORD_TYPE => {
+            let Value::Object(mut fields) = value else {
+                unreachable!("a value with a `type` key is an object")
+            };
+            fields.remove("type");
+            let event = match fields.remove("event") {
+                Some(Value::String(event)) => event,
+                _ => String::new(),
+            };
+            events.push(Event::Record(Record { event, fields }));
+        }
+        _ => events.push(Event::Other { value }),
+    }
+}
+
+/// Flatten tool result content, which is either a string or a list of parts.
+fn content_text(content: &Value) -> String {
+    match content {
+        Value::String(text) => text.clone(),
+        Value::Array(parts) => parts
+            .iter()
+            .filter_map(|part| part["text"].as_str())
+            .collect::<Vec<_>>()
+            .join("\n"),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_messages_into_events() {
+        let log = concat!(
+            r#"{"type":"system","subtype":"init","session_id":"s1","model":"opus","tools":["Bash"]}"#,
+            "\n",
+            r#"{"type":"assistant","message":{"content":[{"type":"text","text":"Running tests"},{"type":"tool_use","id":"t1","name":"Bash","input":{"command":"pytest"}}]}}"#,
+            "\n",
+            r#"{"type":"user","message":{"content":[{"type":"tool_result","tool_use_id":"t1","content":[{"type":"text","text":"1 passed"},{"type":"text","text":"ok"}]}]}}"#,
+            "\n",
+            "warning: not json\n",
+            "\n",
+            r#"{"type":"result","is_error":false,"num_turns":2,"total_cost_usd":0.5,"usage":{"input_tokens":10,"output_tokens":5}}"#,
+            "\n",
+            r#"{"type":"ralph","event":"iteration_end","exit_code":0}"#,
+            "\n",
+        );
+        let events = decode(log);
+        assert_eq!(events.len(), 7);
+        assert!(matches!(&events[0], Event::SystemInit(init) if init.tools == ["Bash"]));
+        assert_eq!(
+            events[1],
+            Event::AssistantText {
+                text: "Running tests".to_string()
+            }
+        );
+        assert!(
+            matches!(&events[2], Event::ToolUse(t) if t.input_str("command") == Some("pytest"))
+        );
+        assert!(matches!(&events[3], Event::ToolResult(r) if r.content == "1 passed\nok"));
+        assert!(matches!(&events[4], Event::Raw { text } if text == "warning: not json"));
+        match &events[5] {
+            Event::Result(result) => {
+                assert_eq!(result.num_turns, Some(2));
+                assert_eq!(result.usage.unwrap().total(), 15);
+            }
+            other => panic!("expected a result, got {other:?}"),
+        }
+        match &events[6] {
+            Event::Record(record) => {
+                assert_eq!(record.event, "iteration_end");
+                assert_eq!(record.fields["exit_code"], 0);
+                assert!(!record.fields.contains_key("type"));
+            }
+            other => panic!("expected a record, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn partial_last_line_is_truncated() {
+        let log = "{\"type\":\"result\"}\n{\"type\":\"assis";
+        assert!(matches!(
+            decode(log).last(),
+            Some(Event::Truncated { text }) if text == "{\"type\":\"assis"
+        ));
+
+        // The same text with a newline after it is a complete, invalid line.
+        let log = "{\"type\":\"assis\n";
+        assert!(matches!(decode(log)[0], Event::Raw { .. }));
+    }
+
+    #[test]
+    fn partial_line_before_a_record_is_truncated() {
+        let log = concat!(
+            "{\"type\":\"assistant\",\"mess\n",
+            r#"{"type":"ralph","event":"stall","idle_secs":600}"#,
+            "\n",
+        );
+        let events = decode(log);
+        assert!(matches!(&events[0], Event::Truncated { .. }));
+        assert!(matches!(&events[1], Event::Record(r) if r.event == "stall"));
+
+        // Followed by anything else, it is just a bad line.
+        let log = "{\"type\":\"assistant\",\"mess\nplain text\n";
+        assert!(matches!(decode(log)[0], Event::Raw { .. }));
+    }
+}
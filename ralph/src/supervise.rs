@@ -0,0 +1,349 @@
+//! Run one iteration's child process under a wall-clock timeout and a stall
+//! detector.
+//!
+//! The child is started in its own process group so that, when a limit is
+//! hit, the whole tree (claude and any tools it spawned) can be signalled at
+//! once. Its stdout and stderr are merged line by line, echoed to our stdout
+//! and written to the iteration log, mirroring the old `2>&1 | tee`. The
+//! iteration ends shortly after the child exits, even when something it
+//! started in the background still holds the pipes.
+
+use std::fs::OpenOptions;
+use std::io::{self, Read, Write};
+use std::os::unix::process::{CommandExt, ExitStatusExt};
+use std::path::Path;
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde_json::json;
+
+use crate::error::{Error, Result};
+use crate::record;
+
+/// Exit code reported when the wall-clock timeout was hit.
+pub const EXIT_TIMEOUT: u8 = 124;
+/// Exit code reported when the child stopped producing output.
+pub const EXIT_STALL: u8 = 125;
+
+/// How long the process group gets to exit after SIGTERM before SIGKILL.
+const KILL_GRACE: Duration = Duration::from_secs(5);
+/// Upper bound on how long we block without checking for signals.
+const TICK: Duration = Duration::from_secs(1);
+/// How long output is still collected after the child exited. A process it
+/// left running in the background may hold the pipes open indefinitely.
+const DRAIN: Duration = Duration::from_secs(1);
+
+/// Signal received by the supervisor itself (0 when none).
+static RECEIVED_SIGNAL: AtomicI32 = AtomicI32::new(0);
+
+extern "C" fn on_signal(signal: libc::c_int) {
+    RECEIVED_SIGNAL.store(signal, Ordering::SeqCst);
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Limits {
+    /// Maximum wall-clock time for the whole iteration.
+    pub timeout: Option<Duration>,
+    /// Maximum time without any output from the child.
+    pub stall: Option<Duration>,
+}
+
+/// How a supervised iteration ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// The child exited on its own.
+    Exited(ExitStatus),
+    /// The wall-clock timeout elapsed and the process group was killed.
+    TimedOut { elapsed: Duration },
+    /// No output arrived for the stall limit and the process group was killed.
+    Stalled { idle: Duration },
+    /// The supervisor was interrupted and forwarded the signal to the group.
+    Interrupted { signal: i32 },
+}
+
+impl Outcome {
+    /// Exit code for the supervisor process, following `timeout(1)` for the
+    /// timeout case and the shell's `128 + signal` convention for signals.
+    pub fn exit_code(self) -> u8 {
+        match self {
+            Outcome::Exited(status) => match (status.code(), status.signal()) {
+                (Some(code), _) => code as u8,
+                (None, Some(signal)) => 128u8.wrapping_add(signal as u8),
+                (None, None) => 1,
+            },
+            Outcome::TimedOut { .. } => EXIT_TIMEOUT,
+            Outcome::Stalled { .. } => EXIT_STALL,
+            Outcome::Interrupted { signal } => 128u8.wrapping_add(signal as u8),
+        }
+    }
+}
+
+/// Run `command`, streaming its merged output to stdout and appending it
+/// to `log`.
+pub fn run(command: &[String], limits: Limits, log: &Path) -> Result<Outcome> {
+    let (program, args) = command
+        .split_first()
+        .expect("clap requires a command to supervise");
+    let mut log_file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(log)
+        .map_err(|e| Error::io(log, e))?;
+
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::inherit())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .process_group(0)
+        .spawn()
+        .map_err(|e| Error::io(program, e))?;
+
+    let handler = on_signal as extern "C" fn(libc::c_int) as libc::sighandler_t;
+    // SAFETY: the handler only stores into an atomic, which is async-signal-safe.
+    unsafe {
+        libc::signal(libc::SIGINT, handler);
+        libc::signal(libc::SIGTERM, handler);
+    }
+
+    let (tx, rx) = mpsc::channel::<Vec<u8>>();
+    forward_lines(child.stdout.take().expect("stdout is piped"), tx.clone());
+    forward_lines(child.stderr.take().expect("stderr is piped"), tx);
+
+    let started = Instant::now();
+    let mut last_output = started;
+    // Exit status of the child and when it was seen, once it has exited.
+    let mut exited: Option<(ExitStatus, Instant)> = None;
+    let mut stdout = io::stdout();
+    // Whether the log ends with a complete line, so records start on their own.
+    let mut at_line_start = true;
+
+    let outcome = loop {
+        let signal = RECEIVED_SIGNAL.load(Ordering::SeqCst);
+        if signal != 0 {
+            kill_group(&mut child, signal);
+            break Outcome::Interrupted { signal };
+        }
+
+        let now = Instant::now();
+        let deadlines = match exited {
+            Some((status, at)) if now - at >= DRAIN => break Outcome::Exited(status),
+            Some((_, at)) => [Some(at + DRAIN), None],
+            None => {
+                if let Some(status) = child.try_wait().map_err(|e| Error::io(program, e))? {
+                    exited = Some((status, now));
+                    continue;
+                }
+                if let Some(timeout) = limits.timeout {
+                    if now - started >= timeout {
+                        kill_group(&mut child, libc::SIGTERM);
+                        break Outcome::TimedOut {
+                            elapsed: now - started,
+                        };
+                    }
+                }
+                if let Some(stall) = limits.stall {
+                    if now - last_output >= stall {
+                        kill_group(&mut child, libc::SIGTERM);
+                        break Outcome::Stalled {
+                            idle: now - last_output,
+                        };
+                    }
+                }
+                [
+                    limits.timeout.map(|t| started + t),
+                    limits.stall.map(|s| last_output + s),
+                ]
+            }
+        };
+        let wait = deadlines
+            .into_iter()
+            .flatten()
+            .map(|deadline| deadline.saturating_duration_since(now))
+            .fold(TICK, Duration::min);
+
+        match rx.recv_timeout(wait) {
+            // An empty chunk is output that has not completed a line yet.
+            Ok(chunk) if chunk.is_empty() => last_output = Instant::now(),
+            Ok(chunk) => {
+                last_output = Instant::now();
+                // The terminal going away must not take the log down with it.
+                let _ = stdout.write_all(&chunk).and_then(|()| stdout.flush());
+                log_file.write_all(&chunk).map_err(|e| Error::io(log, e))?;
+                at_line_start = chunk.ends_with(b"\n");
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => {
+                let status = match exited {
+                    Some((status, _)) => status,
+                    None => child.wait().map_err(|e| Error::io(program, e))?,
+                };
+                break Outcome::Exited(status);
+            }
+        }
+    };
+
+    // Keep whatever the killed tree flushed on its way out, but don't wait on
+    // a stray process outside the group that still holds the pipes open.
+    if !matches!(outcome, Outcome::Exited(_)) {
+        let deadline = Instant::now() + TICK;
+        while let Ok(chunk) = rx.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+            log_file.write_all(&chunk).map_err(|e| Error::io(log, e))?;
+            at_line_start = chunk.ends_with(b"\n");
+        }
+    }
+
+    if let Some(line) = reason_line(outcome, limits) {
+        // A killed child may have left half a line; keep the record off it.
+        if !at_line_start {
+            log_file.write_all(b"\n").map_err(|e| Error::io(log, e))?;
+        }
+        writeln!(log_file, "{line}").map_err(|e| Error::io(log, e))?;
+        eprintln!("ralph: {line}");
+    }
+    Ok(outcome)
+}
+
+/// Send the complete lines read from `pipe`, so the two pipes interleave
+/// only at line breaks. Every read sends something, an empty chunk when it
+/// did not complete a line, because any output counts against a stall.
+fn forward_lines<R: Read + Send + 'static>(mut pipe: R, tx: mpsc::Sender<Vec<u8>>) {
+    thread::spawn(move || {
+        let mut buf = [0; 8192];
+        let mut pending = Vec::new();
+        loop {
+            let n = match pipe.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(_) => break,
+            };
+            pending.extend_from_slice(&buf[..n]);
+            let lines = match pending.iter().rposition(|&b| b == b'\n') {
+                Some(end) => pending.drain(..=end).collect(),
+                None => Vec::new(),
+            };
+            if tx.send(lines).is_err() {
+                return;
+            }
+        }
+        if !pending.is_empty() {
+            let _ = tx.send(pending);
+        }
+    });
+}
+
+/// Signal the child's process group, escalating to SIGKILL after a grace
+/// period, and reap the child.
+fn kill_group(child: &mut Child, signal: i32) {
+    let group = -(child.id() as libc::pid_t);
+    // SAFETY: plain kill(2) on the process group we created at spawn.
+    unsafe { libc::kill(group, signal) };
+
+    let deadline = Instant::now() + KILL_GRACE;
+    while Instant::now() < deadline {
+        if let Ok(Some(_)) = child.try_wait() {
+            // The leader is gone; make sure stragglers in the group are too.
+            unsafe { libc::kill(group, libc::SIGKILL) };
+            return;
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+    unsafe { libc::kill(group, libc::SIGKILL) };
+    let _ = child.wait();
+}
+
+/// The record explaining why the supervisor ended the iteration.
+fn reason_line(outcome: Outcome, limits: Limits) -> Option<String> {
+    let line = match outcome {
+        Outcome::Exited(_) => return None,
+        Outcome::TimedOut { elapsed } => record::line(
+            "timeout",
+            json!({
+                "limit_secs": limits.timeout.map(|d| d.as_secs()),
+                "elapsed_secs": elapsed.as_secs(),
+            }),
+        ),
+        Outcome::Stalled { idle } => record::line(
+            "stall",
+            json!({
+                "limit_secs": limits.stall.map(|d| d.as_secs()),
+                "idle_secs": idle.as_secs(),
+            }),
+        ),
+        Outcome::Interrupted { signal } => record::line("interrupted", json!({ "signal": signal })),
+    };
+    Some(line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    fn sh(script: &str) -> Vec<String> {
+        ["sh", "-c", script].map(str::to_string).to_vec()
+    }
+
+    #[test]
+    fn exit_codes_and_output_are_kept() {
+        let dir = TempDir::new("supervise");
+        let log = dir.join("iteration_1.log");
+        let outcome = run(
+            &sh("echo out; echo err >&2; exit 3"),
+            Limits::default(),
+            &log,
+        )
+        .unwrap();
+        assert_eq!(outcome.exit_code(), 3);
+        let text = std::fs::read_to_string(&log).unwrap();
+        assert!(text.contains("out\n") && text.contains("err\n"));
+    }
+
+    #[test]
+    fn output_without_a_newline_is_not_a_stall() {
+        let dir = TempDir::new("supervise");
+        let log = dir.join("iteration_1.log");
+        let limits = Limits {
+            timeout: None,
+            stall: Some(Duration::from_millis(1500)),
+        };
+        let script = "for i in 1 2 3 4; do printf .; sleep 0.5; done";
+        let outcome = run(&sh(script), limits, &log).unwrap();
+        assert!(matches!(outcome, Outcome::Exited(_)), "{outcome:?}");
+        assert_eq!(std::fs::read_to_string(&log).unwrap(), "....");
+    }
+
+    #[test]
+    fn background_processes_do_not_hold_the_iteration() {
+        let dir = TempDir::new("supervise");
+        let log = dir.join("iteration_1.log");
+        let limits = Limits {
+            timeout: Some(Duration::from_secs(10)),
+            stall: Some(Duration::from_secs(5)),
+        };
+        let started = Instant::now();
+        let outcome = run(&sh("sleep 20 & echo started"), limits, &log).unwrap();
+        assert_eq!(outcome.exit_code(), 0);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn timeouts_kill_the_group_and_are_recorded() {
+        let dir = TempDir::new("supervise");
+        let log = dir.join("iteration_1.log");
+        let limits = Limits {
+            timeout: Some(Duration::from_millis(300)),
+            stall: None,
+        };
+        let outcome = run(&sh("printf partial; sleep 20"), limits, &log).unwrap();
+        assert!(matches!(outcome, Outcome::TimedOut { .. }), "{outcome:?}");
+        assert_eq!(outcome.exit_code(), EXIT_TIMEOUT);
+        let text = std::fs::read_to_string(&log).unwrap();
+        assert!(text.starts_with("partial\n{"), "{text}");
+        assert!(text.contains(r#""event":"timeout""#), "{text}");
+    }
+}
@@ -0,0 +1,72 @@
+//! UTC formatting for the unix timestamps stored in loop records.
+
+/// `YYYY-MM-DD` for a unix timestamp.
+pub fn date(secs: u64) -> String {
+    let (y, m, d) = civil_from_days((secs / 86_400) as i64);
+    format!("{y:04}-{m:02}-{d:02}")
+}
+
+/// `YYYY-MM-DD HH:MM:SS` for a unix timestamp.
+pub fn datetime(secs: u64) -> String {
+    let of_day = secs % 86_400;
+    format!(
+        "{} {:02}:{:02}:{:02}",
+        date(secs),
+        of_day / 3600,
+        of_day % 3600 / 60,
+        of_day % 60
+    )
+}
+
+/// Compact duration such as `45s`, `12m05s` or `2h03m`.
+pub fn duration(secs: u64) -> String {
+    match secs {
+        s if s < 60 => format!("{s}s"),
+        s if s < 3600 => format!("{}m{:02}s", s / 60, s % 60),
+        s => format!("{}h{:02}m", s / 3600, s % 3600 / 60),
+    }
+}
+
+/// Proleptic Gregorian date for a count of days since 1970-01-01
+/// (Howard Hinnant's `civil_from_days`).
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: u64 = 86_400;
+
+    #[test]
+    fn dates_cross_leap_days() {
+        assert_eq!(date(0), "1970-01-01");
+        // 2024-02-28 is day 19781 since the epoch.
+        assert_eq!(date(19_781 * DAY), "2024-02-28");
+        assert_eq!(date(19_782 * DAY), "2024-02-29");
+        assert_eq!(date(19_783 * DAY), "2024-03-01");
+        // 2100 is not a leap year: 02-28 is followed by 03-01.
+        assert_eq!(date(47_540 * DAY), "2100-02-28");
+        assert_eq!(date(47_541 * DAY), "2100-03-01");
+        assert_eq!(date(19_723 * DAY - 1), "2023-12-31");
+        assert_eq!(date(19_723 * DAY), "2024-01-01");
+    }
+
+    #[test]
+    fn times_and_durations() {
+        assert_eq!(datetime(19_782 * DAY + 3_723), "2024-02-29 01:02:03");
+        assert_eq!(duration(45), "45s");
+        assert_eq!(duration(725), "12m05s");
+        assert_eq!(duration(7_380), "2h03m");
+    }
+}
@@ -0,0 +1,310 @@
+//! Scratch worktrees that isolate an iteration from the working branch.
+//!
+//! With `[worktree] enabled`, every iteration runs in a fresh `git worktree`
+//! on a scratch branch cut from the working branch. Afterwards the scratch
+//! branch is merged back only when the iteration passed its tests and moved a
+//! plan task to `[x]`; otherwise the worktree and branch are thrown away, so a
+//! bad iteration never lands on the working branch.
+//!
+//! When the loop assigned the iteration a task, any work with passing tests
+//! lands instead, ticked or not: the loop marks that task itself afterwards
+//! (`ralph task finish`), so the model is not required to.
+
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::os::fd::AsRawFd;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::git;
+use crate::plan::{Status, Transition};
+use crate::report::IterationSummary;
+
+/// Prefix of every scratch branch, so leftovers can be found and deleted.
+pub const BRANCH_PREFIX: &str = "ralph-scratch/";
+
+/// Exit code of `ralph worktree finish` when the iteration was discarded.
+pub const EXIT_DISCARDED: u8 = 7;
+
+/// How a successful scratch branch lands on the working branch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Merge {
+    /// Keep the iteration's commits as they are (`git merge --ff-only`).
+    Ff,
+    /// Collapse the iteration into one commit (`git merge --squash`).
+    Squash,
+}
+
+impl fmt::Display for Merge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Merge::Ff => "ff",
+            Merge::Squash => "squash",
+        })
+    }
+}
+
+/// Default parent directory of scratch worktrees: inside the git directory,
+/// where they are invisible to `git status` of the main checkout.
+pub fn default_dir() -> Result<PathBuf> {
+    let common = git::run(&["rev-parse", "--path-format=absolute", "--git-common-dir"])?;
+    Ok(Path::new(&common).join("ralph-worktrees"))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Worktree {
+    pub path: PathBuf,
+    pub branch: String,
+}
+
+impl Worktree {
+    /// The worktree of `name` under `dir`, whether or not it exists yet.
+    pub fn named(dir: &Path, name: &str) -> Worktree {
+        Worktree {
+            path: dir.join(name),
+            branch: format!("{BRANCH_PREFIX}{name}"),
+        }
+    }
+
+    /// Check out a new scratch branch at `base` into the worktree.
+    pub fn create(&self, base: &str) -> Result<()> {
+        git::run(&[
+            "worktree",
+            "add",
+            "--quiet",
+            "-b",
+            &self.branch,
+            &self.path.to_string_lossy(),
+            base,
+        ])
+        .map(drop)
+    }
+
+    /// Commit anything the iteration left uncommitted. Returns whether there
+    /// was anything to commit.
+    pub fn commit_leftovers(&self, message: &str) -> Result<bool> {
+        let path = self.path.to_string_lossy();
+        if git::run(&["-C", &path, "status", "--porcelain"])?.is_empty() {
+            return Ok(false);
+        }
+        git::run(&["-C", &path, "add", "-A"])?;
+        git::run(&["-C", &path, "commit", "--quiet", "-m", message])?;
+        Ok(true)
+    }
+
+    /// Whether the iteration left commits `into` does not have, or
+    /// uncommitted changes.
+    pub fn has_work(&self, into: &str) -> Result<bool> {
+        let path = self.path.to_string_lossy();
+        Ok(self.commits_ahead(into)? > 0
+            || !git::run(&["-C", &path, "status", "--porcelain"])?.is_empty())
+    }
+
+    /// Commits on the scratch branch that `into` does not have.
+    pub fn commits_ahead(&self, into: &str) -> Result<usize> {
+        git::commits_between(into, &self.branch)
+    }
+
+    /// Merge the scratch branch into `into`, the branch checked out in the
+    /// current directory. A scratch branch that fell behind `into` (another
+    /// worker merged first) is rebased before a fast-forward. A failed merge
+    /// is rolled back so both checkouts stay clean.
+    pub fn merge(&self, merge: Merge, into: &str, message: &str) -> Result<()> {
+        match merge {
+            Merge::Ff => {
+                let behind =
+                    git::run(&["merge-base", "--is-ancestor", into, &self.branch]).is_err();
+                if behind {
+                    let path = self.path.to_string_lossy();
+                    git::run(&["-C", &path, "rebase", "--quiet", into]).inspect_err(|_| {
+                        let _ = git::run(&["-C", &path, "rebase", "--abort"]);
+                    })?;
+                }
+                git::run(&["merge", "--quiet", "--ff-only", &self.branch]).map(drop)
+            }
+            Merge::Squash => git::run(&["merge", "--quiet", "--squash", &self.branch])
+                .and_then(|_| git::run(&["commit", "--quiet", "-m", message]))
+                .map(drop)
+                .inspect_err(|_| {
+                    let _ = git::run(&["reset", "--quiet", "--merge"]);
+                }),
+        }
+    }
+
+    /// Delete the worktree and its scratch branch, ignoring whichever of the
+    /// two is already gone.
+    pub fn remove(&self) -> Result<()> {
+        if self.path.exists() {
+            git::run(&[
+                "worktree",
+                "remove",
+                "--force",
+                &self.path.to_string_lossy(),
+            ])?;
+        }
+        git::run(&["worktree", "prune"])?;
+        if git::rev_parse(&format!("refs/heads/{}", self.branch))?.is_some() {
+            git::run(&["branch", "--quiet", "-D", &self.branch])?;
+        }
+        Ok(())
+    }
+}
+
+/// Hold an exclusive lock on `dir/merge.lock` until the returned file is
+/// dropped, so merges from parallel workers happen one at a time.
+pub fn lock(dir: &Path) -> Result<File> {
+    fs::create_dir_all(dir).map_err(|e| Error::io(dir, e))?;
+    let path = dir.join("merge.lock");
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&path)
+        .map_err(|e| Error::io(&path, e))?;
+    // SAFETY: flock(2) on a descriptor we own; the lock is released on close.
+    if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX) } != 0 {
+        return Err(Error::io(&path, std::io::Error::last_os_error()));
+    }
+    Ok(file)
+}
+
+/// Remove every scratch worktree under `dir` and every scratch branch, e.g.
+/// after an interrupted run.
+pub fn clean(dir: &Path) -> Result<usize> {
+    let mut removed = 0;
+    let branches = git::run(&[
+        "for-each-ref",
+        "--format=%(refname:short)",
+        &format!("refs/heads/{BRANCH_PREFIX}"),
+    ])?;
+    for branch in branches.lines() {
+        let name = branch.trim_start_matches(BRANCH_PREFIX);
+        Worktree::named(dir, name).remove()?;
+        removed += 1;
+    }
+    git::run(&["worktree", "prune"])?;
+    Ok(removed)
+}
+
+/// Whether an iteration's work may land on the working branch.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Verdict {
+    /// Tasks the iteration moved to `[x]`.
+    pub completed: Vec<String>,
+    /// Why the iteration is discarded; empty when it may be merged.
+    pub reasons: Vec<String>,
+}
+
+impl Verdict {
+    pub fn judge(
+        summary: &IterationSummary,
+        transitions: &[Transition],
+        require_tests: bool,
+    ) -> Verdict {
+        let completed: Vec<String> = transitions
+            .iter()
+            .filter(|t| t.to == Some(Status::Done) && t.from != Some(Status::Done))
+            .map(|t| {
+                if t.task == t.title {
+                    t.title.clone()
+                } else {
+                    format!("{} {}", t.task, t.title)
+                }
+            })
+            .collect();
+        let mut reasons = test_reasons(summary, require_tests);
+        if completed.is_empty() {
+            reasons.push("no plan task moved to [x]".to_string());
+        }
+        Verdict { completed, reasons }
+    }
+
+    /// Judge an iteration the loop assigned `task` to. Unlike [`Verdict::judge`]
+    /// no plan task has to move to `[x]`: the loop marks that task itself
+    /// afterwards, so the branch lands when the iteration left any work
+    /// (`worked`) and its tests passed, whether or not it ticked the checkbox.
+    pub fn judge_assigned(
+        summary: &IterationSummary,
+        task: &str,
+        worked: bool,
+        require_tests: bool,
+    ) -> Verdict {
+        let mut reasons = test_reasons(summary, require_tests);
+        if !worked {
+            reasons.push("nothing was committed".to_string());
+        }
+        Verdict {
+            completed: vec![task.to_string()],
+            reasons,
+        }
+    }
+
+    pub fn accepted(&self) -> bool {
+        self.reasons.is_empty()
+    }
+}
+
+fn test_reasons(summary: &IterationSummary, require_tests: bool) -> Vec<String> {
+    match (require_tests, summary.tests_passed()) {
+        (false, _) | (true, Some(true)) => Vec::new(),
+        (true, Some(false)) => vec!["last test run failed".to_string()],
+        (true, None) => vec!["no tests were run".to_string()],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::TestRun;
+
+    fn tested(passed: bool) -> IterationSummary {
+        IterationSummary {
+            tests: vec![TestRun {
+                command: "cargo test".to_string(),
+                passed,
+            }],
+            ..IterationSummary::default()
+        }
+    }
+
+    fn moved(task: &str, title: &str, from: Status, to: Status) -> Transition {
+        Transition {
+            task: task.to_string(),
+            title: title.to_string(),
+            from: Some(from),
+            to: Some(to),
+        }
+    }
+
+    #[test]
+    fn judge_needs_a_finished_task_and_passing_tests() {
+        let done = [moved("1.2", "Parse input", Status::Todo, Status::Done)];
+        let verdict = Verdict::judge(&tested(true), &done, true);
+        assert!(verdict.accepted());
+        assert_eq!(verdict.completed, ["1.2 Parse input"]);
+
+        let started = [moved("1.2", "Parse input", Status::Todo, Status::Doing)];
+        let verdict = Verdict::judge(&tested(true), &started, true);
+        assert_eq!(verdict.reasons, ["no plan task moved to [x]"]);
+
+        let verdict = Verdict::judge(&tested(false), &done, true);
+        assert_eq!(verdict.reasons, ["last test run failed"]);
+
+        let untested = IterationSummary::default();
+        let verdict = Verdict::judge(&untested, &done, true);
+        assert_eq!(verdict.reasons, ["no tests were run"]);
+        assert!(Verdict::judge(&untested, &done, false).accepted());
+    }
+
+    #[test]
+    fn assigned_tasks_land_on_any_work() {
+        assert!(Verdict::judge_assigned(&tested(true), "1.2", true, true).accepted());
+        let verdict = Verdict::judge_assigned(&tested(true), "1.2", false, true);
+        assert_eq!(verdict.reasons, ["nothing was committed"]);
+        let verdict = Verdict::judge_assigned(&tested(false), "1.2", true, true);
+        assert_eq!(verdict.reasons, ["last test run failed"]);
+    }
+}